@@ -2,23 +2,28 @@ mod theme;
 
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::{mpsc, Arc};
 use std::thread;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use anyhow::{anyhow, Context, Result};
 use chrono::{DateTime, Utc};
 use duckdb::{params, Connection};
 use eframe::egui::{self, Color32, ColorImage, IconData, Image, Key, RichText, Sense, TextEdit, TextWrapMode, TextureHandle, TextureOptions, Vec2};
 use egui_extras::{Column, TableBuilder};
+use flate2::read::MultiGzDecoder;
 use memmap2::Mmap;
+use notify::{EventKind, RecursiveMode, Watcher};
 use rayon::prelude::*;
 use regex::Regex;
 use rfd::FileDialog;
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use smooai_logger::LoggedError;
 use walkdir::WalkDir;
 
 mod keys {
@@ -33,8 +38,26 @@ mod keys {
     pub const NAME: &str = "name";
     pub const NAMESPACE: &str = "namespace";
     pub const SERVICE: &str = "service";
+    pub const ERROR_DETAILS: &str = "errorDetails";
 }
 
+const COLUMN_TRANSFORMS_KEY: &str = "column_transforms";
+const VIEWER_SETTINGS_KEY: &str = "viewer_settings";
+const SEARCH_INPUT_ID: &str = "filters-search-text";
+
+const LIVE_FILTER_DEBOUNCE: Duration = Duration::from_millis(250);
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Result rows beyond this are dropped from the SQL panel to keep rendering cheap.
+const SQL_MAX_ROWS: usize = 10_000;
+/// Rows prepared and appended to DuckDB per batch while indexing.
+const DUCKDB_INSERT_CHUNK: usize = 50_000;
+/// Levels kept by the "Errors only" toggle and dropped by "Hide trace/debug", matched
+/// case-insensitively against the whole level.
+const ERROR_LEVELS_PATTERN: &str = "^(error|fatal)$";
+const VERBOSE_LEVELS_PATTERN: &str = "^(trace|debug)$";
+const DEFAULT_SQL_QUERY: &str = "SELECT level, count(*) AS n FROM logs GROUP BY level ORDER BY n DESC";
+
 const APP_ICON_BYTES: &[u8] = include_bytes!("../assets/app-icon.png");
 const LOGO_BYTES: &[u8] = include_bytes!("../assets/smoo-logo.png");
 
@@ -90,11 +113,24 @@ fn header_label_for(key: &str) -> String {
         .unwrap_or_else(|| key.to_string())
 }
 
-type ParsedFile = (PathBuf, Vec<String>, Vec<Row>, BTreeSet<String>);
+/// Row count, filtered count and first/last filtered index: enough to notice the filtered set changed.
+type SetFingerprint = (usize, usize, Option<usize>, Option<usize>);
+/// Distinct values of a column with how many rows have each.
+type ValueCounts = Vec<(String, usize)>;
+
+/// Identifies a row across reindexing: its file and the line it starts on.
+type RowKey = (PathBuf, usize);
+
+type ParsedFile = (PathBuf, Vec<String>, Vec<Row>, BTreeSet<String>, Option<AppendCursor>, Option<String>);
 
 enum IndexEvent {
-    Progress { processed: usize, total: usize },
+    Progress {
+        processed: usize,
+        total: usize,
+    },
     Finished(Result<Catalog>),
+    /// The index was cancelled; the previous catalog stays loaded.
+    Cancelled,
 }
 
 enum WatchEvent {
@@ -102,10 +138,127 @@ enum WatchEvent {
     FileRemoved(PathBuf),
 }
 
+/// What to index: every `.smooai-logs` folder under `root` (when set) plus files opened
+/// explicitly, given as paths or glob patterns.
+#[derive(Debug, Clone, Default)]
+struct IndexSources {
+    root: Option<PathBuf>,
+    patterns: Vec<String>,
+}
+
+impl IndexSources {
+    fn log_dirs(&self) -> Vec<PathBuf> {
+        self.root.as_deref().map(find_smooai_log_dirs).unwrap_or_default()
+    }
+
+    fn explicit_files(&self) -> Vec<PathBuf> {
+        resolve_file_patterns(&self.patterns)
+    }
+
+    /// All files to index, sorted and without duplicates.
+    fn files(&self) -> Vec<PathBuf> {
+        let mut files: BTreeSet<PathBuf> = self.log_dirs().iter().flat_map(|dir| list_log_files(dir)).collect();
+        files.extend(self.explicit_files());
+        files.into_iter().collect()
+    }
+
+    fn describe(&self) -> String {
+        let opened = match self.patterns.len() {
+            0 => None,
+            1 => Some(self.patterns[0].clone()),
+            count => Some(format!("{count} opened paths")),
+        };
+        match (&self.root, opened) {
+            (Some(root), Some(opened)) => format!("{} + {opened}", root.display()),
+            (Some(root), None) => root.display().to_string(),
+            (None, Some(opened)) => opened,
+            (None, None) => "nothing".into(),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 struct FileEntry {
     path: PathBuf,
     sanitized_lines: Vec<String>,
+    /// Where to resume parsing when the file only grew; `None` forces a full reparse.
+    cursor: Option<AppendCursor>,
+}
+
+/// Parse state kept per file so appended bytes can be parsed without rereading the rest.
+/// The file's last row is always reparsed, because it may have been an incomplete block.
+#[derive(Debug, Clone, Copy)]
+struct AppendCursor {
+    len: u64,
+    fingerprint: u64,
+    resume_line: usize,
+    resume_offset: u64,
+}
+
+impl AppendCursor {
+    /// `lines` are relative to `base_offset`, while `rows` carry absolute line numbers
+    /// starting at `base_line`.
+    fn new(bytes: &[u8], lines: &[LineHeader], rows: &[Row], base_line: usize, base_offset: u64) -> Self {
+        let (resume_line, resume_offset) = rows
+            .last()
+            .and_then(|row| {
+                lines
+                    .get(row.line_start - base_line)
+                    .map(|header| (row.line_start, base_offset + header.offset))
+            })
+            .unwrap_or((base_line, base_offset));
+        Self {
+            len: bytes.len() as u64,
+            fingerprint: prefix_fingerprint(bytes, bytes.len()),
+            resume_line,
+            resume_offset,
+        }
+    }
+
+    /// Whether `bytes` is the content this cursor was taken from plus newly appended bytes.
+    fn can_resume(&self, bytes: &[u8]) -> bool {
+        (bytes.len() as u64) > self.len && prefix_fingerprint(bytes, self.len as usize) == self.fingerprint
+    }
+}
+
+/// Hashes the head and tail of `bytes[..len]`, enough to notice a truncated, rotated or
+/// rewritten file without hashing all of it.
+fn prefix_fingerprint(bytes: &[u8], len: usize) -> u64 {
+    use std::hash::{Hash, Hasher};
+    const WINDOW: usize = 4096;
+    let prefix = &bytes[..len.min(bytes.len())];
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    prefix.len().hash(&mut hasher);
+    prefix[..prefix.len().min(WINDOW)].hash(&mut hasher);
+    prefix[prefix.len().saturating_sub(WINDOW)..].hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Oldest first; rows with the same timestamp keep file and line order.
+fn row_order(left: &Row, right: &Row) -> std::cmp::Ordering {
+    left.ts
+        .cmp(&right.ts)
+        .then_with(|| left.file_id.cmp(&right.file_id))
+        .then_with(|| left.line_start.cmp(&right.line_start))
+}
+
+/// Merges `new_rows` into the already sorted `rows` and returns the first index that changed,
+/// or `None` when there was nothing to merge.
+fn merge_sorted_rows(rows: &mut Vec<Row>, mut new_rows: Vec<Row>) -> Option<usize> {
+    new_rows.sort_by(row_order);
+    let first = new_rows.first()?;
+    let start = rows.partition_point(|row| row_order(row, first).is_lt());
+    let tail = rows.split_off(start);
+    rows.reserve(tail.len() + new_rows.len());
+    let mut tail = tail.into_iter().peekable();
+    let mut new_rows = new_rows.into_iter().peekable();
+    while let (Some(old), Some(new)) = (tail.peek(), new_rows.peek()) {
+        let next = if row_order(old, new).is_le() { tail.next() } else { new_rows.next() };
+        rows.extend(next);
+    }
+    rows.extend(tail);
+    rows.extend(new_rows);
+    Some(start)
 }
 
 #[derive(Debug, Clone)]
@@ -124,22 +277,64 @@ struct Row {
     request_id: Option<String>,
     flat: BTreeMap<String, String>,
     raw_json: String,
+    /// Typed `errorDetails`, filled only by the strict parse path.
+    error_details: Vec<LoggedError>,
 }
 
 #[derive(Default, Clone)]
 struct Catalog {
     files: Vec<FileEntry>,
-    rows: Vec<Row>,
+    /// Shared with filter workers (see [`FilterJob`]); changing the rows while one still
+    /// runs copies them first.
+    rows: Arc<Vec<Row>>,
     columns: Vec<String>,
     duckdb_path: Option<PathBuf>,
+    /// DuckDB `row_id` of `rows[0]`. Evicting old rows raises it instead of renumbering the table.
+    duckdb_row_offset: usize,
+    warnings: Vec<IndexWarning>,
+}
+
+#[derive(Debug, Clone)]
+struct IndexWarning {
+    path: PathBuf,
+    message: String,
 }
 
 #[derive(Clone)]
-struct Extractor;
+struct Extractor {
+    /// Try the typed [`SmooLogLine`] shape before falling back to generic field lookup.
+    strict: bool,
+}
+
+/// The shape `smooai-logger` emits. Only used by the opt-in strict parse path; lines that
+/// don't deserialize into it are handled generically.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SmooLogLine {
+    time: String,
+    #[serde(rename = "LogLevel")]
+    log_level: String,
+    msg: Option<String>,
+    name: Option<String>,
+    correlation_id: Option<String>,
+    namespace: Option<String>,
+    service: Option<String>,
+    trace_id: Option<String>,
+    request_id: Option<String>,
+    #[serde(default)]
+    error_details: Vec<LoggedError>,
+}
 
 impl Extractor {
-    fn new() -> Self {
-        Self
+    fn new(strict: bool) -> Self {
+        Self { strict }
+    }
+
+    fn parse_strict(&self, obj: &Value) -> Option<SmooLogLine> {
+        if !self.strict {
+            return None;
+        }
+        SmooLogLine::deserialize(obj).ok()
     }
 
     #[cfg(feature = "simd")]
@@ -161,7 +356,13 @@ impl Extractor {
     }
 
     fn pick_ts(&self, obj: &Value) -> Option<DateTime<Utc>> {
-        let raw = self.pick_str(obj, keys::TIME)?;
+        match obj.get(keys::TIME)? {
+            Value::Number(epoch) => self.parse_ts(&epoch.as_i64()?.to_string()),
+            value => self.parse_ts(value.as_str()?),
+        }
+    }
+
+    fn parse_ts(&self, raw: &str) -> Option<DateTime<Utc>> {
         if let Ok(dt) = raw.parse::<DateTime<Utc>>() {
             return Some(dt);
         }
@@ -207,9 +408,46 @@ impl Extractor {
         let request_id = self.pick_str(obj, keys::REQUEST_ID).map(|s| s.to_string());
         (ts, level, corr, name, msg, service, namespace, trace_id, request_id)
     }
+
+    /// Same fields as [`Extractor::extract`], plus the typed `errorDetails`, from a line
+    /// that matched the strict shape.
+    #[allow(clippy::type_complexity)]
+    fn extract_typed(
+        &self,
+        line: SmooLogLine,
+    ) -> (
+        (
+            Option<DateTime<Utc>>,
+            Option<String>,
+            Option<String>,
+            Option<String>,
+            Option<String>,
+            Option<String>,
+            Option<String>,
+            Option<String>,
+            Option<String>,
+        ),
+        Vec<LoggedError>,
+    ) {
+        (
+            (
+                self.parse_ts(&line.time),
+                Some(line.log_level),
+                line.correlation_id,
+                line.name,
+                line.msg,
+                line.service,
+                line.namespace,
+                line.trace_id,
+                line.request_id,
+            ),
+            line.error_details,
+        )
+    }
 }
 
-#[derive(Default, Clone)]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 struct Filters {
     text: String,
     level: String,
@@ -218,7 +456,506 @@ struct Filters {
     namespace: String,
     trace: String,
     request: String,
+    /// `path op value` expression over the flattened fields, see [`PathFilter`].
+    expr: String,
     regex_mode: bool,
+    /// Match `text` as a fuzzy subsequence and rank rows best match first. Mutually
+    /// exclusive with `regex_mode`.
+    fuzzy: bool,
+    /// Keep only error and fatal rows, on top of the other filters.
+    errors_only: bool,
+    /// Drop trace and debug rows, on top of the other filters.
+    hide_verbose: bool,
+}
+
+/// The column filters of [`Filters`] prepared for matching rows in memory, when DuckDB is
+/// disabled or its query fails. Regexes are only set in regex mode.
+struct MemoryFilter {
+    filters: Filters,
+    re_text: Option<Regex>,
+    re_level: Option<Regex>,
+    re_corr: Option<Regex>,
+    re_service: Option<Regex>,
+    re_namespace: Option<Regex>,
+    re_trace: Option<Regex>,
+    re_request: Option<Regex>,
+    re_errors: Option<Regex>,
+    re_verbose: Option<Regex>,
+    lowered: LoweredFilters,
+}
+
+/// Lowercased filter values for case-insensitive substring matching.
+struct LoweredFilters {
+    text: String,
+    level: String,
+    corr: String,
+    service: String,
+    namespace: String,
+    trace: String,
+    request: String,
+}
+
+impl MemoryFilter {
+    /// Whether `row` passes every column filter and the text search. The expression filter
+    /// and fuzzy ranking are applied separately.
+    fn matches(&self, row: &Row) -> bool {
+        if let Some(re) = &self.re_errors {
+            if !row.level.as_ref().is_some_and(|value| re.is_match(value)) {
+                return false;
+            }
+        }
+        if let Some(re) = &self.re_verbose {
+            if row.level.as_ref().is_some_and(|value| re.is_match(value)) {
+                return false;
+            }
+        }
+
+        if !self.filters.level.is_empty() {
+            let matches = row.level.as_ref().is_some_and(|value| {
+                if let Some(re) = &self.re_level {
+                    re.is_match(value)
+                } else {
+                    value.to_ascii_lowercase().contains(&self.lowered.level)
+                }
+            });
+            if !matches {
+                return false;
+            }
+        }
+
+        if !self.filters.corr.is_empty() {
+            let matches = row.corr.as_ref().is_some_and(|value| {
+                if let Some(re) = &self.re_corr {
+                    re.is_match(value)
+                } else {
+                    value.to_ascii_lowercase().contains(&self.lowered.corr)
+                }
+            });
+            if !matches {
+                return false;
+            }
+        }
+
+        if !self.filters.service.is_empty() {
+            let matches = row.service.as_ref().is_some_and(|value| {
+                if let Some(re) = &self.re_service {
+                    re.is_match(value)
+                } else {
+                    value.to_ascii_lowercase().contains(&self.lowered.service)
+                }
+            });
+            if !matches {
+                return false;
+            }
+        }
+
+        if !self.filters.namespace.is_empty() {
+            let matches = row.namespace.as_ref().is_some_and(|value| {
+                if let Some(re) = &self.re_namespace {
+                    re.is_match(value)
+                } else {
+                    value.to_ascii_lowercase().contains(&self.lowered.namespace)
+                }
+            });
+            if !matches {
+                return false;
+            }
+        }
+
+        if !self.filters.trace.is_empty() {
+            let matches = row.trace_id.as_ref().is_some_and(|value| {
+                if let Some(re) = &self.re_trace {
+                    re.is_match(value)
+                } else {
+                    value.to_ascii_lowercase().contains(&self.lowered.trace)
+                }
+            });
+            if !matches {
+                return false;
+            }
+        }
+
+        if !self.filters.request.is_empty() {
+            let matches = row.request_id.as_ref().is_some_and(|value| {
+                if let Some(re) = &self.re_request {
+                    re.is_match(value)
+                } else {
+                    value.to_ascii_lowercase().contains(&self.lowered.request)
+                }
+            });
+            if !matches {
+                return false;
+            }
+        }
+
+        if !self.filters.text.is_empty() && !self.filters.fuzzy {
+            let haystack = row_haystack(row);
+            let matches = if let Some(re) = &self.re_text {
+                re.is_match(&haystack)
+            } else {
+                haystack.to_ascii_lowercase().contains(&self.lowered.text)
+            };
+            if !matches {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// A snapshot of everything one filter run needs, so it can run on a worker thread while
+/// the UI keeps drawing.
+struct FilterJob {
+    rows: Arc<Vec<Row>>,
+    conn: Option<Connection>,
+    row_offset: usize,
+    memory: MemoryFilter,
+    path_filter: std::result::Result<Option<PathFilter>, String>,
+    sort_desc: bool,
+    sort_column: Option<String>,
+    sort_ascending: bool,
+}
+
+/// The rows a [`FilterJob`] kept, in display order, and the status line describing them.
+struct FilterOutcome {
+    filtered: Vec<usize>,
+    status: String,
+}
+
+impl FilterJob {
+    fn run(self) -> FilterOutcome {
+        let rows = &self.rows;
+        let filters = &self.memory.filters;
+        let queried = self
+            .conn
+            .as_ref()
+            .and_then(|conn| App::duckdb_filter_query(conn, self.row_offset, filters, self.sort_desc));
+        let mut filtered = queried.unwrap_or_else(|| {
+            let mut filtered: Vec<usize> = (0..rows.len()).into_par_iter().filter(|&idx| self.memory.matches(&rows[idx])).collect();
+            // Rows are kept oldest first; newest-first display reverses them.
+            if self.sort_desc {
+                filtered.reverse();
+            }
+            filtered
+        });
+        if let Ok(Some(path_filter)) = &self.path_filter {
+            filtered.retain(|&idx| path_filter.matches(&rows[idx]));
+        }
+        if let Some(column) = &self.sort_column {
+            sort_rows_by_column(rows, &mut filtered, column, self.sort_ascending);
+        }
+        let query = filters.text.trim().to_lowercase();
+        if filters.fuzzy && !query.is_empty() {
+            filtered = rank_fuzzy(rows, &filtered, &query);
+        }
+        let status = match &self.path_filter {
+            Err(error) => format!("Filter expression ignored: {error}"),
+            Ok(_) => format!("{} matches", filtered.len()),
+        };
+        FilterOutcome { filtered, status }
+    }
+}
+
+/// Orders `filtered` by `column`, keeping the current order among equal values.
+fn sort_rows_by_column(rows: &[Row], filtered: &mut [usize], column: &str, ascending: bool) {
+    filtered.sort_by(|a, b| {
+        let ordering = compare_rows_by_column(&rows[*a], &rows[*b], column);
+        if ascending {
+            ordering
+        } else {
+            ordering.reverse()
+        }
+    });
+}
+
+/// Keeps the rows of `filtered` that fuzzily match `query`, best match first.
+fn rank_fuzzy(rows: &[Row], filtered: &[usize], query: &str) -> Vec<usize> {
+    let mut scored: Vec<(i64, usize)> = filtered
+        .par_iter()
+        .filter_map(|&idx| fuzzy_score(query, &row_haystack(&rows[idx])).map(|score| (score, idx)))
+        .collect();
+    scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    scored.into_iter().map(|(_, idx)| idx).collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PathOp {
+    Eq,
+    Ne,
+    Ge,
+    Le,
+    Gt,
+    Lt,
+    Contains,
+    Exists,
+}
+
+/// A predicate such as `context.userId == "123"` or `http.response.statusCode >= 500`,
+/// matched against a row's flattened fields (dotted keys, `[n]` for array items).
+#[derive(Debug, Clone, PartialEq)]
+struct PathFilter {
+    path: String,
+    op: PathOp,
+    value: String,
+}
+
+impl PathFilter {
+    fn parse(input: &str) -> std::result::Result<Self, String> {
+        let input = input.trim();
+        let path_end = input.find(|c: char| c.is_whitespace() || "=!<>".contains(c)).unwrap_or(input.len());
+        let (path, rest) = input.split_at(path_end);
+        if path.is_empty() {
+            return Err("expected a field path, e.g. context.userId".into());
+        }
+        let rest = rest.trim_start();
+        if rest == "exists" {
+            return Ok(Self {
+                path: path.into(),
+                op: PathOp::Exists,
+                value: String::new(),
+            });
+        }
+        const OPS: [(&str, PathOp); 7] = [
+            ("==", PathOp::Eq),
+            ("!=", PathOp::Ne),
+            (">=", PathOp::Ge),
+            ("<=", PathOp::Le),
+            (">", PathOp::Gt),
+            ("<", PathOp::Lt),
+            ("contains", PathOp::Contains),
+        ];
+        let Some((op, value)) = OPS.iter().find_map(|(token, op)| rest.strip_prefix(token).map(|value| (*op, value.trim()))) else {
+            return Err(format!("expected ==, !=, >=, <=, >, <, contains or exists after `{path}`"));
+        };
+        // Double-quoted values may escape `"` and `\` with a backslash, see [`PathFilter::quote`].
+        let value = match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+            Some(quoted) => unescape_quoted(quoted),
+            None => value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')).unwrap_or(value).to_string(),
+        };
+        if value.is_empty() {
+            return Err(format!("missing value after `{path}`"));
+        }
+        Ok(Self { path: path.into(), op, value })
+    }
+
+    /// `value` as a double-quoted literal that [`PathFilter::parse`] reads back unchanged.
+    fn quote(value: &str) -> String {
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+
+    fn matches(&self, row: &Row) -> bool {
+        let Some(actual) = row.flat.get(&self.path) else {
+            return match self.op {
+                // Objects and arrays are only present through their leaves.
+                PathOp::Exists => {
+                    let object_prefix = format!("{}.", self.path);
+                    let array_prefix = format!("{}[", self.path);
+                    row.flat.keys().any(|key| key.starts_with(&object_prefix) || key.starts_with(&array_prefix))
+                }
+                PathOp::Ne => true,
+                _ => false,
+            };
+        };
+        let ordering = match (actual.parse::<f64>(), self.value.parse::<f64>()) {
+            (Ok(left), Ok(right)) => left.partial_cmp(&right),
+            _ => Some(actual.as_str().cmp(self.value.as_str())),
+        };
+        match self.op {
+            PathOp::Eq => ordering == Some(std::cmp::Ordering::Equal),
+            PathOp::Ne => ordering != Some(std::cmp::Ordering::Equal),
+            PathOp::Ge => ordering.is_some_and(|o| o.is_ge()),
+            PathOp::Le => ordering.is_some_and(|o| o.is_le()),
+            PathOp::Gt => ordering.is_some_and(|o| o.is_gt()),
+            PathOp::Lt => ordering.is_some_and(|o| o.is_lt()),
+            PathOp::Contains => actual.to_ascii_lowercase().contains(&self.value.to_ascii_lowercase()),
+            PathOp::Exists => true,
+        }
+    }
+}
+
+/// Drops the backslash before an escaped character; a trailing lone backslash is kept.
+fn unescape_quoted(quoted: &str) -> String {
+    let mut value = String::with_capacity(quoted.len());
+    let mut chars = quoted.chars();
+    while let Some(ch) = chars.next() {
+        value.push(if ch == '\\' { chars.next().unwrap_or('\\') } else { ch });
+    }
+    value
+}
+
+/// Display-only conversion applied to a column's values. Filtering and sorting keep using the raw values.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum ColumnTransform {
+    EpochTimestamp,
+    Bytes,
+    DurationMs,
+    Format(String),
+}
+
+impl ColumnTransform {
+    const PRESETS: [ColumnTransform; 3] = [ColumnTransform::EpochTimestamp, ColumnTransform::Bytes, ColumnTransform::DurationMs];
+
+    fn label(&self) -> &'static str {
+        match self {
+            ColumnTransform::EpochTimestamp => "Epoch → timestamp",
+            ColumnTransform::Bytes => "Bytes → KB/MB",
+            ColumnTransform::DurationMs => "Milliseconds → duration",
+            ColumnTransform::Format(_) => "Custom format",
+        }
+    }
+
+    /// Returns the transformed value, or the raw value unchanged when it doesn't parse.
+    fn apply(&self, raw: &str) -> String {
+        let number = raw.trim().parse::<f64>().ok().filter(|value| value.is_finite());
+        match (self, number) {
+            (ColumnTransform::EpochTimestamp, Some(value)) => {
+                // Values past ~1973 in milliseconds are far larger than any plausible epoch in seconds.
+                let millis = if value.abs() > 100_000_000_000.0 { value } else { value * 1_000.0 };
+                DateTime::<Utc>::from_timestamp_millis(millis as i64)
+                    .map(|dt| dt.to_rfc3339_opts(chrono::SecondsFormat::Millis, true))
+                    .unwrap_or_else(|| raw.to_string())
+            }
+            (ColumnTransform::Bytes, Some(value)) => format_bytes(value),
+            (ColumnTransform::DurationMs, Some(value)) => format_duration_ms(value),
+            (ColumnTransform::Format(template), _) => template.replace("{}", raw),
+            _ => raw.to_string(),
+        }
+    }
+}
+
+fn format_bytes(bytes: f64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes;
+    let mut unit = 0;
+    while value.abs() >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{value} {}", UNITS[unit])
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+fn format_duration_ms(ms: f64) -> String {
+    if ms.abs() < 1_000.0 {
+        format!("{ms} ms")
+    } else if ms.abs() < 60_000.0 {
+        format!("{:.2} s", ms / 1_000.0)
+    } else if ms.abs() < 3_600_000.0 {
+        format!("{:.0}m {:.0}s", (ms / 60_000.0).trunc(), (ms % 60_000.0) / 1_000.0)
+    } else {
+        format!("{:.0}h {:.0}m", (ms / 3_600_000.0).trunc(), (ms % 3_600_000.0) / 60_000.0)
+    }
+}
+
+/// `12340` as `12,340`.
+fn format_count(count: usize) -> String {
+    let digits = count.to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(digit);
+    }
+    out
+}
+
+/// A row's JSON in its own window, for reading large payloads or comparing rows side by side.
+struct JsonWindow {
+    id: u64,
+    /// The row shown; `None` follows the selection.
+    pinned: Option<RowKey>,
+    /// Catalog index `pinned` resolved to last time, checked before searching the rows again.
+    row_hint: Option<usize>,
+    /// The row currently shown and its parsed JSON.
+    shown: Option<(RowKey, Value)>,
+}
+
+struct SqlResult {
+    columns: Vec<String>,
+    rows: Vec<Vec<String>>,
+    truncated: bool,
+    elapsed: Duration,
+}
+
+/// Settings restored on the next launch from eframe's storage.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct ViewerSettings {
+    root: Option<PathBuf>,
+    dark_mode: bool,
+    live_mode: bool,
+    page_size: usize,
+    visible_columns: Vec<String>,
+    column_widths: HashMap<String, f32>,
+    strict_parse: bool,
+    filter_presets: BTreeMap<String, Filters>,
+    compact_rows: bool,
+}
+
+impl Default for ViewerSettings {
+    fn default() -> Self {
+        Self {
+            root: None,
+            dark_mode: true,
+            live_mode: true,
+            page_size: 200,
+            visible_columns: vec!["traceId".into(), "requestId".into()],
+            column_widths: default_column_widths(),
+            strict_parse: false,
+            filter_presets: BTreeMap::new(),
+            compact_rows: false,
+        }
+    }
+}
+
+/// The free-text search, used to highlight matches the same way `apply_filters` finds them:
+/// the compiled regex in regex mode, otherwise a case-insensitive substring.
+struct SearchHighlight {
+    needle: String,
+    regex: Option<Regex>,
+}
+
+impl SearchHighlight {
+    /// Non-overlapping byte ranges of the matches in `text`.
+    fn ranges(&self, text: &str) -> Vec<std::ops::Range<usize>> {
+        match &self.regex {
+            Some(regex) => regex.find_iter(text).filter(|m| !m.is_empty()).map(|m| m.range()).collect(),
+            // ASCII lowercasing keeps byte offsets, so ranges map straight back onto `text`.
+            None => text
+                .to_ascii_lowercase()
+                .match_indices(&self.needle)
+                .map(|(start, found)| start..start + found.len())
+                .collect(),
+        }
+    }
+
+    /// Lays out `text` with matches on a highlighted background, or `None` when nothing matches.
+    fn layout(&self, text: &str, font_id: egui::FontId, color: Color32, background: Color32) -> Option<egui::text::LayoutJob> {
+        let ranges = self.ranges(text);
+        if ranges.is_empty() {
+            return None;
+        }
+        let plain = egui::TextFormat::simple(font_id, color);
+        let marked = egui::TextFormat { background, ..plain.clone() };
+        let mut job = egui::text::LayoutJob::default();
+        let mut cursor = 0;
+        for range in ranges {
+            job.append(&text[cursor..range.start], 0.0, plain.clone());
+            job.append(&text[range.clone()], 0.0, marked.clone());
+            cursor = range.end;
+        }
+        job.append(&text[cursor..], 0.0, plain);
+        Some(job)
+    }
+}
+
+enum ExpandedJson {
+    Tree(Value),
+    Text(String),
 }
 
 enum ColumnAddResult {
@@ -241,6 +978,9 @@ struct App {
     ctx_after: usize,
     status: String,
     sort_desc: bool,
+    /// Column chosen by clicking a table header; `None` keeps the timestamp order.
+    sort_column: Option<String>,
+    sort_ascending: bool,
     filters: Filters,
     re_cache: HashMap<String, Regex>,
     dark_mode: bool,
@@ -250,6 +990,14 @@ struct App {
     index_rx: Option<mpsc::Receiver<IndexEvent>>,
     indexing: bool,
     show_startup_modal: bool,
+    /// `file:line` to jump to once the running index or filter finishes.
+    pending_goto: Option<(PathBuf, usize)>,
+    goto_input: String,
+    /// Scan `root` for `.smooai-logs` folders. Off when only explicitly opened files are viewed.
+    index_dirs: bool,
+    /// Files and glob patterns opened with "Open file(s)…", indexed alongside `root`.
+    file_patterns: Vec<String>,
+    pattern_input: String,
     watch_rx: Option<mpsc::Receiver<WatchEvent>>,
     watch_handle: Option<thread::JoinHandle<()>>,
     watch_stop: Option<Arc<AtomicBool>>,
@@ -258,10 +1006,65 @@ struct App {
     pending_watch_events: Vec<WatchEvent>,
     visible_columns: Vec<String>,
     column_search: String,
-    expanded_rows: HashSet<usize>,
+    /// Expanded rows by identity, so expansion survives sorting, filtering and reindexing.
+    expanded_rows: HashSet<RowKey>,
     column_widths: HashMap<String, f32>,
     index_progress: Option<(usize, usize)>,
+    /// Set to stop the running index early.
+    index_cancel: Option<Arc<AtomicBool>>,
     db_conn: Option<Connection>,
+    inline_json_depth: usize,
+    /// Row whose context stays visible while browsing, by identity so the pin survives live
+    /// updates and reindexing.
+    pinned_row: Option<RowKey>,
+    /// Catalog index `pinned_row` resolved to last time, checked before searching the rows again.
+    pinned_row_hint: Option<usize>,
+    json_windows: Vec<JsonWindow>,
+    next_json_window: u64,
+    live_filter: bool,
+    max_rows: usize,
+    /// Rows parsed from appended bytes, merged into `catalog.rows` by the next sync.
+    pending_rows: Vec<Row>,
+    /// First index of `catalog.rows` dropped since the last sync; DuckDB is rewritten from there.
+    rows_changed_from: Option<usize>,
+    /// Set when a file was reparsed or removed, so the next sync rebuilds everything.
+    full_resync: bool,
+    show_index_warnings: bool,
+    column_transforms: HashMap<String, ColumnTransform>,
+    transform_format_input: String,
+    filter_edited_at: Option<Instant>,
+    filter_rx: Option<mpsc::Receiver<FilterOutcome>>,
+    /// Shown instead of the match count when the running filter finishes, for the change that started it.
+    filter_status: Option<String>,
+    /// Wakes the UI when a filter worker finishes.
+    egui_ctx: Option<egui::Context>,
+    hovered_cell_value: Option<String>,
+    /// Set by keyboard navigation so the table scrolls the selected row into view once.
+    scroll_to_selected: bool,
+    /// Keep the newest row in view after live updates. Scrolling the table pauses it.
+    follow_tail: bool,
+    scroll_to_tail: bool,
+    /// Per-level counts of `filtered`, tagged with a cheap fingerprint of the set they were computed from.
+    level_counts: Option<(SetFingerprint, ValueCounts)>,
+    strict_parse: bool,
+    /// Saved filter combinations, by name.
+    filter_presets: BTreeMap<String, Filters>,
+    preset_name: String,
+    show_sql_panel: bool,
+    show_aggregate_panel: bool,
+    /// Shorter rows and smaller text in the log table.
+    compact_rows: bool,
+    /// Column the aggregate panel groups `filtered` by.
+    aggregate_column: String,
+    /// Per-value counts for `aggregate_column`, most frequent first, with the set and column they were computed from.
+    aggregate_counts: Option<(SetFingerprint, String, ValueCounts)>,
+    /// `--keep-db`: leave DuckDB files on disk instead of deleting them on reindex and exit.
+    keep_db: bool,
+    /// Cleared by `--no-db`: filter in memory only and skip writing a DuckDB file.
+    use_duckdb: bool,
+    sql_query: String,
+    sql_result: Option<SqlResult>,
+    sql_rx: Option<mpsc::Receiver<std::result::Result<SqlResult, String>>>,
 }
 
 impl Default for App {
@@ -283,6 +1086,8 @@ impl Default for App {
             ctx_after: 2,
             status: "Choose a directory to index".into(),
             sort_desc: true,
+            sort_column: None,
+            sort_ascending: true,
             filters: Filters::default(),
             re_cache: HashMap::new(),
             dark_mode: true,
@@ -292,6 +1097,11 @@ impl Default for App {
             index_rx: None,
             indexing: false,
             show_startup_modal: true,
+            pending_goto: None,
+            goto_input: String::new(),
+            index_dirs: true,
+            file_patterns: Vec::new(),
+            pattern_input: String::new(),
             watch_rx: None,
             watch_handle: None,
             watch_stop: None,
@@ -303,14 +1113,168 @@ impl Default for App {
             expanded_rows: HashSet::new(),
             column_widths: default_column_widths(),
             index_progress: None,
+            index_cancel: None,
             db_conn: None,
+            inline_json_depth: 2,
+            pinned_row: None,
+            pinned_row_hint: None,
+            json_windows: Vec::new(),
+            next_json_window: 0,
+            live_filter: true,
+            max_rows: 1_000_000,
+            pending_rows: Vec::new(),
+            rows_changed_from: None,
+            full_resync: false,
+            show_index_warnings: false,
+            column_transforms: HashMap::new(),
+            transform_format_input: "{}".into(),
+            filter_edited_at: None,
+            filter_rx: None,
+            filter_status: None,
+            egui_ctx: None,
+            hovered_cell_value: None,
+            scroll_to_selected: false,
+            follow_tail: false,
+            scroll_to_tail: false,
+            level_counts: None,
+            strict_parse: false,
+            filter_presets: BTreeMap::new(),
+            preset_name: String::new(),
+            show_sql_panel: false,
+            show_aggregate_panel: false,
+            compact_rows: false,
+            aggregate_column: "service".into(),
+            aggregate_counts: None,
+            keep_db: false,
+            use_duckdb: true,
+            sql_query: DEFAULT_SQL_QUERY.into(),
+            sql_result: None,
+            sql_rx: None,
         }
     }
 }
 
 impl App {
-    fn start_index(&mut self, path: PathBuf, ctx: &egui::Context) {
-        self.status = format!("Indexing {}…", path.display());
+    fn new(cc: &eframe::CreationContext<'_>) -> Self {
+        let mut app = Self::default();
+        app.egui_ctx = Some(cc.egui_ctx.clone());
+        if let Some(storage) = cc.storage {
+            app.column_transforms = eframe::get_value(storage, COLUMN_TRANSFORMS_KEY).unwrap_or_default();
+            if let Some(settings) = eframe::get_value::<ViewerSettings>(storage, VIEWER_SETTINGS_KEY) {
+                app.apply_settings(settings);
+            }
+        }
+        // Paths or globs on the command line open just those files, skipping the directory prompt.
+        let mut flags = Vec::new();
+        let mut patterns = Vec::new();
+        let mut goto = None;
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            if arg == "--goto" {
+                goto = args.next();
+            } else if let Some(target) = arg.strip_prefix("--goto=") {
+                goto = Some(target.to_string());
+            } else if arg.starts_with("--") {
+                flags.push(arg);
+            } else {
+                patterns.push(arg);
+            }
+        }
+        app.keep_db = flags.iter().any(|flag| flag == "--keep-db");
+        app.use_duckdb = !flags.iter().any(|flag| flag == "--no-db");
+        // `--goto <file>:<line>` jumps to that line once indexed, opening just the file
+        // when no other files were given.
+        match goto.as_deref().map(parse_permalink) {
+            Some(Some((path, line))) => {
+                if patterns.is_empty() {
+                    patterns.push(path.display().to_string());
+                }
+                // Relative to where the viewer was started, not to the restored root.
+                let path = std::path::absolute(&path).unwrap_or(path);
+                app.pending_goto = Some((path, line));
+            }
+            Some(None) => eprintln!("Ignoring --goto: expected <file>:<line>"),
+            None => {}
+        }
+        if !patterns.is_empty() {
+            app.file_patterns = patterns;
+            app.index_dirs = false;
+            app.show_startup_modal = false;
+            app.pending_reindex = true;
+            if app.live_mode {
+                app.watch_sources();
+            }
+        }
+        app
+    }
+
+    fn apply_settings(&mut self, settings: ViewerSettings) {
+        // A root that was moved or deleted falls back to the current directory; the
+        // startup modal is still shown so another one can be picked.
+        if let Some(root) = settings.root.filter(|root| root.is_dir()) {
+            self.root = root.clone();
+            self.pending_root = root;
+        }
+        self.dark_mode = settings.dark_mode;
+        self.live_mode = settings.live_mode;
+        self.page_size = settings.page_size.clamp(50, 3000);
+        self.visible_columns = settings.visible_columns;
+        self.column_widths.extend(settings.column_widths);
+        self.strict_parse = settings.strict_parse;
+        self.filter_presets = settings.filter_presets;
+        self.compact_rows = settings.compact_rows;
+    }
+
+    fn settings(&self) -> ViewerSettings {
+        ViewerSettings {
+            root: Some(self.root.clone()),
+            dark_mode: self.dark_mode,
+            live_mode: self.live_mode,
+            page_size: self.page_size,
+            visible_columns: self.visible_columns.clone(),
+            column_widths: self.column_widths.clone(),
+            strict_parse: self.strict_parse,
+            filter_presets: self.filter_presets.clone(),
+            compact_rows: self.compact_rows,
+        }
+    }
+
+    fn index_sources(&self) -> IndexSources {
+        IndexSources {
+            root: self.index_dirs.then(|| self.root.clone()),
+            patterns: self.file_patterns.clone(),
+        }
+    }
+
+    /// Restarts the watcher (when live) and reindexes after the index sources changed.
+    fn reload_sources(&mut self, ctx: &egui::Context) {
+        if self.live_mode {
+            self.watch_sources();
+        } else {
+            self.stop_watch();
+        }
+        self.start_index(ctx);
+    }
+
+    /// Adds the paths or glob patterns to the opened files and reindexes.
+    fn open_file_patterns(&mut self, patterns: Vec<String>, ctx: &egui::Context) {
+        for pattern in patterns {
+            let pattern = pattern.trim().to_string();
+            if pattern.is_empty() || self.file_patterns.contains(&pattern) {
+                continue;
+            }
+            if let Err(error) = glob::Pattern::new(&pattern) {
+                self.status = format!("Invalid glob `{pattern}`: {error}");
+                return;
+            }
+            self.file_patterns.push(pattern);
+        }
+        self.reload_sources(ctx);
+    }
+
+    fn start_index(&mut self, ctx: &egui::Context) {
+        let sources = self.index_sources();
+        self.status = format!("Indexing {}…", sources.describe());
         self.index_progress = None;
         self.pending_watch_events.clear();
         let (tx, rx) = mpsc::channel();
@@ -318,31 +1282,200 @@ impl App {
         self.indexing = true;
         let ctx_clone = ctx.clone();
         let progress_sender = tx.clone();
+        let strict = self.strict_parse;
+        let use_duckdb = self.use_duckdb;
+        let cancel = Arc::new(AtomicBool::new(false));
+        self.index_cancel = Some(cancel.clone());
         thread::spawn(move || {
-            let result = index_monorepo(&path, Some(progress_sender));
-            let _ = tx.send(IndexEvent::Finished(result));
+            let event = match index_sources(&sources, strict, use_duckdb, Some(progress_sender), &cancel) {
+                Ok(None) => IndexEvent::Cancelled,
+                Ok(Some(catalog)) => IndexEvent::Finished(Ok(catalog)),
+                Err(error) => IndexEvent::Finished(Err(error)),
+            };
+            let _ = tx.send(event);
             ctx_clone.request_repaint();
         });
     }
 
+    /// Asks the running index to stop; the worker reports back with [`IndexEvent::Cancelled`].
+    fn cancel_index(&mut self) {
+        if let Some(cancel) = &self.index_cancel {
+            cancel.store(true, Ordering::SeqCst);
+            self.status = "Cancelling index…".into();
+        }
+    }
+
+    /// Filters, sorts and ranks the rows on a worker thread, publishing through `filter_rx`.
+    /// Starting another run discards the result of any still in flight.
     fn apply_filters(&mut self) {
-        // Try DuckDB-backed filtering first
-        if let Some(conn) = self.db_conn.take() {
-            let result = Self::duckdb_filter_query(&conn, &self.filters, self.sort_desc);
-            self.db_conn = Some(conn);
-            if let Some(filtered) = result {
-                self.filtered = filtered;
-                self.page = 0;
-                self.selected = None;
-                self.status = format!("{} matches", self.filtered.len());
-                return;
+        // An explicit apply supersedes any pending live filter
+        self.filter_edited_at = None;
+        self.filter_status = None;
+        let job = FilterJob {
+            rows: Arc::clone(&self.catalog.rows),
+            conn: self.db_conn.as_ref().and_then(|conn| conn.try_clone().ok()),
+            row_offset: self.catalog.duckdb_row_offset,
+            memory: self.memory_filter(),
+            path_filter: self.path_filter(),
+            sort_desc: self.sort_desc,
+            sort_column: self.sort_column.clone(),
+            sort_ascending: self.sort_ascending,
+        };
+        let (tx, rx) = mpsc::channel();
+        self.filter_rx = Some(rx);
+        self.status = "Filtering…".into();
+        let ctx = self.egui_ctx.clone();
+        thread::spawn(move || {
+            let _ = tx.send(job.run());
+            if let Some(ctx) = ctx {
+                ctx.request_repaint();
+            }
+        });
+    }
+
+    fn set_filtered(&mut self, outcome: FilterOutcome) {
+        self.filtered = outcome.filtered;
+        self.page = 0;
+        self.selected = None;
+        self.status = self.filter_status.take().unwrap_or(outcome.status);
+        if self.follow_tail {
+            self.jump_to_tail();
+        }
+        if !self.indexing {
+            if let Some((path, line)) = self.pending_goto.take() {
+                self.goto_line(&path, line);
             }
         }
-        // Fall back to in-memory filtering
-        self.apply_filters_memory();
     }
 
-    fn duckdb_filter_query(conn: &Connection, filters: &Filters, sort_desc: bool) -> Option<Vec<usize>> {
+    /// The parsed expression filter; an invalid expression is reported rather than matching nothing.
+    fn path_filter(&self) -> std::result::Result<Option<PathFilter>, String> {
+        let expr = self.filters.expr.trim();
+        if expr.is_empty() {
+            return Ok(None);
+        }
+        PathFilter::parse(expr).map(Some)
+    }
+
+    fn process_live_filter(&mut self, ctx: &egui::Context) {
+        if let Some(rx) = &self.filter_rx {
+            if let Ok(outcome) = rx.try_recv() {
+                self.filter_rx = None;
+                self.set_filtered(outcome);
+            }
+        }
+
+        let Some(edited_at) = self.filter_edited_at else {
+            return;
+        };
+        let elapsed = edited_at.elapsed();
+        if elapsed >= LIVE_FILTER_DEBOUNCE {
+            self.filter_edited_at = None;
+            self.apply_filters();
+        } else {
+            ctx.request_repaint_after(LIVE_FILTER_DEBOUNCE - elapsed);
+        }
+    }
+
+    /// Runs the SQL panel query on a worker thread against a clone of the open connection.
+    fn run_sql_query(&mut self, ctx: &egui::Context) {
+        let Some(conn) = self.db_conn.as_ref().and_then(|conn| conn.try_clone().ok()) else {
+            self.status = if self.use_duckdb {
+                "SQL: no DuckDB connection — index logs first".into()
+            } else {
+                "SQL: DuckDB is disabled by --no-db".into()
+            };
+            return;
+        };
+        let sql = self.sql_query.clone();
+        let (tx, rx) = mpsc::channel();
+        self.sql_rx = Some(rx);
+        self.status = "SQL: running…".into();
+        let ctx_clone = ctx.clone();
+        thread::spawn(move || {
+            let _ = tx.send(execute_sql(&conn, &sql));
+            ctx_clone.request_repaint();
+        });
+    }
+
+    fn process_sql_result(&mut self) {
+        let Some(rx) = &self.sql_rx else {
+            return;
+        };
+        let Ok(result) = rx.try_recv() else {
+            return;
+        };
+        self.sql_rx = None;
+        match result {
+            Ok(result) => {
+                self.status = format!(
+                    "SQL: {}{} rows in {} ms",
+                    result.rows.len(),
+                    if result.truncated { "+" } else { "" },
+                    result.elapsed.as_millis()
+                );
+                self.sql_result = Some(result);
+            }
+            Err(error) => self.status = format!("SQL error: {error}"),
+        }
+    }
+
+    fn render_sql_panel(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
+        ui.horizontal(|ui| {
+            ui.heading("SQL");
+            ui.label(RichText::new("Query the `logs` table").color(Color32::from_gray(150)));
+            if let Some(path) = &self.catalog.duckdb_path {
+                let path = path.display().to_string();
+                if ui.small_button("Copy DB path").on_hover_text(&path).clicked() {
+                    ui.output_mut(|o| o.copied_text = path);
+                }
+            }
+        });
+        let response = ui.add(
+            TextEdit::multiline(&mut self.sql_query)
+                .code_editor()
+                .desired_rows(3)
+                .desired_width(f32::INFINITY)
+                .hint_text("SELECT level, count(*) FROM logs GROUP BY level"),
+        );
+        let run_shortcut = response.has_focus() && ui.input(|i| i.modifiers.command && i.key_pressed(Key::Enter));
+        ui.horizontal(|ui| {
+            let running = self.sql_rx.is_some();
+            if ui.add_enabled(!running, egui::Button::new("Run")).on_hover_text("⌘/Ctrl + Enter").clicked() || (run_shortcut && !running) {
+                self.run_sql_query(ctx);
+            }
+            if running {
+                ui.spinner();
+            }
+            if self.sql_result.is_some() && ui.button("Clear").clicked() {
+                self.sql_result = None;
+            }
+        });
+
+        let Some(result) = &self.sql_result else {
+            return;
+        };
+        ui.separator();
+        egui::ScrollArea::both().id_source("sql_results").auto_shrink([false, false]).show(ui, |ui| {
+            egui::Grid::new("sql_results_grid").striped(true).show(ui, |ui| {
+                for column in &result.columns {
+                    ui.label(RichText::new(column).strong());
+                }
+                ui.end_row();
+                for row in &result.rows {
+                    for value in row {
+                        ui.monospace(shorten_for_display(value, 200));
+                    }
+                    ui.end_row();
+                }
+            });
+            if result.truncated {
+                ui.label(format!("Showing the first {SQL_MAX_ROWS} rows."));
+            }
+        });
+    }
+
+    fn duckdb_filter_query(conn: &Connection, row_offset: usize, filters: &Filters, sort_desc: bool) -> Option<Vec<usize>> {
         let escape = |s: &str| s.replace('\'', "''");
 
         let mut sql = String::from("SELECT row_id FROM logs");
@@ -367,8 +1500,15 @@ impl App {
         add_column_filter!(filters.namespace, "namespace");
         add_column_filter!(filters.trace, "trace_id");
         add_column_filter!(filters.request, "request_id");
+        if filters.errors_only {
+            conditions.push(format!("regexp_matches(level, '{ERROR_LEVELS_PATTERN}', 'i')"));
+        }
+        if filters.hide_verbose {
+            conditions.push(format!("(level IS NULL OR NOT regexp_matches(level, '{VERBOSE_LEVELS_PATTERN}', 'i'))"));
+        }
 
-        if !filters.text.is_empty() {
+        // Fuzzy matching and ranking happen in memory, see `rank_fuzzy`.
+        if !filters.text.is_empty() && !filters.fuzzy {
             let escaped = escape(&filters.text);
             let haystack = "COALESCE(msg,'') || ' ' || COALESCE(corr,'') || ' ' || COALESCE(level,'') || ' ' || COALESCE(service,'') || ' ' || COALESCE(namespace,'') || ' ' || COALESCE(trace_id,'') || ' ' || COALESCE(request_id,'') || ' ' || COALESCE(flat_json,'')";
             if filters.regex_mode {
@@ -391,166 +1531,72 @@ impl App {
 
         let mut stmt = conn.prepare(&sql).ok()?;
         let rows = stmt.query_map([], |row| row.get::<_, i64>(0)).ok()?;
-        let filtered: Vec<usize> = rows.filter_map(|r| r.ok()).map(|id| id as usize).collect();
+        let filtered: Vec<usize> = rows.filter_map(|r| r.ok()).filter_map(|id| (id as usize).checked_sub(row_offset)).collect();
         Some(filtered)
     }
 
-    fn apply_filters_memory(&mut self) {
+    /// Compiles the current filters for [`MemoryFilter::matches`], reusing cached regexes.
+    fn memory_filter(&mut self) -> MemoryFilter {
         let filters = self.filters.clone();
-
-        let re_text = if filters.regex_mode { self.compile(&filters.text) } else { None };
-        let re_level = if filters.regex_mode { self.compile(&filters.level) } else { None };
-        let re_corr = if filters.regex_mode { self.compile(&filters.corr) } else { None };
-        let re_service = if filters.regex_mode { self.compile(&filters.service) } else { None };
-        let re_namespace = if filters.regex_mode { self.compile(&filters.namespace) } else { None };
-        let re_trace = if filters.regex_mode { self.compile(&filters.trace) } else { None };
-        let re_request = if filters.regex_mode { self.compile(&filters.request) } else { None };
-
+        let regex_mode = filters.regex_mode;
+        let mut compile = |source: &str| if regex_mode { self.compile(source) } else { None };
+        let level_pattern = |pattern: &str| regex::RegexBuilder::new(pattern).case_insensitive(true).build().ok();
         let lowercase = |input: &str| input.to_ascii_lowercase();
-        let text = lowercase(&filters.text);
-        let level = lowercase(&filters.level);
-        let corr = lowercase(&filters.corr);
-        let service = lowercase(&filters.service);
-        let namespace = lowercase(&filters.namespace);
-        let trace = lowercase(&filters.trace);
-        let request = lowercase(&filters.request);
-
-        self.filtered.clear();
-
-        for (idx, row) in self.catalog.rows.iter().enumerate() {
-            if !filters.level.is_empty() {
-                let matches = row.level.as_ref().is_some_and(|value| {
-                    if let Some(re) = &re_level {
-                        re.is_match(value)
-                    } else {
-                        value.to_ascii_lowercase().contains(&level)
-                    }
-                });
-                if !matches {
-                    continue;
-                }
-            }
-
-            if !filters.corr.is_empty() {
-                let matches = row.corr.as_ref().is_some_and(|value| {
-                    if let Some(re) = &re_corr {
-                        re.is_match(value)
-                    } else {
-                        value.to_ascii_lowercase().contains(&corr)
-                    }
-                });
-                if !matches {
-                    continue;
-                }
-            }
+        MemoryFilter {
+            re_text: compile(&filters.text),
+            re_level: compile(&filters.level),
+            re_corr: compile(&filters.corr),
+            re_service: compile(&filters.service),
+            re_namespace: compile(&filters.namespace),
+            re_trace: compile(&filters.trace),
+            re_request: compile(&filters.request),
+            re_errors: filters.errors_only.then(|| level_pattern(ERROR_LEVELS_PATTERN)).flatten(),
+            re_verbose: filters.hide_verbose.then(|| level_pattern(VERBOSE_LEVELS_PATTERN)).flatten(),
+            lowered: LoweredFilters {
+                text: lowercase(&filters.text),
+                level: lowercase(&filters.level),
+                corr: lowercase(&filters.corr),
+                service: lowercase(&filters.service),
+                namespace: lowercase(&filters.namespace),
+                trace: lowercase(&filters.trace),
+                request: lowercase(&filters.request),
+            },
+            filters,
+        }
+    }
 
-            if !filters.service.is_empty() {
-                let matches = row.service.as_ref().is_some_and(|value| {
-                    if let Some(re) = &re_service {
-                        re.is_match(value)
-                    } else {
-                        value.to_ascii_lowercase().contains(&service)
-                    }
-                });
-                if !matches {
-                    continue;
-                }
+    fn render_filter_presets(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.add(TextEdit::singleline(&mut self.preset_name).hint_text("preset name").desired_width(120.0));
+            let name = self.preset_name.trim().to_string();
+            if ui.add_enabled(!name.is_empty(), egui::Button::new("Save preset")).clicked() {
+                self.filter_presets.insert(name.clone(), self.filters.clone());
+                self.status = format!("Saved filter preset \"{name}\"");
             }
-
-            if !filters.namespace.is_empty() {
-                let matches = row.namespace.as_ref().is_some_and(|value| {
-                    if let Some(re) = &re_namespace {
-                        re.is_match(value)
-                    } else {
-                        value.to_ascii_lowercase().contains(&namespace)
-                    }
-                });
-                if !matches {
-                    continue;
-                }
-            }
-
-            if !filters.trace.is_empty() {
-                let matches = row.trace_id.as_ref().is_some_and(|value| {
-                    if let Some(re) = &re_trace {
-                        re.is_match(value)
-                    } else {
-                        value.to_ascii_lowercase().contains(&trace)
-                    }
-                });
-                if !matches {
-                    continue;
-                }
+            if ui.add_enabled(self.filter_presets.contains_key(&name), egui::Button::new("Delete")).clicked() {
+                self.filter_presets.remove(&name);
+                self.status = format!("Deleted filter preset \"{name}\"");
             }
-
-            if !filters.request.is_empty() {
-                let matches = row.request_id.as_ref().is_some_and(|value| {
-                    if let Some(re) = &re_request {
-                        re.is_match(value)
-                    } else {
-                        value.to_ascii_lowercase().contains(&request)
+        });
+        if self.filter_presets.is_empty() {
+            return;
+        }
+        let mut chosen = None;
+        egui::ComboBox::from_id_source("filter-presets")
+            .selected_text("Apply preset…")
+            .show_ui(ui, |ui| {
+                for name in self.filter_presets.keys() {
+                    if ui.selectable_label(false, name).clicked() {
+                        chosen = Some(name.clone());
                     }
-                });
-                if !matches {
-                    continue;
                 }
-            }
-
-            if !filters.text.is_empty() {
-                let mut haystack = String::new();
-                if let Some(value) = row.msg.as_ref() {
-                    haystack.push_str(value);
-                    haystack.push(' ');
-                }
-                if let Some(value) = row.corr.as_ref() {
-                    haystack.push_str(value);
-                    haystack.push(' ');
-                }
-                if let Some(value) = row.level.as_ref() {
-                    haystack.push_str(value);
-                    haystack.push(' ');
-                }
-                if let Some(value) = row.service.as_ref() {
-                    haystack.push_str(value);
-                    haystack.push(' ');
-                }
-                if let Some(value) = row.namespace.as_ref() {
-                    haystack.push_str(value);
-                    haystack.push(' ');
-                }
-                if let Some(value) = row.trace_id.as_ref() {
-                    haystack.push_str(value);
-                    haystack.push(' ');
-                }
-                if let Some(value) = row.request_id.as_ref() {
-                    haystack.push_str(value);
-                    haystack.push(' ');
-                }
-                for value in row.flat.values() {
-                    haystack.push_str(value);
-                    haystack.push(' ');
-                }
-                let matches = if let Some(re) = &re_text {
-                    re.is_match(&haystack)
-                } else {
-                    haystack.to_ascii_lowercase().contains(&text)
-                };
-                if !matches {
-                    continue;
-                }
-            }
-
-            self.filtered.push(idx);
-        }
-
-        // catalog.rows is always in ASC order; reverse filtered indices for DESC display
-        if self.sort_desc {
-            self.filtered.reverse();
+            });
+        // Presets hold plain filter values, so one naming a service that is gone simply matches nothing.
+        if let Some(filters) = chosen.as_ref().and_then(|name| self.filter_presets.get(name)) {
+            self.filters = filters.clone();
+            self.preset_name = chosen.unwrap_or_default();
+            self.apply_filters();
         }
-
-        self.page = 0;
-        self.selected = None;
-        self.status = format!("{} matches", self.filtered.len());
     }
 
     fn compile(&mut self, source: &str) -> Option<Regex> {
@@ -567,6 +1613,27 @@ impl App {
             .ok()
     }
 
+    /// In fuzzy mode, keeps the rows whose text contains the search as a subsequence,
+    /// best match first. Equal scores keep their current order.
+    fn search_highlight(&mut self) -> Option<SearchHighlight> {
+        let text = self.filters.text.clone();
+        if text.is_empty() || self.filters.fuzzy {
+            return None;
+        }
+        if self.filters.regex_mode {
+            let regex = self.compile(&text)?;
+            Some(SearchHighlight {
+                needle: text,
+                regex: Some(regex),
+            })
+        } else {
+            Some(SearchHighlight {
+                needle: text.to_ascii_lowercase(),
+                regex: None,
+            })
+        }
+    }
+
     fn has_rows(&self) -> bool {
         !self.filtered.is_empty()
     }
@@ -657,7 +1724,7 @@ impl App {
             return;
         }
 
-        let extractor = Extractor::new();
+        let extractor = Extractor::new(self.strict_parse);
         let mut updated_files = 0usize;
         let mut removed_files = 0usize;
         let mut errors = Vec::new();
@@ -681,7 +1748,7 @@ impl App {
         }
 
         if updated_files > 0 || removed_files > 0 {
-            self.sync_after_catalog_changes();
+            let evicted = self.sync_after_catalog_changes();
             let mut parts = Vec::new();
             if updated_files > 0 {
                 parts.push(format!("updated {} file{}", updated_files, if updated_files == 1 { "" } else { "s" }));
@@ -689,31 +1756,94 @@ impl App {
             if removed_files > 0 {
                 parts.push(format!("removed {} file{}", removed_files, if removed_files == 1 { "" } else { "s" }));
             }
-            self.status = format!("Live update: {}", parts.join(", "));
+            if evicted > 0 {
+                parts.push(format!("evicted {} oldest row{}", evicted, if evicted == 1 { "" } else { "s" }));
+            }
+            self.filter_status = Some(format!("Live update: {}", parts.join(", ")));
             ctx.request_repaint();
         }
 
         if let Some((path, error)) = errors.first() {
-            self.status = format!("Live update error for {}: {:#}", path.display(), error);
+            let status = format!("Live update error for {}: {:#}", path.display(), error);
+            if self.filter_rx.is_some() {
+                self.filter_status = Some(status);
+            } else {
+                self.status = status;
+            }
+        }
+        if !errors.is_empty() {
+            for (path, error) in errors {
+                self.catalog.warnings.retain(|warning| warning.path != path);
+                self.catalog.warnings.push(IndexWarning {
+                    path,
+                    message: format!("{error:#}"),
+                });
+            }
+            self.show_index_warnings = true;
+        }
+    }
+
+    /// Moves to the page holding the newest row (first page when newest-first, otherwise the last)
+    /// and scrolls it into view on the next frame.
+    fn jump_to_tail(&mut self) {
+        self.page = if self.sort_desc {
+            0
+        } else {
+            self.filtered.len().saturating_sub(1) / self.page_size.max(1)
+        };
+        self.scroll_to_tail = true;
+    }
+
+    /// Re-reads one file from disk without reindexing the rest, for changes seen while live
+    /// mode is off.
+    fn reload_file(&mut self, file_id: usize) {
+        if self.indexing {
+            return;
+        }
+        let Some(path) = self.catalog.files.get(file_id).map(|file| file.path.clone()) else {
+            return;
+        };
+        let extractor = Extractor::new(self.strict_parse);
+        match self.refresh_file_from_disk(&path, &extractor) {
+            Ok(true) => {
+                self.catalog.warnings.retain(|warning| warning.path != path);
+                let evicted = self.sync_after_catalog_changes();
+                let mut status = format!("Reloaded {}: updated", path.display());
+                if evicted > 0 {
+                    status.push_str(&format!(", evicted {} oldest row{}", evicted, if evicted == 1 { "" } else { "s" }));
+                }
+                self.filter_status = Some(status);
+            }
+            Ok(false) => self.status = format!("Reloaded {}: unchanged", path.display()),
+            Err(error) => self.status = format!("Reload failed for {}: {error:#}", path.display()),
         }
     }
 
     fn refresh_file_from_disk(&mut self, path: &Path, extractor: &Extractor) -> Result<bool> {
         let existing_index = self.catalog.files.iter().position(|file| file.path == *path);
+        if let Some(idx) = existing_index {
+            if let Some(updated) = self.append_file_from_disk(idx, path, extractor)? {
+                return Ok(updated);
+            }
+        }
         let file_id = existing_index.unwrap_or(self.catalog.files.len());
 
-        let (sanitized_lines, mut rows) = index_single_file(file_id, path, extractor)?;
+        let (sanitized_lines, mut rows, cursor) = index_single_file(file_id, path, extractor)?;
         for row in &mut rows {
             row.file_id = file_id;
         }
 
         if let Some(idx) = existing_index {
+            self.catalog.files[idx].cursor = Some(cursor);
             if self.catalog.files[idx].sanitized_lines == sanitized_lines {
                 return Ok(false);
             }
         }
 
-        self.catalog.rows.retain(|row| row.file_id != file_id);
+        let catalog_rows = Arc::make_mut(&mut self.catalog.rows);
+        catalog_rows.retain(|row| row.file_id != file_id);
+        catalog_rows.extend(rows);
+        self.full_resync = true;
 
         if let Some(idx) = existing_index {
             self.catalog.files[idx].sanitized_lines = sanitized_lines;
@@ -721,38 +1851,107 @@ impl App {
             self.catalog.files.push(FileEntry {
                 path: path.to_path_buf(),
                 sanitized_lines,
+                cursor: Some(cursor),
             });
         }
-
-        self.catalog.rows.extend(rows);
         Ok(true)
     }
 
+    /// Parses only what was appended to an already indexed file. Returns `None` when the
+    /// file shrank, was rewritten or has no cursor, so the caller falls back to a full reparse.
+    fn append_file_from_disk(&mut self, file_id: usize, path: &Path, extractor: &Extractor) -> Result<Option<bool>> {
+        let Some(cursor) = self.catalog.files[file_id].cursor.filter(|_| !is_gzip(path)) else {
+            return Ok(None);
+        };
+        let mmap = mmap_file(path)?;
+        let bytes = &mmap[..];
+        if !cursor.can_resume(bytes) {
+            return Ok(None);
+        }
+
+        let tail = &bytes[cursor.resume_offset as usize..];
+        let lines = scan_lines(tail);
+        let sanitized_lines = sanitize_lines(tail, &lines);
+        let (mut rows, _columns) = parse_rows(file_id, path, &lines, &sanitized_lines, extractor);
+        for row in &mut rows {
+            row.line_start += cursor.resume_line;
+            row.line_end += cursor.resume_line;
+        }
+
+        let file = &mut self.catalog.files[file_id];
+        // Pads with empty lines if eviction already freed this file's earlier lines.
+        file.sanitized_lines.resize(cursor.resume_line, String::new());
+        file.sanitized_lines.extend(sanitized_lines);
+        file.cursor = Some(AppendCursor::new(bytes, &lines, &rows, cursor.resume_line, cursor.resume_offset));
+
+        let replaced = |row: &Row| row.file_id == file_id && row.line_start >= cursor.resume_line;
+        if let Some(first) = self.catalog.rows.iter().position(replaced) {
+            Arc::make_mut(&mut self.catalog.rows).retain(|row| !replaced(row));
+            self.rows_changed_from = Some(self.rows_changed_from.map_or(first, |from| from.min(first)));
+        }
+        self.pending_rows.extend(rows);
+        Ok(Some(true))
+    }
+
     fn remove_file_by_path(&mut self, path: &Path) -> bool {
         if let Some(index) = self.catalog.files.iter().position(|file| file.path == *path) {
             self.catalog.files.remove(index);
-            self.catalog.rows.retain(|row| row.file_id != index);
-            for row in &mut self.catalog.rows {
+            let rows = Arc::make_mut(&mut self.catalog.rows);
+            rows.retain(|row| row.file_id != index);
+            for row in rows {
                 if row.file_id > index {
                     row.file_id -= 1;
                 }
             }
+            self.full_resync = true;
             true
         } else {
             false
         }
     }
 
-    fn sync_after_catalog_changes(&mut self) {
-        self.catalog.rows.sort_by(|left, right| {
-            left.ts
-                .cmp(&right.ts)
-                .then_with(|| left.file_id.cmp(&right.file_id))
-                .then_with(|| left.line_start.cmp(&right.line_start))
-        });
+    fn sync_after_catalog_changes(&mut self) -> usize {
+        if std::mem::take(&mut self.full_resync) {
+            return self.resync_catalog();
+        }
+        let new_rows = std::mem::take(&mut self.pending_rows);
+        let mut changed_from = self.rows_changed_from.take().unwrap_or(self.catalog.rows.len());
+        for row in &new_rows {
+            for key in row.flat.keys() {
+                if let Err(pos) = self.catalog.columns.binary_search(key) {
+                    self.catalog.columns.insert(pos, key.clone());
+                }
+            }
+        }
+        if let Some(first) = merge_sorted_rows(Arc::make_mut(&mut self.catalog.rows), new_rows) {
+            changed_from = changed_from.min(first);
+        }
+        let evicted = self.evict_oldest_rows();
+        self.sync_duckdb_rows(changed_from.saturating_sub(evicted), evicted);
+        // Until the worker publishes, keep showing the filtered rows that did not move.
+        let unchanged = changed_from.saturating_sub(evicted);
+        self.filtered = self
+            .filtered
+            .iter()
+            .filter_map(|&idx| idx.checked_sub(evicted))
+            .filter(|&idx| idx < unchanged)
+            .collect();
+        self.apply_filters();
+        self.selected = None;
+        self.page = 0;
+        evicted
+    }
+
+    /// Re-sorts every row and rebuilds the columns and DuckDB, for changes that renumber
+    /// files or replace rows in the middle of a file.
+    fn resync_catalog(&mut self) -> usize {
+        self.pending_rows.clear();
+        self.rows_changed_from = None;
+        Arc::make_mut(&mut self.catalog.rows).sort_by(row_order);
+        let evicted = self.evict_oldest_rows();
 
         let mut column_set = BTreeSet::new();
-        for row in &self.catalog.rows {
+        for row in self.catalog.rows.iter() {
             for key in row.flat.keys() {
                 column_set.insert(key.clone());
             }
@@ -764,18 +1963,105 @@ impl App {
         self.apply_filters();
         self.selected = None;
         self.page = 0;
+        evicted
+    }
+
+    /// Brings DuckDB in line with `catalog.rows` after `evicted` rows were dropped from the
+    /// front and the rows from `changed_from` on were replaced. Rebuilds the table if that fails.
+    fn sync_duckdb_rows(&mut self, changed_from: usize, evicted: usize) {
+        let Some(conn) = &self.db_conn else {
+            return;
+        };
+        self.catalog.duckdb_row_offset += evicted;
+        if let Err(e) = update_duckdb_rows(conn, &self.catalog.rows, self.catalog.duckdb_row_offset, changed_from) {
+            eprintln!("Failed to update DuckDB, rebuilding it: {e:#}");
+            self.rebuild_duckdb();
+        }
+    }
+
+    fn row_key(&self, row_idx: usize) -> Option<RowKey> {
+        let row = self.catalog.rows.get(row_idx)?;
+        let file = self.catalog.files.get(row.file_id)?;
+        Some((file.path.clone(), row.line_start))
+    }
+
+    /// Drops the oldest rows beyond `max_rows` so long live sessions keep memory bounded.
+    /// Expects `catalog.rows` sorted oldest first. Lines of files left without rows are freed;
+    /// files that still have rows keep all their lines for context.
+    fn evict_oldest_rows(&mut self) -> usize {
+        if self.max_rows == 0 || self.catalog.rows.len() <= self.max_rows {
+            return 0;
+        }
+        let evicted = self.catalog.rows.len() - self.max_rows;
+        let mut emptied: BTreeSet<usize> = Arc::make_mut(&mut self.catalog.rows).drain(..evicted).map(|row| row.file_id).collect();
+        for row in self.catalog.rows.iter() {
+            emptied.remove(&row.file_id);
+        }
+        for file_id in emptied {
+            let lines = &mut self.catalog.files[file_id].sanitized_lines;
+            lines.clear();
+            lines.shrink_to_fit();
+        }
+        evicted
+    }
+
+    /// Applies a lowered `max_rows` right away instead of waiting for the next live update.
+    fn apply_row_cap(&mut self) {
+        if !self.live_mode || self.indexing || self.max_rows == 0 || self.catalog.rows.len() <= self.max_rows {
+            return;
+        }
+        let evicted = self.sync_after_catalog_changes();
+        self.filter_status = Some(format!("Evicted {} oldest row{}", evicted, if evicted == 1 { "" } else { "s" }));
+    }
+
+    /// Deletes a DuckDB file that is no longer in use, unless `--keep-db` was given.
+    fn discard_duckdb(&self, path: PathBuf) {
+        if self.keep_db {
+            eprintln!("Keeping DuckDB database at {}", path.display());
+        } else {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+
+    /// Copies the current DuckDB database to a chosen file for use with the `duckdb` CLI.
+    fn export_database(&mut self) {
+        let (Some(conn), Some(db_path)) = (&self.db_conn, &self.catalog.duckdb_path) else {
+            return;
+        };
+        let Some(target) = FileDialog::new()
+            .set_title("Export database")
+            .set_directory(&self.root)
+            .set_file_name("logs.duckdb")
+            .add_filter("DuckDB", &["duckdb", "db"])
+            .save_file()
+        else {
+            return;
+        };
+        // Fold the write-ahead log into the main file so the copy has every row.
+        let result = conn
+            .execute_batch("CHECKPOINT")
+            .context("checkpoint database")
+            .and_then(|()| std::fs::copy(db_path, &target).context("copy database"));
+        self.status = match result {
+            Ok(_) => format!("Exported database to {}", target.display()),
+            Err(err) => format!("Database export failed: {err:#}"),
+        };
     }
 
     fn rebuild_duckdb(&mut self) {
         self.db_conn = None;
         if let Some(old_path) = self.catalog.duckdb_path.take() {
-            let _ = std::fs::remove_file(old_path);
+            self.discard_duckdb(old_path);
+        }
+        if !self.use_duckdb {
+            return;
         }
         match populate_duckdb(&self.catalog.rows) {
             Ok(db_path) => match Connection::open(&db_path) {
                 Ok(conn) => {
                     self.db_conn = Some(conn);
                     self.catalog.duckdb_path = Some(db_path);
+                    self.catalog.duckdb_row_offset = 0;
                 }
                 Err(e) => {
                     eprintln!("Failed to open rebuilt DuckDB: {e}");
@@ -802,6 +2088,92 @@ impl App {
         }
     }
 
+    fn handle_copy_shortcut(&mut self, ctx: &egui::Context) {
+        // Text inputs keep their own copy behavior.
+        if ctx.wants_keyboard_input() {
+            return;
+        }
+        let copy_requested = ctx.input(|i| i.events.iter().any(|event| matches!(event, egui::Event::Copy)));
+        if !copy_requested {
+            return;
+        }
+
+        if let Some(value) = self.hovered_cell_value.clone() {
+            ctx.output_mut(|output| output.copied_text = value);
+            self.status = "Copied cell value".into();
+        } else if let Some(selected_idx) = self.selected {
+            if let Some(row) = self.filtered.get(selected_idx).and_then(|row_idx| self.catalog.rows.get(*row_idx)) {
+                ctx.output_mut(|output| output.copied_text = row.raw_json.clone());
+                self.status = "Copied selected JSON".into();
+            }
+        }
+    }
+
+    /// Up/Down (or k/j) move the selection, Enter toggles the selected row's expansion, `r`
+    /// reloads the selected row's file and `/` focuses the search box. Ignored while a text
+    /// field has focus.
+    fn handle_navigation_keys(&mut self, ctx: &egui::Context) {
+        if ctx.wants_keyboard_input() {
+            return;
+        }
+        let (up, down, enter, slash, reload) = ctx.input(|i| {
+            (
+                i.key_pressed(Key::ArrowUp) || i.key_pressed(Key::K),
+                i.key_pressed(Key::ArrowDown) || i.key_pressed(Key::J),
+                i.key_pressed(Key::Enter),
+                i.key_pressed(Key::Slash),
+                i.key_pressed(Key::R) && i.modifiers.is_none(),
+            )
+        });
+
+        if reload {
+            let file_id = self
+                .selected
+                .and_then(|idx| self.filtered.get(idx))
+                .and_then(|row_idx| self.catalog.rows.get(*row_idx))
+                .map(|row| row.file_id);
+            if let Some(file_id) = file_id {
+                self.reload_file(file_id);
+            }
+            return;
+        }
+
+        if slash {
+            // Drop the typed "/" so it doesn't land in the freshly focused search box.
+            ctx.input_mut(|i| i.events.retain(|event| !matches!(event, egui::Event::Text(text) if text == "/")));
+            ctx.memory_mut(|memory| memory.request_focus(egui::Id::new(SEARCH_INPUT_ID)));
+            return;
+        }
+
+        if self.filtered.is_empty() {
+            return;
+        }
+        if up || down {
+            let last = self.filtered.len() - 1;
+            let next = match (self.selected, down) {
+                (None, true) => self.page * self.page_size,
+                (None, false) => ((self.page + 1) * self.page_size).saturating_sub(1),
+                (Some(idx), true) => idx + 1,
+                (Some(idx), false) => idx.saturating_sub(1),
+            }
+            .min(last);
+            self.selected = Some(next);
+            self.page = next / self.page_size.max(1);
+            self.scroll_to_selected = true;
+        }
+        if enter {
+            if let Some(key) = self
+                .selected
+                .and_then(|idx| self.filtered.get(idx).copied())
+                .and_then(|row_idx| self.row_key(row_idx))
+            {
+                if !self.expanded_rows.remove(&key) {
+                    self.expanded_rows.insert(key);
+                }
+            }
+        }
+    }
+
     fn column_width_for(&mut self, key: &str) -> f32 {
         let default = default_width_for_column(key);
         *self.column_widths.entry(key.to_string()).or_insert(default)
@@ -814,14 +2186,108 @@ impl App {
         }
     }
 
+    /// Writes the filtered rows, in display order, to a user-chosen `.csv` or `.jsonl` file.
+    fn export_filtered(&mut self) {
+        let Some(path) = FileDialog::new()
+            .set_title("Export filtered rows")
+            .set_directory(&self.root)
+            .set_file_name("logs.csv")
+            .add_filter("CSV", &["csv"])
+            .add_filter("JSON Lines", &["jsonl", "ndjson"])
+            .save_file()
+        else {
+            return;
+        };
+        let rows = self.filtered.iter().filter_map(|idx| self.catalog.rows.get(*idx));
+        let is_jsonl = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("jsonl") || ext.eq_ignore_ascii_case("ndjson"));
+        let result = if is_jsonl {
+            export_jsonl(&path, rows)
+        } else {
+            let mut columns: Vec<String> = BASE_COLUMNS.iter().map(|(key, _)| (*key).to_string()).collect();
+            columns.extend(self.dynamic_columns());
+            export_csv(&path, &columns, rows)
+        };
+        self.status = match result {
+            Ok(()) => format!("Exported {} rows to {}", self.filtered.len(), path.display()),
+            Err(err) => format!("Export failed: {err:#}"),
+        };
+    }
+
+    /// Resorts the filtered set by the clicked header column. The sort is stable, so rows
+    /// with equal values keep their timestamp order.
+    fn sort_filtered_by_column(&mut self) {
+        let Some(column) = self.sort_column.as_deref() else {
+            return;
+        };
+        sort_rows_by_column(&self.catalog.rows, &mut self.filtered, column, self.sort_ascending);
+    }
+
+    fn toggle_sort_column(&mut self, key: &str) {
+        if self.sort_column.as_deref() == Some(key) {
+            self.sort_ascending = !self.sort_ascending;
+        } else {
+            self.sort_column = Some(key.to_string());
+            self.sort_ascending = true;
+        }
+        if self.filter_rx.is_some() {
+            // The running filter sorts by the old column; rerun it with the new one.
+            self.apply_filters();
+        } else {
+            self.sort_filtered_by_column();
+            self.page = 0;
+            self.selected = None;
+        }
+    }
+
+    fn header_text(&self, key: &str, label: &str) -> String {
+        match self.sort_column.as_deref() {
+            Some(column) if column == key => format!("{label} {}", if self.sort_ascending { "▲" } else { "▼" }),
+            _ => label.to_string(),
+        }
+    }
+
     fn handle_header_response(&mut self, ctx: &egui::Context, key: &str, response: &egui::Response) {
         let width = response.rect.width().max(60.0);
         self.update_column_width_entry(key, width);
+        if response.clicked() {
+            self.toggle_sort_column(key);
+            ctx.request_repaint();
+        }
         if response.double_clicked() {
             let target = self.auto_column_width(ctx, key);
             self.column_widths.insert(key.to_string(), target);
             ctx.request_repaint();
         }
+        response.context_menu(|ui| self.render_transform_menu(ui, key));
+    }
+
+    fn render_transform_menu(&mut self, ui: &mut egui::Ui, key: &str) {
+        ui.label(RichText::new("Display as").strong());
+        let current = self.column_transforms.get(key).cloned();
+        if ui.selectable_label(current.is_none(), "Raw value").clicked() {
+            self.column_transforms.remove(key);
+            ui.close_menu();
+        }
+        for preset in ColumnTransform::PRESETS {
+            if ui.selectable_label(current.as_ref() == Some(&preset), preset.label()).clicked() {
+                self.column_transforms.insert(key.to_string(), preset);
+                ui.close_menu();
+            }
+        }
+        ui.separator();
+        ui.label("Custom format ({} is the value)");
+        ui.horizontal(|ui| {
+            ui.add(TextEdit::singleline(&mut self.transform_format_input).desired_width(140.0));
+            let is_custom = matches!(current, Some(ColumnTransform::Format(_)));
+            if ui.selectable_label(is_custom, "Apply").clicked() {
+                self.column_transforms
+                    .insert(key.to_string(), ColumnTransform::Format(self.transform_format_input.clone()));
+                ui.close_menu();
+            }
+        });
     }
 
     fn auto_column_width(&self, ctx: &egui::Context, key: &str) -> f32 {
@@ -848,16 +2314,205 @@ impl App {
         (max_width + 32.0).clamp(80.0, 1024.0)
     }
 
+    /// Level counts for the filtered set, recomputed only when the set changes.
+    /// Cheap identity of the current filtered set, for caching values derived from it.
+    fn filtered_fingerprint(&self) -> SetFingerprint {
+        (
+            self.catalog.rows.len(),
+            self.filtered.len(),
+            self.filtered.first().copied(),
+            self.filtered.last().copied(),
+        )
+    }
+
+    fn level_counts(&mut self) -> &[(String, usize)] {
+        let fingerprint = self.filtered_fingerprint();
+        if self.level_counts.as_ref().map(|(key, _)| *key) != Some(fingerprint) {
+            let mut counts: HashMap<String, usize> = HashMap::new();
+            for row in self.filtered.iter().filter_map(|idx| self.catalog.rows.get(*idx)) {
+                let level = row.level.as_deref().unwrap_or("").to_ascii_lowercase();
+                *counts.entry(level).or_default() += 1;
+            }
+            let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+            // Most severe first; unknown levels last, alphabetically.
+            counts.sort_by(|(a, _), (b, _)| {
+                let rank = |level: &str| std::cmp::Reverse(smooai_logger::Level::parse_level(level).map(|level| level as u8));
+                rank(a).cmp(&rank(b)).then_with(|| a.cmp(b))
+            });
+            self.level_counts = Some((fingerprint, counts));
+        }
+        self.level_counts.as_ref().map(|(_, counts)| counts.as_slice()).unwrap_or_default()
+    }
+
+    fn aggregate_counts(&mut self) -> &[(String, usize)] {
+        let fingerprint = self.filtered_fingerprint();
+        let stale = self
+            .aggregate_counts
+            .as_ref()
+            .is_none_or(|(key, column, _)| *key != fingerprint || *column != self.aggregate_column);
+        if stale {
+            let mut counts: HashMap<String, usize> = HashMap::new();
+            for row in self.filtered.iter().filter_map(|idx| self.catalog.rows.get(*idx)) {
+                *counts.entry(resolve_row_value(row, &self.aggregate_column)).or_default() += 1;
+            }
+            let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+            counts.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then_with(|| a.cmp(b)));
+            self.aggregate_counts = Some((fingerprint, self.aggregate_column.clone(), counts));
+        }
+        self.aggregate_counts.as_ref().map(|(_, _, counts)| counts.as_slice()).unwrap_or_default()
+    }
+
+    /// Narrows the filters to rows whose `column` is `value`, using the dedicated filter
+    /// field when the column has one and the path expression otherwise.
+    fn filter_on_value(&mut self, column: &str, value: &str) {
+        let field = match column {
+            "level" => &mut self.filters.level,
+            "correlationId" => &mut self.filters.corr,
+            "service" => &mut self.filters.service,
+            "namespace" => &mut self.filters.namespace,
+            "traceId" => &mut self.filters.trace,
+            "requestId" => &mut self.filters.request,
+            _ => {
+                self.filters.expr = format!("{column} == {}", PathFilter::quote(value));
+                self.apply_filters();
+                return;
+            }
+        };
+        *field = value.to_string();
+        self.apply_filters();
+    }
+
+    /// Counts of the filtered rows per distinct value of one column, with bars; clicking a
+    /// value filters to it.
+    fn render_aggregate_panel(&mut self, ui: &mut egui::Ui) {
+        const MAX_VALUES: usize = 200;
+        ui.horizontal(|ui| {
+            ui.heading("Group by");
+            let mut columns: Vec<String> = BASE_COLUMNS.iter().map(|(key, _)| key.to_string()).filter(|key| key != "time").collect();
+            for extra in ["traceId", "requestId"] {
+                columns.push(extra.into());
+            }
+            for column in &self.catalog.columns {
+                if !columns.contains(column) {
+                    columns.push(column.clone());
+                }
+            }
+            egui::ComboBox::from_id_source("aggregate-column")
+                .selected_text(&self.aggregate_column)
+                .show_ui(ui, |ui| {
+                    for column in columns {
+                        ui.selectable_value(&mut self.aggregate_column, column.clone(), column);
+                    }
+                });
+            ui.label(RichText::new(format!("over {} filtered rows", format_count(self.filtered.len()))).color(Color32::from_gray(150)));
+        });
+        let column = self.aggregate_column.clone();
+        let counts = self.aggregate_counts().to_vec();
+        let max = counts.first().map(|(_, count)| *count).unwrap_or(1).max(1);
+        let mut clicked: Option<String> = None;
+        ui.separator();
+        egui::ScrollArea::vertical()
+            .id_source("aggregate_results")
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                egui::Grid::new("aggregate_grid").num_columns(2).striped(true).show(ui, |ui| {
+                    for (value, count) in counts.iter().take(MAX_VALUES) {
+                        if value.is_empty() {
+                            ui.label(RichText::new("(empty)").italics().color(Color32::from_gray(150)));
+                        } else if ui.link(value).on_hover_text(format!("Filter to {column} = {value}")).clicked() {
+                            clicked = Some(value.clone());
+                        }
+                        let fraction = *count as f32 / max as f32;
+                        ui.add(egui::ProgressBar::new(fraction).desired_width(240.0).text(format_count(*count)));
+                        ui.end_row();
+                    }
+                });
+                if counts.len() > MAX_VALUES {
+                    ui.label(RichText::new(format!("…and {} more values", format_count(counts.len() - MAX_VALUES))).color(Color32::from_gray(150)));
+                }
+            });
+        if let Some(value) = clicked {
+            self.filter_on_value(&column, &value);
+        }
+    }
+
+    /// Strip of level shortcuts and per-level chips above the table; clicking a chip filters
+    /// to that level. Shown even when nothing matches, so the shortcuts can be turned off.
+    fn render_level_summary(&mut self, ui: &mut egui::Ui) {
+        let counts = self.level_counts().to_vec();
+        let mut clicked: Option<String> = None;
+        let mut toggled = false;
+        ui.horizontal_wrapped(|ui| {
+            toggled |= ui
+                .toggle_value(&mut self.filters.errors_only, RichText::new("Errors only").color(theme::smoo::RED))
+                .on_hover_text("Show only error and fatal rows, together with the other filters")
+                .changed();
+            toggled |= ui
+                .toggle_value(&mut self.filters.hide_verbose, "Hide trace/debug")
+                .on_hover_text("Hide trace and debug rows, together with the other filters")
+                .changed();
+            ui.separator();
+            for (level, count) in &counts {
+                let label = if level.is_empty() { "(none)" } else { level.as_str() };
+                let color = theme::level_color(level);
+                let chip = egui::Button::new(RichText::new(format!("{label} {count}")).color(color).strong())
+                    .stroke(egui::Stroke::new(1.0, color))
+                    .selected(!level.is_empty() && self.filters.level.eq_ignore_ascii_case(level));
+                if ui.add(chip).on_hover_text("Filter to this level").clicked() && !level.is_empty() {
+                    clicked = Some(level.clone());
+                }
+            }
+            if !self.filters.level.is_empty() && ui.small_button("✕ level").on_hover_text("Clear the level filter").clicked() {
+                clicked = Some(String::new());
+            }
+        });
+        if let Some(level) = clicked {
+            self.filters.level = level;
+            self.apply_filters();
+        } else if toggled {
+            self.apply_filters();
+        }
+    }
+
     fn render_log_table(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
         let extra_columns = self.dynamic_columns();
+        let search = self.search_highlight();
+        let match_bg = theme::match_background(self.dark_mode);
+        if self.follow_tail && !self.scroll_to_tail {
+            let scrolled = ui.rect_contains_pointer(ui.available_rect_before_wrap()) && ui.input(|i| i.raw_scroll_delta.y != 0.0);
+            if scrolled {
+                self.follow_tail = false;
+                self.status = "Follow tail paused".into();
+            }
+        }
+        let transforms = self.column_transforms.clone();
+        // Reloading changes the rows being drawn, so it waits until the table is done.
+        let mut reload_file_id = None;
+        let (base_row_height, header_height) = if self.compact_rows { (16.0, 22.0) } else { (22.0, 28.0) };
 
         egui::ScrollArea::both().auto_shrink([false, false]).show(ui, |ui| {
+            if self.compact_rows {
+                theme::compact_table_style(ui.style_mut());
+            }
             ui.set_width(ui.available_width());
             let available = ui.available_height();
 
             let mut table = TableBuilder::new(ui).striped(true).min_scrolled_height(available.max(200.0));
             table = table.column(Column::initial(28.0).resizable(false).clip(true));
             table = table.sense(egui::Sense::click());
+            if std::mem::take(&mut self.scroll_to_selected) {
+                if let Some(selected) = self.selected {
+                    table = table.scroll_to_row(selected.saturating_sub(self.page * self.page_size), Some(egui::Align::Center));
+                }
+            } else if std::mem::take(&mut self.scroll_to_tail) {
+                let rows_on_page = self.filtered.len().saturating_sub(self.page * self.page_size).min(self.page_size);
+                let (row, align) = if self.sort_desc {
+                    (0, egui::Align::TOP)
+                } else {
+                    (rows_on_page.saturating_sub(1), egui::Align::BOTTOM)
+                };
+                table = table.scroll_to_row(row, Some(align));
+            }
 
             for (key, _) in BASE_COLUMNS.iter() {
                 let width = self.column_width_for(key);
@@ -878,7 +2533,7 @@ impl App {
             let grid_stroke = theme::grid_stroke(self.dark_mode);
 
             table
-                .header(28.0, |mut header| {
+                .header(header_height, |mut header| {
                     header.col(|ui| {
                         let response = ui.label(RichText::new(" ").background_color(header_bg));
                         ui.painter().rect_stroke(response.rect, 0.0, grid_stroke);
@@ -886,7 +2541,8 @@ impl App {
 
                     for (key, label) in BASE_COLUMNS.iter() {
                         header.col(|ui| {
-                            let response = ui.add(egui::Label::new(RichText::new(*label).strong().background_color(header_bg)).sense(Sense::click()));
+                            let text = self.header_text(key, label);
+                            let response = ui.add(egui::Label::new(RichText::new(text).strong().background_color(header_bg)).sense(Sense::click()));
                             ui.painter().rect_stroke(response.rect, 0.0, grid_stroke);
                             self.handle_header_response(ctx, key, &response);
                         });
@@ -894,7 +2550,8 @@ impl App {
 
                     for column in &extra_columns {
                         header.col(|ui| {
-                            let response = ui.add(egui::Label::new(RichText::new(column).strong().background_color(header_bg)).sense(Sense::click()));
+                            let text = self.header_text(column, column);
+                            let response = ui.add(egui::Label::new(RichText::new(text).strong().background_color(header_bg)).sense(Sense::click()));
                             ui.painter().rect_stroke(response.rect, 0.0, grid_stroke);
                             self.handle_header_response(ctx, column.as_str(), &response);
                         });
@@ -928,44 +2585,80 @@ impl App {
                             .iter()
                             .map(|column| {
                                 let full = resolve_row_value(row, column);
-                                let short = shorten_for_display(&full, 160);
+                                let short = match transforms.get(column) {
+                                    Some(transform) => transform.apply(&full),
+                                    None => shorten_for_display(&full, 160),
+                                };
                                 (full, short)
                             })
                             .collect();
 
-                        let is_expanded = self.expanded_rows.contains(&row_idx);
-                        let (pretty_json, json_lines) = if is_expanded {
-                            let (formatted, lines) = format_json_for_display(&row.raw_json);
-                            (Some(formatted), lines)
+                        let row_key = self.row_key(row_idx);
+                        let is_expanded = row_key.as_ref().is_some_and(|key| self.expanded_rows.contains(key));
+                        let (expanded_json, json_lines) = if is_expanded {
+                            match serde_json::from_str::<Value>(&row.raw_json) {
+                                Ok(value) => {
+                                    let lines = inline_json_line_count(&value, 0, self.inline_json_depth);
+                                    (Some(ExpandedJson::Tree(value)), lines)
+                                }
+                                Err(_) => {
+                                    let (formatted, lines) = format_json_for_display(&row.raw_json);
+                                    (Some(ExpandedJson::Text(formatted)), lines)
+                                }
+                            }
                         } else {
                             (None, 0)
                         };
+                        let multiline_msg = if is_expanded && msg_value.contains(['\n', '\t']) {
+                            Some(msg_value.replace('\t', "    "))
+                        } else {
+                            None
+                        };
+                        let msg_lines = multiline_msg.as_ref().map(|msg| msg.lines().count() + 1).unwrap_or(0);
+                        let inline_json_id = egui::Id::new(("inline-json", row_idx, self.inline_json_depth));
+                        let inline_json_depth = self.inline_json_depth;
                         let extra_height = if is_expanded {
-                            ((json_lines as f32) * 18.0 + 12.0).clamp(54.0, 360.0)
+                            (((json_lines + msg_lines) as f32) * 18.0 + 12.0).clamp(54.0, 360.0)
                         } else {
                             0.0
                         };
-                        let row_height = 22.0 + extra_height;
+                        let row_height = base_row_height + extra_height;
 
                         let mut open_file_request = false;
                         let mut open_with_request = false;
+                        let mut reload_request = false;
+                        let mut hovered_value: Option<String> = None;
+
+                        let is_selected = self.selected == Some(filtered_idx);
+                        // Faint tint for error/fatal rows; the selection highlight takes precedence.
+                        let row_tint = (!is_selected && theme::is_error_level(&level_value)).then(|| theme::error_row_background(self.dark_mode));
+                        let paint_tint = |ui: &mut egui::Ui| {
+                            if let Some(tint) = row_tint {
+                                ui.painter().rect_filled(ui.max_rect(), 0.0, tint);
+                            }
+                        };
 
                         body.row(row_height, |mut row_ui| {
+                            row_ui.set_selected(is_selected);
                             let mut row_clicked = false;
 
                             row_ui.col(|ui| {
+                                paint_tint(ui);
                                 let symbol = if is_expanded { "⌄" } else { "›" };
                                 let response = ui.add(egui::Label::new(RichText::new(symbol).color(Color32::from_gray(180))).sense(Sense::click()));
-                                if response.clicked() {
+                                if let Some(key) = row_key.clone().filter(|_| response.clicked()) {
                                     if is_expanded {
-                                        self.expanded_rows.remove(&row_idx);
+                                        self.expanded_rows.remove(&key);
                                     } else {
-                                        self.expanded_rows.insert(row_idx);
+                                        self.expanded_rows.insert(key);
                                     }
                                 }
                             });
 
-                            let mut process_response = |response: egui::Response, row_clicked: &mut bool| {
+                            let mut process_response = |response: egui::Response, full_value: &str, row_clicked: &mut bool| {
+                                if response.hovered() {
+                                    hovered_value = Some(full_value.to_string());
+                                }
                                 if response.clicked() {
                                     *row_clicked = true;
                                 }
@@ -981,6 +2674,10 @@ impl App {
                                         open_with_request = true;
                                         ui.close_menu();
                                     }
+                                    if ui.button("Reload this file").on_hover_text("Re-read this file from disk (r)").clicked() {
+                                        reload_request = true;
+                                        ui.close_menu();
+                                    }
                                 });
                             };
 
@@ -998,10 +2695,11 @@ impl App {
                                     _ => String::new(),
                                 };
 
-                                let display_value = match *key {
-                                    "msg" => msg_display.clone(),
-                                    "error" => error_display.clone(),
-                                    "errorDetails" => error_details_display.clone(),
+                                let display_value = match (transforms.get(*key), *key) {
+                                    (Some(transform), _) => transform.apply(&raw_value),
+                                    (None, "msg") => msg_display.clone(),
+                                    (None, "error") => error_display.clone(),
+                                    (None, "errorDetails") => error_details_display.clone(),
                                     _ => raw_value.clone(),
                                 };
 
@@ -1013,18 +2711,43 @@ impl App {
                                 }
 
                                 row_ui.col(|ui| {
-                                    let response = ui.add(egui::Label::new(rich.clone()).truncate().sense(Sense::click()));
+                                    paint_tint(ui);
+                                    let highlighted = match (&search, *key) {
+                                        (Some(search), "msg") => {
+                                            let font_id = egui::TextStyle::Body.resolve(ui.style());
+                                            search.layout(&display_value, font_id, ui.visuals().text_color(), match_bg)
+                                        }
+                                        _ => None,
+                                    };
+                                    let label = match highlighted {
+                                        Some(job) => egui::Label::new(job),
+                                        None => egui::Label::new(rich.clone()),
+                                    };
+                                    let response = ui.add(label.truncate().sense(Sense::click()));
                                     let response = response.on_hover_text(raw_value.clone());
-                                    process_response(response, &mut row_clicked);
+                                    process_response(response, &raw_value, &mut row_clicked);
 
                                     if *key == "msg" && is_expanded {
-                                        if let Some(json) = pretty_json.as_ref() {
+                                        if let Some(message) = multiline_msg.as_ref() {
+                                            ui.add_space(6.0);
+                                            ui.scope(|ui| {
+                                                ui.style_mut().wrap_mode = Some(TextWrapMode::Extend);
+                                                ui.monospace(message);
+                                            });
+                                            ui.separator();
+                                        }
+                                        if let Some(json) = expanded_json.as_ref() {
                                             ui.add_space(6.0);
                                             let max_height = ((json_lines as f32) * 18.0 + 12.0).clamp(54.0, 360.0);
-                                            egui::ScrollArea::vertical().max_height(max_height).show(ui, |ui| {
+                                            egui::ScrollArea::vertical().id_source(inline_json_id).max_height(max_height).show(ui, |ui| {
                                                 ui.scope(|ui| {
                                                     ui.style_mut().wrap_mode = Some(TextWrapMode::Extend);
-                                                    ui.monospace(json);
+                                                    match json {
+                                                        ExpandedJson::Tree(value) => render_inline_json(ui, inline_json_id, value, inline_json_depth),
+                                                        ExpandedJson::Text(text) => {
+                                                            ui.monospace(text);
+                                                        }
+                                                    }
                                                 });
                                             });
                                         }
@@ -1034,9 +2757,10 @@ impl App {
 
                             for (full_value, short_value) in &extra_values {
                                 row_ui.col(|ui| {
+                                    paint_tint(ui);
                                     let response = ui.add(egui::Label::new(RichText::new(short_value.clone())).truncate().sense(Sense::click()));
                                     let response = response.on_hover_text(full_value.clone());
-                                    process_response(response, &mut row_clicked);
+                                    process_response(response, full_value, &mut row_clicked);
                                 });
                             }
 
@@ -1045,6 +2769,10 @@ impl App {
                             }
                         });
 
+                        if hovered_value.is_some() {
+                            self.hovered_cell_value = hovered_value;
+                        }
+
                         if open_file_request {
                             let file = &self.catalog.files[file_id];
                             match open_file_with_default(&file.path) {
@@ -1060,43 +2788,71 @@ impl App {
                         if open_with_request {
                             self.open_file_with_dialog(file_id);
                         }
+
+                        if reload_request {
+                            reload_file_id = Some(file_id);
+                        }
                     }
                 });
         });
+
+        if let Some(file_id) = reload_file_id {
+            self.reload_file(file_id);
+        }
     }
 
     fn render_context_panel(&mut self, ui: &mut egui::Ui) {
-        ui.heading("Context (within the same file)");
-
-        if let Some(selected_idx) = self.selected {
-            let row_idx = self.filtered[selected_idx];
-            let row = &self.catalog.rows[row_idx];
-            let (start, end) = self.context_range(row);
-            let file = &self.catalog.files[row.file_id];
-            let highlight = if self.dark_mode {
-                theme::dark_theme().ring
-            } else {
-                theme::light_theme().ring
-            };
-
-            egui::ScrollArea::vertical().show(ui, |ui| {
-                ui.monospace(format!("File: {}", file.path.display()));
-                for idx in start..end {
-                    let line = file.sanitized_lines.get(idx).map(|s| s.as_str()).unwrap_or("<binary>");
-                    if idx >= row.line_start && idx <= row.line_end {
-                        ui.colored_label(highlight, line);
-                    } else {
-                        ui.colored_label(theme::smoo::GRAY_400, line);
-                    }
+        let selected_row = self.selected.and_then(|selected_idx| self.filtered.get(selected_idx).copied());
+        let search = self.search_highlight();
+        let pinned_row = self.pinned_row.as_ref().and_then(|key| self.find_row(key, self.pinned_row_hint));
+        self.pinned_row_hint = pinned_row;
+
+        ui.horizontal(|ui| {
+            ui.heading("Context (within the same file)");
+            let goto = ui.add(TextEdit::singleline(&mut self.goto_input).hint_text("Go to file:line").desired_width(200.0));
+            if goto.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter)) && !self.goto_input.trim().is_empty() {
+                let target = std::mem::take(&mut self.goto_input);
+                self.goto_permalink(&target);
+            }
+            if self.pinned_row.is_some() {
+                if ui.button("📌 Follow selection").clicked() {
+                    self.pinned_row = None;
                 }
-            });
+            } else if let Some(row_idx) = selected_row {
+                if ui
+                    .button("📌 Pin context")
+                    .on_hover_text("Keep this row's context visible while browsing")
+                    .clicked()
+                {
+                    self.pinned_row = self.row_key(row_idx);
+                    self.pinned_row_hint = Some(row_idx);
+                }
+            }
+        });
 
-            if let Ok(json_value) = serde_json::from_str::<Value>(&row.raw_json) {
-                ui.separator();
-                ui.heading("JSON");
-                render_json_root(ui, &json_value);
+        match (pinned_row, selected_row) {
+            (Some(pinned), Some(selected)) if pinned != selected => {
+                ui.columns(2, |columns| {
+                    columns[0].push_id("pinned-context", |ui| {
+                        ui.strong("Pinned");
+                        self.render_row_context(ui, pinned, search.as_ref());
+                    });
+                    columns[1].push_id("selected-context", |ui| {
+                        ui.strong("Selected");
+                        self.render_row_context(ui, selected, search.as_ref());
+                    });
+                });
+            }
+            (Some(row_idx), _) | (None, Some(row_idx)) => {
+                self.render_row_context(ui, row_idx, search.as_ref());
+            }
+            (None, None) => {
+                ui.label("Select a row to view context.");
+                return;
             }
+        }
 
+        if let (Some(selected_idx), Some(row_idx)) = (self.selected, selected_row) {
             ui.horizontal(|ui| {
                 if ui.button("⟸ Prev match").clicked() && selected_idx > 0 {
                     self.selected = Some(selected_idx - 1);
@@ -1104,13 +2860,230 @@ impl App {
                 if ui.button("Next match ⟹").clicked() && selected_idx + 1 < self.filtered.len() {
                     self.selected = Some(selected_idx + 1);
                 }
+                if ui.button("⧉ Pop out").on_hover_text("Show this row's JSON in its own window").clicked() {
+                    self.pop_out_json(row_idx);
+                }
+                if let Some(permalink) = self.permalink(row_idx) {
+                    if ui
+                        .button("Copy permalink")
+                        .on_hover_text(format!("{permalink}\nOpen it with --goto or the Go to box"))
+                        .clicked()
+                    {
+                        ui.output_mut(|output| output.copied_text = permalink);
+                        self.status = "Copied permalink".into();
+                    }
+                }
                 if ui.button("Copy selected JSON").clicked() {
-                    ui.output_mut(|output| output.copied_text = row.raw_json.clone());
+                    ui.output_mut(|output| output.copied_text = self.catalog.rows[row_idx].raw_json.clone());
                     self.status = "Copied".into();
                 }
+                let curl = serde_json::from_str::<Value>(&self.catalog.rows[row_idx].raw_json)
+                    .ok()
+                    .and_then(|value| value.pointer("/http/request").and_then(curl_from_http_request));
+                if let Some(curl) = curl {
+                    if ui
+                        .button("Copy as curl")
+                        .on_hover_text("Rebuild the logged http.request as a curl command")
+                        .clicked()
+                    {
+                        ui.output_mut(|output| output.copied_text = curl);
+                        self.status = "Copied curl command".into();
+                    }
+                }
             });
+        }
+    }
+
+    /// Opens a JSON window pinned to `row_idx`.
+    fn pop_out_json(&mut self, row_idx: usize) {
+        self.json_windows.push(JsonWindow {
+            id: self.next_json_window,
+            pinned: self.row_key(row_idx),
+            row_hint: Some(row_idx),
+            shown: None,
+        });
+        self.next_json_window += 1;
+    }
+
+    /// `file:line` reference to a row, accepted by `--goto` and the "Go to" box.
+    fn permalink(&self, row_idx: usize) -> Option<String> {
+        let (path, line) = self.row_key(row_idx)?;
+        let path = path.canonicalize().unwrap_or(path);
+        Some(format!("{}:{}", path.display(), line + 1))
+    }
+
+    /// Jumps to a permalink typed into the "Go to" box, or after the running index.
+    fn goto_permalink(&mut self, target: &str) {
+        let Some((path, line)) = parse_permalink(target) else {
+            self.status = format!("Go to: expected <file>:<line>, got `{}`", target.trim());
+            return;
+        };
+        if self.indexing {
+            self.pending_goto = Some((path, line));
+        } else {
+            self.goto_line(&path, line);
+        }
+    }
+
+    /// Selects, scrolls to and expands the row covering `line` (1-based) of `path`, clearing
+    /// the filters if they hide it. A relative `path` is taken from the root folder. Waits
+    /// for a running filter first.
+    fn goto_line(&mut self, path: &Path, line: usize) {
+        if self.filter_rx.is_some() {
+            self.pending_goto = Some((path.to_path_buf(), line));
+            return;
+        }
+        let resolved = self.root.join(path);
+        let target = resolved.canonicalize().unwrap_or(resolved);
+        // Catalog paths are only canonicalized when none of them matches as written.
+        let cwd = std::env::current_dir().unwrap_or_default();
+        let file_id = self.catalog.files.iter().position(|file| cwd.join(&file.path) == target).or_else(|| {
+            self.catalog
+                .files
+                .iter()
+                .position(|file| file.path.canonicalize().is_ok_and(|file_path| file_path == target))
+        });
+        let Some(file_id) = file_id else {
+            self.status = format!("Go to: {} is not loaded", path.display());
+            return;
+        };
+        let line_idx = line.saturating_sub(1);
+        let Some(row_idx) = self
+            .catalog
+            .rows
+            .iter()
+            .position(|row| row.file_id == file_id && row.line_start <= line_idx && line_idx <= row.line_end)
+        else {
+            self.status = format!("Go to: no log entry at {}:{line}", path.display());
+            return;
+        };
+        let Some(position) = self.filtered.iter().position(|&idx| idx == row_idx) else {
+            if self.filters != Filters::default() {
+                self.filters = Filters::default();
+                self.apply_filters();
+                self.pending_goto = Some((path.to_path_buf(), line));
+            }
+            return;
+        };
+        self.selected = Some(position);
+        self.page = position / self.page_size.max(1);
+        self.scroll_to_selected = true;
+        self.follow_tail = false;
+        if let Some(key) = self.row_key(row_idx) {
+            self.expanded_rows.insert(key);
+        }
+        self.status = format!("Went to {}:{line}", path.display());
+    }
+
+    /// Catalog index of the row identified by `key`, trying `hint` first.
+    fn find_row(&self, key: &RowKey, hint: Option<usize>) -> Option<usize> {
+        if let Some(idx) = hint.filter(|idx| self.row_key(*idx).as_ref() == Some(key)) {
+            return Some(idx);
+        }
+        let file_id = self.catalog.files.iter().position(|file| file.path == key.0)?;
+        self.catalog.rows.iter().position(|row| row.file_id == file_id && row.line_start == key.1)
+    }
+
+    fn render_json_windows(&mut self, ctx: &egui::Context) {
+        let selected_row = self.selected.and_then(|idx| self.filtered.get(idx).copied());
+        let mut windows = std::mem::take(&mut self.json_windows);
+        windows.retain_mut(|window| {
+            let row_idx = match &window.pinned {
+                Some(key) => self.find_row(key, window.row_hint),
+                None => selected_row,
+            };
+            if window.pinned.is_some() {
+                window.row_hint = row_idx;
+            }
+            let key = row_idx.and_then(|idx| self.row_key(idx));
+            if window.shown.as_ref().map(|(shown, _)| shown) != key.as_ref() {
+                window.shown = key.zip(row_idx).map(|(key, idx)| {
+                    let raw = &self.catalog.rows[idx].raw_json;
+                    (key, serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.clone())))
+                });
+            }
+
+            let title = match &window.shown {
+                Some(((path, line), _)) => {
+                    let file = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+                    format!("{file}:{}", line + 1)
+                }
+                None => "No row".to_string(),
+            };
+            let mut open = true;
+            egui::Window::new(title)
+                .id(egui::Id::new(("json-window", window.id)))
+                .open(&mut open)
+                .resizable(true)
+                .default_size([460.0, 520.0])
+                .show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        let mut follow = window.pinned.is_none();
+                        if ui.checkbox(&mut follow, "Follow selection").changed() {
+                            window.pinned = if follow { None } else { window.shown.as_ref().map(|(key, _)| key.clone()) };
+                        }
+                        if let Some((_, value)) = &window.shown {
+                            if ui.button("Copy JSON").clicked() {
+                                ui.output_mut(|output| output.copied_text = serde_json::to_string_pretty(value).unwrap_or_default());
+                            }
+                        }
+                    });
+                    ui.separator();
+                    egui::ScrollArea::both().auto_shrink([false, false]).show(ui, |ui| match &window.shown {
+                        Some((_, value)) => render_json_root(ui, value),
+                        None if window.pinned.is_some() => {
+                            ui.label("The pinned row is no longer loaded.");
+                        }
+                        None => {
+                            ui.label("Select a row to view its JSON.");
+                        }
+                    });
+                });
+            open
+        });
+        self.json_windows = windows;
+    }
+
+    fn render_row_context(&self, ui: &mut egui::Ui, row_idx: usize, search: Option<&SearchHighlight>) {
+        let Some(row) = self.catalog.rows.get(row_idx) else {
+            return;
+        };
+        let (start, end) = self.context_range(row);
+        let file = &self.catalog.files[row.file_id];
+        let highlight = if self.dark_mode {
+            theme::dark_theme().ring
         } else {
-            ui.label("Select a row to view context.");
+            theme::light_theme().ring
+        };
+
+        let match_bg = theme::match_background(self.dark_mode);
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            ui.monospace(format!("File: {}", file.path.display()));
+            let font_id = egui::TextStyle::Monospace.resolve(ui.style());
+            for idx in start..end {
+                let line = file.sanitized_lines.get(idx).map(|s| s.as_str()).unwrap_or("<binary>");
+                let color = if idx >= row.line_start && idx <= row.line_end {
+                    highlight
+                } else {
+                    theme::smoo::GRAY_400
+                };
+                match search.and_then(|search| search.layout(line, font_id.clone(), color, match_bg)) {
+                    Some(job) => ui.label(job),
+                    None => ui.colored_label(color, line),
+                };
+            }
+        });
+
+        if let Ok(json_value) = serde_json::from_str::<Value>(&row.raw_json) {
+            let errors = error_details_from_json(&json_value);
+            if !errors.is_empty() {
+                ui.separator();
+                render_error_details(ui, row_idx, &errors);
+            }
+            ui.separator();
+            ui.heading("JSON");
+            render_json_root(ui, &json_value);
         }
     }
 
@@ -1231,7 +3204,8 @@ impl App {
         }
     }
 
-    fn watch_root(&mut self, path: PathBuf) {
+    fn watch_sources(&mut self) {
+        let sources = self.index_sources();
         if let Some(stop) = self.watch_stop.take() {
             stop.store(false, Ordering::SeqCst);
         }
@@ -1246,42 +3220,62 @@ impl App {
         let thread_flag = stop_flag.clone();
 
         let handle = thread::spawn(move || {
-            let mut known: HashMap<PathBuf, (SystemTime, u64)> = HashMap::new();
-            for dir in find_smooai_log_dirs(&path) {
-                for file in list_log_files(&dir) {
-                    if let Ok(metadata) = std::fs::metadata(&file) {
-                        if let Ok(modified) = metadata.modified() {
-                            known.insert(file.clone(), (modified, metadata.len()));
-                        }
-                    }
+            let (event_tx, event_rx) = mpsc::channel::<notify::Result<notify::Event>>();
+            let mut watcher = match notify::recommended_watcher(event_tx) {
+                Ok(watcher) => watcher,
+                Err(error) => {
+                    eprintln!("log watcher unavailable: {error}");
+                    return;
+                }
+            };
+            for dir in sources.log_dirs() {
+                if let Err(error) = watcher.watch(&dir, RecursiveMode::NonRecursive) {
+                    eprintln!("failed to watch {}: {error}", dir.display());
+                }
+            }
+            // Opened files are watched one by one, whatever their extension.
+            let explicit: HashSet<PathBuf> = sources.explicit_files().into_iter().collect();
+            for file in &explicit {
+                if let Err(error) = watcher.watch(file, RecursiveMode::NonRecursive) {
+                    eprintln!("failed to watch {}: {error}", file.display());
                 }
             }
 
+            // Editors and the rotating writer touch files in bursts; hold each path until it has
+            // been quiet for WATCH_DEBOUNCE and then report its final state once.
+            let mut pending: HashMap<PathBuf, Instant> = HashMap::new();
             while thread_flag.load(Ordering::SeqCst) {
-                let mut seen = HashSet::new();
-                for dir in find_smooai_log_dirs(&path) {
-                    for file in list_log_files(&dir) {
-                        seen.insert(file.clone());
-                        if let Ok(metadata) = std::fs::metadata(&file) {
-                            if let Ok(modified) = metadata.modified() {
-                                let len = metadata.len();
-                                match known.get(&file) {
-                                    Some((prev_mod, prev_len)) if *prev_mod >= modified && *prev_len == len => {}
-                                    _ => {
-                                        known.insert(file.clone(), (modified, len));
-                                        let _ = tx.send(WatchEvent::FileChanged(file.clone()));
-                                    }
-                                }
+                match event_rx.recv_timeout(WATCH_DEBOUNCE / 2) {
+                    Ok(Ok(event)) => {
+                        let relevant = matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_) | EventKind::Any);
+                        if relevant {
+                            for file in event.paths.into_iter().filter(|file| has_log_extension(file) || explicit.contains(file)) {
+                                pending.insert(file, Instant::now());
                             }
                         }
                     }
+                    Ok(Err(error)) => eprintln!("log watcher error: {error}"),
+                    Err(mpsc::RecvTimeoutError::Timeout) => {}
+                    Err(mpsc::RecvTimeoutError::Disconnected) => break,
                 }
-                let removed: Vec<PathBuf> = known.keys().filter(|path| !seen.contains(*path)).cloned().collect();
-                for path in removed {
-                    known.remove(&path);
-                    let _ = tx.send(WatchEvent::FileRemoved(path));
+
+                let ready: Vec<PathBuf> = pending
+                    .iter()
+                    .filter(|(_, seen)| seen.elapsed() >= WATCH_DEBOUNCE)
+                    .map(|(file, _)| file.clone())
+                    .collect();
+                for file in ready {
+                    pending.remove(&file);
+                    // Deletes and renames away both leave nothing at the old path.
+                    let event = if file.is_file() {
+                        WatchEvent::FileChanged(file)
+                    } else {
+                        WatchEvent::FileRemoved(file)
+                    };
+                    if tx.send(event).is_err() {
+                        return;
+                    }
                 }
-                thread::sleep(Duration::from_secs(2));
             }
         });
 
@@ -1306,27 +3300,34 @@ impl Drop for App {
         self.stop_watch();
         self.db_conn = None;
         if let Some(path) = self.catalog.duckdb_path.take() {
-            let _ = std::fs::remove_file(path);
+            self.discard_duckdb(path);
         }
     }
 }
 
 impl eframe::App for App {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, COLUMN_TRANSFORMS_KEY, &self.column_transforms);
+        eframe::set_value(storage, VIEWER_SETTINGS_KEY, &self.settings());
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         theme::apply_visuals(ctx, self.dark_mode);
         self.ensure_logo_texture(ctx);
+        self.handle_navigation_keys(ctx);
 
         if let Some(rx) = &self.watch_rx {
             while let Ok(event) = rx.try_recv() {
                 if self.live_mode {
                     self.pending_watch_events.push(event);
                 } else {
-                    self.status = "Log changes detected while live mode is off. Reindex to refresh.".into();
+                    self.status = "Log changes detected while live mode is off. Reindex, or press r to reload the selected row's file.".into();
                 }
             }
         }
 
         let mut finished_event: Option<Result<Catalog>> = None;
+        let mut cancelled = false;
         if let Some(receiver) = self.index_rx.as_ref() {
             while let Ok(event) = receiver.try_recv() {
                 match event {
@@ -1334,9 +3335,15 @@ impl eframe::App for App {
                         let capped_total = total.max(1);
                         let capped_processed = processed.min(capped_total);
                         self.index_progress = Some((capped_processed, total));
-                        self.status = format!("Indexing {}/{} files", capped_processed, total);
+                        if !self.index_cancel.as_ref().is_some_and(|cancel| cancel.load(Ordering::SeqCst)) {
+                            self.status = format!("Indexing {}/{} files", capped_processed, total);
+                        }
                         ctx.request_repaint();
                     }
+                    IndexEvent::Cancelled => {
+                        cancelled = true;
+                        break;
+                    }
                     IndexEvent::Finished(result) => {
                         finished_event = Some(result);
                         break;
@@ -1345,16 +3352,30 @@ impl eframe::App for App {
             }
         }
 
+        if cancelled {
+            self.indexing = false;
+            self.index_rx = None;
+            self.index_progress = None;
+            self.index_cancel = None;
+            self.pending_goto = None;
+            self.status = if self.has_rows() {
+                format!("Index cancelled; showing the previous {} rows", self.catalog.rows.len())
+            } else {
+                "Index cancelled".into()
+            };
+        }
+
         if let Some(result) = finished_event {
             self.indexing = false;
             self.index_rx = None;
             self.index_progress = None;
+            self.index_cancel = None;
             match result {
                 Ok(catalog) => {
                     // Close old DuckDB connection and file
                     self.db_conn = None;
                     if let Some(old) = self.catalog.duckdb_path.take() {
-                        let _ = std::fs::remove_file(old);
+                        self.discard_duckdb(old);
                     }
                     self.catalog = catalog;
                     // Open DuckDB connection for querying
@@ -1365,12 +3386,17 @@ impl eframe::App for App {
                         }
                     }
                     self.prune_visible_columns();
-                    self.expanded_rows.clear();
                     self.filtered = (0..self.catalog.rows.len()).collect();
                     self.selected = None;
                     self.page = 0;
                     self.apply_filters();
-                    self.status = format!("Indexed {} files, {} rows", self.catalog.files.len(), self.catalog.rows.len());
+                    let mut status = format!("Indexed {} files, {} rows", self.catalog.files.len(), self.catalog.rows.len());
+                    if !self.catalog.warnings.is_empty() {
+                        status.push_str(&format!(" ({} unreadable)", self.catalog.warnings.len()));
+                        self.show_index_warnings = true;
+                    }
+                    // A pending goto runs once these rows are filtered.
+                    self.filter_status = Some(status);
                 }
                 Err(error) => {
                     self.status = format!("Index error: {error:#}");
@@ -1380,13 +3406,14 @@ impl eframe::App for App {
 
         if self.pending_reindex && !self.indexing {
             self.pending_reindex = false;
-            let root = self.root.clone();
-            self.start_index(root, ctx);
+            self.start_index(ctx);
         }
 
         if !self.indexing {
             self.process_live_events(ctx);
+            self.process_live_filter(ctx);
         }
+        self.process_sql_result();
 
         if self.show_startup_modal {
             egui::Window::new("Choose log directory")
@@ -1394,7 +3421,7 @@ impl eframe::App for App {
                 .collapsible(false)
                 .resizable(false)
                 .show(ctx, |ui| {
-                    ui.label("Select the root folder containing `.smooai-logs/`.");
+                    ui.label("Select the root folder containing `.smooai-logs/`, or open log files directly.");
                     let mut path_string = self.pending_root.display().to_string();
                     ui.add_enabled(false, TextEdit::singleline(&mut path_string));
                     if ui.button("Browse…").clicked() {
@@ -1404,17 +3431,53 @@ impl eframe::App for App {
                     }
                     if ui.button("Start watching").clicked() {
                         self.root = self.pending_root.clone();
+                        self.index_dirs = true;
                         self.show_startup_modal = false;
-                        if self.live_mode {
-                            self.watch_root(self.root.clone());
-                        } else {
-                            self.stop_watch();
+                        self.reload_sources(ctx);
+                    }
+                    ui.separator();
+                    let mut opened = None;
+                    ui.horizontal(|ui| {
+                        if ui.button("Open files…").clicked() {
+                            if let Some(files) = FileDialog::new().set_directory(&self.pending_root).pick_files() {
+                                opened = Some(files.iter().map(|file| file.display().to_string()).collect::<Vec<_>>());
+                            }
+                        }
+                        let input = ui.add(TextEdit::singleline(&mut self.pattern_input).hint_text("/var/log/myservice/*.jsonl"));
+                        if input.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter)) && !self.pattern_input.trim().is_empty() {
+                            opened = Some(vec![std::mem::take(&mut self.pattern_input)]);
                         }
-                        self.start_index(self.root.clone(), ctx);
+                    });
+                    if let Some(patterns) = opened {
+                        self.index_dirs = false;
+                        self.show_startup_modal = false;
+                        self.open_file_patterns(patterns, ctx);
                     }
                 });
         }
 
+        if self.show_index_warnings && !self.catalog.warnings.is_empty() {
+            let mut open = true;
+            let mut dismiss = false;
+            egui::Window::new("Indexing warnings").open(&mut open).default_width(560.0).show(ctx, |ui| {
+                ui.label("These files could not be read and are missing from the results:");
+                egui::ScrollArea::vertical().max_height(280.0).show(ui, |ui| {
+                    for warning in &self.catalog.warnings {
+                        ui.monospace(warning.path.display().to_string());
+                        ui.colored_label(theme::smoo::RED, &warning.message);
+                        ui.add_space(4.0);
+                    }
+                });
+                if ui.button("Dismiss").clicked() {
+                    dismiss = true;
+                }
+            });
+            if dismiss {
+                self.catalog.warnings.clear();
+            }
+            self.show_index_warnings = open && !dismiss;
+        }
+
         egui::TopBottomPanel::top("top").show(ctx, |ui| {
             ui.horizontal_wrapped(|ui| {
                 if let Some(texture) = &self.logo_texture {
@@ -1430,83 +3493,233 @@ impl eframe::App for App {
                 if ui.button("Change Root…").clicked() {
                     if let Some(dir) = FileDialog::new().set_directory(&self.root).pick_folder() {
                         self.pending_root = dir.clone();
-                        self.root = dir.clone();
-                        if self.live_mode {
-                            self.watch_root(dir.clone());
-                        } else {
-                            self.stop_watch();
-                        }
-                        self.start_index(dir, ctx);
+                        self.root = dir;
+                        self.index_dirs = true;
+                        self.reload_sources(ctx);
+                    }
+                }
+                if ui
+                    .button("Open file(s)…")
+                    .on_hover_text("Index individual log files alongside the root")
+                    .clicked()
+                {
+                    if let Some(files) = FileDialog::new().set_directory(&self.root).pick_files() {
+                        self.open_file_patterns(files.iter().map(|file| file.display().to_string()).collect(), ctx);
                     }
                 }
+                let glob_input = ui.add(
+                    TextEdit::singleline(&mut self.pattern_input)
+                        .hint_text("Add file or glob…")
+                        .desired_width(160.0),
+                );
+                if glob_input.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter)) && !self.pattern_input.trim().is_empty() {
+                    let pattern = std::mem::take(&mut self.pattern_input);
+                    self.open_file_patterns(vec![pattern], ctx);
+                }
                 if ui.button("Reindex").clicked() {
-                    self.start_index(self.root.clone(), ctx);
+                    self.start_index(ctx);
                 }
                 if ui.checkbox(&mut self.live_mode, "Live mode").changed() {
                     if self.live_mode {
-                        self.watch_root(self.root.clone());
+                        self.watch_sources();
                         self.status = "Live mode enabled. Watching for log deltas.".into();
+                        self.apply_row_cap();
                     } else {
                         self.stop_watch();
                         self.status = "Live mode disabled.".into();
                     }
                     ctx.request_repaint();
                 }
+                let follow = ui
+                    .add_enabled(self.live_mode, egui::Checkbox::new(&mut self.follow_tail, "Follow tail"))
+                    .on_hover_text("Keep the newest row in view as live updates arrive. Scrolling pauses it.");
+                if follow.changed() && self.follow_tail {
+                    self.jump_to_tail();
+                }
+                if self.live_mode && !self.follow_tail && ui.button("⤓ Newest").on_hover_text("Jump to the newest row and resume following").clicked() {
+                    self.follow_tail = true;
+                    self.jump_to_tail();
+                }
                 ui.separator();
-                ui.label(RichText::new(self.root.display().to_string()).color(Color32::from_gray(170)));
+                ui.label(RichText::new(self.index_sources().describe()).color(Color32::from_gray(170)));
+                if !self.file_patterns.is_empty() {
+                    let clear = ui
+                        .small_button("✕")
+                        .on_hover_text(format!("Close opened files:\n{}", self.file_patterns.join("\n")));
+                    if clear.clicked() {
+                        self.file_patterns.clear();
+                        self.index_dirs = true;
+                        self.reload_sources(ctx);
+                    }
+                }
                 ui.separator();
                 ui.checkbox(&mut self.sort_desc, "Newest first");
-                if ui.button("Apply sort").clicked() {
+                if ui.button("Apply sort").on_hover_text("Sort by timestamp, clearing any header sort").clicked() {
+                    self.sort_column = None;
                     self.apply_filters();
                 }
                 ui.separator();
                 ui.toggle_value(&mut self.dark_mode, "🌙 Dark");
+                ui.toggle_value(&mut self.compact_rows, "Compact")
+                    .on_hover_text("Shorter rows and smaller text, to fit more lines on screen");
                 ui.separator();
+                ui.toggle_value(&mut self.show_sql_panel, "SQL");
+                ui.toggle_value(&mut self.show_aggregate_panel, "Σ Group by")
+                    .on_hover_text("Count the filtered rows per value of a column");
+                if ui
+                    .add_enabled(!self.filtered.is_empty(), egui::Button::new("Export…"))
+                    .on_hover_text("Save the filtered rows as .csv or .jsonl")
+                    .clicked()
+                {
+                    self.export_filtered();
+                }
+                if ui
+                    .add_enabled(self.db_conn.is_some(), egui::Button::new("Export database…"))
+                    .on_hover_text("Save a copy of the DuckDB `logs` table for the duckdb CLI")
+                    .clicked()
+                {
+                    self.export_database();
+                }
+                ui.separator();
+                if !self.catalog.warnings.is_empty() {
+                    let label = RichText::new(format!(
+                        "⚠ {} warning{}",
+                        self.catalog.warnings.len(),
+                        if self.catalog.warnings.len() == 1 { "" } else { "s" }
+                    ))
+                    .color(theme::smoo::ORANGE);
+                    ui.toggle_value(&mut self.show_index_warnings, label);
+                    ui.separator();
+                }
             });
         });
 
         egui::SidePanel::left("filters").resizable(true).default_width(330.0).show(ctx, |ui| {
             ui.heading("Filters");
             let mut any_filter_lost_focus = false;
-            let r = ui.add(TextEdit::singleline(&mut self.filters.text).hint_text("search across fields"));
+            let mut any_filter_changed = false;
+            let r = ui.add(
+                TextEdit::singleline(&mut self.filters.text)
+                    .id(egui::Id::new(SEARCH_INPUT_ID))
+                    .hint_text("search across fields (/)"),
+            );
             any_filter_lost_focus |= r.lost_focus();
+            any_filter_changed |= r.changed();
             let r = ui.add(TextEdit::singleline(&mut self.filters.level).hint_text("level / LogLevel"));
             any_filter_lost_focus |= r.lost_focus();
+            any_filter_changed |= r.changed();
             let r = ui.add(TextEdit::singleline(&mut self.filters.corr).hint_text("correlationId"));
             any_filter_lost_focus |= r.lost_focus();
+            any_filter_changed |= r.changed();
             let r = ui.add(TextEdit::singleline(&mut self.filters.service).hint_text("service"));
             any_filter_lost_focus |= r.lost_focus();
+            any_filter_changed |= r.changed();
             let r = ui.add(TextEdit::singleline(&mut self.filters.namespace).hint_text("namespace"));
             any_filter_lost_focus |= r.lost_focus();
+            any_filter_changed |= r.changed();
             let r = ui.add(TextEdit::singleline(&mut self.filters.trace).hint_text("traceId"));
             any_filter_lost_focus |= r.lost_focus();
+            any_filter_changed |= r.changed();
             let r = ui.add(TextEdit::singleline(&mut self.filters.request).hint_text("requestId"));
             any_filter_lost_focus |= r.lost_focus();
-            ui.checkbox(&mut self.filters.regex_mode, "Regex mode");
+            any_filter_changed |= r.changed();
+            let r = ui
+                .add(TextEdit::singleline(&mut self.filters.expr).hint_text("http.response.statusCode >= 500"))
+                .on_hover_text("path op value, with ==, !=, >=, <=, >, <, contains, or `path exists`");
+            any_filter_lost_focus |= r.lost_focus();
+            any_filter_changed |= r.changed();
+            ui.horizontal(|ui| {
+                if ui.checkbox(&mut self.filters.regex_mode, "Regex mode").changed() {
+                    any_filter_changed = true;
+                    self.filters.fuzzy &= !self.filters.regex_mode;
+                }
+                let fuzzy = ui
+                    .checkbox(&mut self.filters.fuzzy, "Fuzzy")
+                    .on_hover_text("Match the search text as a subsequence, typos between letters allowed, and list the best matches first. Turns regex mode off; the two are mutually exclusive.");
+                if fuzzy.changed() {
+                    any_filter_changed = true;
+                    self.filters.regex_mode &= !self.filters.fuzzy;
+                }
+            });
+            ui.checkbox(&mut self.live_filter, "Filter as I type")
+                .on_hover_text("Re-apply filters shortly after the last edit. Turn off for very large catalogs.");
+            if any_filter_changed && self.live_filter {
+                self.filter_edited_at = Some(Instant::now());
+            }
             let enter_pressed = ui.input(|i| i.key_pressed(Key::Enter));
             if ui.button("Apply filters").clicked() || (any_filter_lost_focus && enter_pressed) {
                 self.apply_filters();
             }
+            self.render_filter_presets(ui);
 
             ui.separator();
             ui.heading("Pagination");
             ui.add(egui::Slider::new(&mut self.page_size, 50..=3000).text("rows/page"));
+            let total_pages = self.filtered.len().div_ceil(self.page_size.max(1)).max(1);
+            // Also covers a larger page size or a shrunken result set leaving the page past the end.
+            self.page = self.page.min(total_pages - 1);
             ui.horizontal(|ui| {
-                if ui.button("Prev").clicked() && self.page > 0 {
+                if ui.add_enabled(self.page > 0, egui::Button::new("⏮ First")).clicked() {
+                    self.page = 0;
+                }
+                if ui.add_enabled(self.page > 0, egui::Button::new("Prev")).clicked() {
                     self.page -= 1;
                 }
-                let total_pages = ((self.filtered.len() + self.page_size - 1) / self.page_size.max(1)).max(1);
-                ui.label(format!("Page {} / {}", self.page + 1, total_pages));
-                if ui.button("Next").clicked() && self.page + 1 < total_pages {
+                let mut page_number = self.page + 1;
+                let response = ui.add(egui::DragValue::new(&mut page_number).range(1..=total_pages).speed(0.2));
+                if response.changed() {
+                    self.page = page_number.clamp(1, total_pages) - 1;
+                }
+                ui.label(format!("/ {total_pages}"));
+                if ui.add_enabled(self.page + 1 < total_pages, egui::Button::new("Next")).clicked() {
                     self.page += 1;
                 }
+                if ui.add_enabled(self.page + 1 < total_pages, egui::Button::new("Last ⏭")).clicked() {
+                    self.page = total_pages - 1;
+                }
             });
+            if self.filtered.is_empty() {
+                ui.label("No rows");
+            } else {
+                let start = self.page * self.page_size;
+                let end = (start + self.page_size).min(self.filtered.len());
+                ui.label(format!("rows {}–{} of {}", format_count(start + 1), format_count(end), format_count(self.filtered.len())));
+            }
+
+            ui.separator();
+            ui.heading("Memory");
+            let cap = ui
+                .horizontal(|ui| {
+                    let cap = ui.add_enabled(self.live_mode, egui::DragValue::new(&mut self.max_rows).speed(1000.0).range(0..=usize::MAX));
+                    ui.label("max rows (live mode)");
+                    cap
+                })
+                .inner
+                .on_hover_text("Live mode evicts the oldest rows beyond this cap. 0 keeps everything.");
+            // Wait for a drag to finish so each step doesn't rewrite DuckDB.
+            if (cap.changed() && !cap.dragged()) || cap.drag_stopped() {
+                self.apply_row_cap();
+            }
+
+            ui.separator();
+            ui.heading("Parsing");
+            let strict = ui.checkbox(&mut self.strict_parse, "Strict SmooAI lines").on_hover_text(
+                "Read lines with the smooai-logger shape into typed fields, so errorDetails shows names and causes. Other lines are parsed as before.",
+            );
+            if strict.changed() && !self.indexing {
+                self.start_index(ctx);
+            }
 
             ui.separator();
             ui.heading("Context");
             ui.add(egui::Slider::new(&mut self.ctx_before, 0..=50).text("lines before"));
             ui.add(egui::Slider::new(&mut self.ctx_after, 0..=50).text("lines after"));
 
+            ui.separator();
+            ui.heading("Expanded rows");
+            ui.add(egui::Slider::new(&mut self.inline_json_depth, 0..=10).text("JSON depth"))
+                .on_hover_text("Objects and arrays nested deeper than this start collapsed. 0 expands everything.");
+
             ui.separator();
             ui.heading("Columns");
             ui.label("Select extra fields to render on demand.");
@@ -1569,6 +3782,8 @@ impl eframe::App for App {
             }
         });
 
+        self.hovered_cell_value = None;
+
         egui::CentralPanel::default().show(ctx, |ui| {
             if self.indexing {
                 ui.vertical_centered(|ui| {
@@ -1584,6 +3799,15 @@ impl eframe::App for App {
                     }
                     ui.add_space(12.0);
                     ui.label(&self.status);
+                    ui.add_space(8.0);
+                    let cancelling = self.index_cancel.as_ref().is_some_and(|cancel| cancel.load(Ordering::SeqCst));
+                    if ui
+                        .add_enabled(!cancelling, egui::Button::new("✕ Cancel"))
+                        .on_hover_text("Stop indexing and keep the logs that were loaded before")
+                        .clicked()
+                    {
+                        self.cancel_index();
+                    }
                 });
                 return;
             }
@@ -1593,6 +3817,26 @@ impl eframe::App for App {
                 return;
             }
 
+            if self.show_sql_panel {
+                egui::TopBottomPanel::top("sql_panel")
+                    .resizable(true)
+                    .default_height(240.0)
+                    .min_height(120.0)
+                    .show_inside(ui, |ui| {
+                        self.render_sql_panel(ui, ctx);
+                    });
+            }
+
+            if self.show_aggregate_panel {
+                egui::SidePanel::right("aggregate_panel")
+                    .resizable(true)
+                    .default_width(380.0)
+                    .min_width(240.0)
+                    .show_inside(ui, |ui| {
+                        self.render_aggregate_panel(ui);
+                    });
+            }
+
             // Split the central area into resizable top (table) and bottom (context) sections
             egui::TopBottomPanel::bottom("context_panel")
                 .resizable(true)
@@ -1603,12 +3847,23 @@ impl eframe::App for App {
                 });
 
             egui::CentralPanel::default().show_inside(ui, |ui| {
+                self.render_level_summary(ui);
                 self.render_log_table(ui, ctx);
             });
         });
+
+        self.render_json_windows(ctx);
+        self.handle_copy_shortcut(ctx);
     }
 }
 
+/// Splits a `file:line` permalink; the line is 1-based.
+fn parse_permalink(target: &str) -> Option<(PathBuf, usize)> {
+    let (path, line) = target.trim().rsplit_once(':')?;
+    let line = line.parse::<usize>().ok().filter(|line| *line > 0)?;
+    (!path.is_empty()).then(|| (PathBuf::from(path), line))
+}
+
 fn flatten_json_map(value: &Value) -> BTreeMap<String, String> {
     let mut map = BTreeMap::new();
     if let Value::Object(obj) = value {
@@ -1662,6 +3917,7 @@ fn resolve_row_value(row: &Row, key: &str) -> String {
         "namespace" => row.namespace.clone().unwrap_or_default(),
         "traceId" => row.trace_id.clone().unwrap_or_default(),
         "requestId" => row.request_id.clone().unwrap_or_default(),
+        "errorDetails" if !row.error_details.is_empty() => summarize_errors(&row.error_details),
         "error" | "errorDetails" => row
             .flat
             .get(key)
@@ -1672,6 +3928,177 @@ fn resolve_row_value(row: &Row, key: &str) -> String {
     }
 }
 
+fn export_jsonl<'a>(path: &Path, rows: impl Iterator<Item = &'a Row>) -> Result<()> {
+    let mut out = BufWriter::new(File::create(path).with_context(|| format!("create {path:?}"))?);
+    for row in rows {
+        writeln!(out, "{}", row.raw_json.trim_end())?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn export_csv<'a>(path: &Path, columns: &[String], rows: impl Iterator<Item = &'a Row>) -> Result<()> {
+    let mut out = BufWriter::new(File::create(path).with_context(|| format!("create {path:?}"))?);
+    let header: Vec<String> = columns.iter().map(|column| csv_field(column)).collect();
+    writeln!(out, "{}", header.join(","))?;
+    for row in rows {
+        let fields: Vec<String> = columns.iter().map(|column| csv_field(&resolve_row_value(row, column))).collect();
+        writeln!(out, "{}", fields.join(","))?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Quotes a CSV field (RFC 4180) when it contains a delimiter, quote or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Orders two rows by `key`: `time` by the parsed timestamp, values that both parse as
+/// numbers numerically, everything else lexicographically.
+fn compare_rows_by_column(a: &Row, b: &Row, key: &str) -> std::cmp::Ordering {
+    if key == "time" {
+        return a.ts.cmp(&b.ts);
+    }
+    let left = resolve_row_value(a, key);
+    let right = resolve_row_value(b, key);
+    match (left.trim().parse::<f64>(), right.trim().parse::<f64>()) {
+        (Ok(l), Ok(r)) => l.total_cmp(&r),
+        _ => left.cmp(&right),
+    }
+}
+
+/// One-line summary of typed error details: `Name: message ← cause ← cause`, with
+/// multiple errors separated by ` | `.
+fn summarize_errors(errors: &[LoggedError]) -> String {
+    errors
+        .iter()
+        .map(|error| {
+            let mut summary = format!("{}: {}", error.name, error.message);
+            for cause in &error.causes {
+                summary.push_str(" ← ");
+                summary.push_str(cause);
+            }
+            summary
+        })
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+/// Reads `errorDetails` leniently: entries from other SmooAI loggers may lack a `name`
+/// or carry causes as objects rather than strings.
+fn error_details_from_json(value: &Value) -> Vec<LoggedError> {
+    let Some(details) = value.get(keys::ERROR_DETAILS).and_then(Value::as_array) else {
+        return Vec::new();
+    };
+    let text = |entry: &Value, key: &str| entry.get(key).and_then(Value::as_str).map(str::to_string);
+    details
+        .iter()
+        .filter(|entry| entry.is_object())
+        .map(|entry| LoggedError {
+            message: text(entry, "message").unwrap_or_default(),
+            name: text(entry, "name").unwrap_or_else(|| "Error".into()),
+            stack: text(entry, "stack"),
+            causes: entry
+                .get("causes")
+                .and_then(Value::as_array)
+                .map(|causes| {
+                    causes
+                        .iter()
+                        .map(|cause| match cause {
+                            Value::String(cause) => cause.clone(),
+                            other => text(other, "message").unwrap_or_else(|| other.to_string()),
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+            code: entry.get("code").and_then(|code| match code {
+                Value::String(code) => Some(code.clone()),
+                Value::Number(code) => Some(code.to_string()),
+                _ => None,
+            }),
+        })
+        .collect()
+}
+
+fn render_error_details(ui: &mut egui::Ui, row_idx: usize, errors: &[LoggedError]) {
+    let red = theme::smoo::RED;
+    ui.heading(RichText::new("Error").color(red));
+    for (idx, error) in errors.iter().enumerate() {
+        ui.horizontal_wrapped(|ui| {
+            ui.label(RichText::new(&error.name).color(red).strong());
+            ui.label(RichText::new(&error.message).color(red));
+            if let Some(code) = &error.code {
+                ui.label(RichText::new(format!("[{code}]")).color(red).monospace());
+            }
+        });
+        if let Some(stack) = error.stack.as_deref().filter(|stack| !stack.trim().is_empty()) {
+            egui::CollapsingHeader::new(RichText::new("Stack").color(red))
+                .id_source(("error-stack", row_idx, idx))
+                .show(ui, |ui| {
+                    ui.scope(|ui| {
+                        ui.style_mut().wrap_mode = Some(TextWrapMode::Extend);
+                        ui.monospace(stack);
+                    });
+                });
+        }
+        if !error.causes.is_empty() {
+            ui.label(RichText::new("Caused by").color(red));
+            ui.indent(("error-causes", row_idx, idx), |ui| {
+                for cause in &error.causes {
+                    ui.label(RichText::new(format!("• {cause}")).color(red));
+                }
+            });
+        }
+    }
+}
+
+/// Headers the logger or the infrastructure adds per request; replaying them would tie
+/// the reproduction to the original request's correlation.
+const CURL_SKIPPED_HEADERS: &[&str] = &["x-correlation-id", "traceparent", "tracestate", "x-amzn-trace-id", "host", "content-length"];
+
+/// Rebuilds a logged `http.request` (method, protocol + hostname + path + queryString,
+/// headers and body) as a `curl` command. Returns `None` without a path.
+fn curl_from_http_request(request: &Value) -> Option<String> {
+    let text = |key: &str| request.get(key).and_then(Value::as_str).filter(|value| !value.is_empty());
+    let path = text("path")?;
+    let method = text("method").unwrap_or("GET").to_ascii_uppercase();
+    let protocol = text("protocol").unwrap_or("https").trim_end_matches("://").trim_end_matches(':');
+    let hostname = text("hostname").unwrap_or("localhost");
+    let mut url = format!("{protocol}://{hostname}{path}");
+    if let Some(query) = text("queryString") {
+        if !query.starts_with('?') {
+            url.push('?');
+        }
+        url.push_str(query);
+    }
+
+    let mut command = format!("curl -X {method} {}", shell_quote(&url));
+    if let Some(headers) = request.get("headers").and_then(Value::as_object) {
+        for (name, value) in headers {
+            if CURL_SKIPPED_HEADERS.iter().any(|skipped| name.eq_ignore_ascii_case(skipped)) {
+                continue;
+            }
+            let value = value.as_str().map(str::to_string).unwrap_or_else(|| value.to_string());
+            command.push_str(&format!(" \\\n  -H {}", shell_quote(&format!("{name}: {value}"))));
+        }
+    }
+    match request.get("body") {
+        None | Some(Value::Null) => {}
+        Some(Value::String(body)) => command.push_str(&format!(" \\\n  --data {}", shell_quote(body))),
+        Some(body) => command.push_str(&format!(" \\\n  --data {}", shell_quote(&body.to_string()))),
+    }
+    Some(command)
+}
+
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
 fn format_json_for_display(raw: &str) -> (String, usize) {
     if let Ok(value) = serde_json::from_str::<Value>(raw) {
         if let Ok(pretty) = serde_json::to_string_pretty(&value) {
@@ -1762,24 +4189,31 @@ fn open_file_with_app(_path: &Path, _app: &Path) -> Result<()> {
     Err(anyhow!("opening files with specific app is not supported on this platform"))
 }
 
-fn index_single_file(file_id: usize, path: &Path, extractor: &Extractor) -> Result<(Vec<String>, Vec<Row>)> {
+fn index_single_file(file_id: usize, path: &Path, extractor: &Extractor) -> Result<(Vec<String>, Vec<Row>, AppendCursor)> {
     let mmap = mmap_file(path)?;
     let lines = scan_lines(&mmap);
     let sanitized_lines = sanitize_lines(&mmap, &lines);
     let (rows, _columns) = parse_rows(file_id, path, &lines, &sanitized_lines, extractor);
-    Ok((sanitized_lines, rows))
+    let cursor = AppendCursor::new(&mmap, &lines, &rows, 0, 0);
+    Ok((sanitized_lines, rows, cursor))
 }
 
-fn index_monorepo(root: &Path, progress_tx: Option<mpsc::Sender<IndexEvent>>) -> Result<Catalog> {
-    let log_dirs = find_smooai_log_dirs(root);
+/// Indexes every source file, writing the rows to DuckDB when `use_duckdb` is set. Returns
+/// `None` if `cancel` was set before the index finished.
+fn index_sources(
+    sources: &IndexSources,
+    strict: bool,
+    use_duckdb: bool,
+    progress_tx: Option<mpsc::Sender<IndexEvent>>,
+    cancel: &AtomicBool,
+) -> Result<Option<Catalog>> {
+    let files = sources.files();
     let mut catalog = Catalog::default();
 
-    if log_dirs.is_empty() {
-        return Ok(catalog);
+    if files.is_empty() {
+        return Ok(Some(catalog));
     }
 
-    let files: Vec<PathBuf> = log_dirs.iter().flat_map(|dir| list_log_files(dir)).collect();
-
     let total_files = files.len();
     if let Some(tx) = &progress_tx {
         let _ = tx.send(IndexEvent::Progress {
@@ -1788,56 +4222,98 @@ fn index_monorepo(root: &Path, progress_tx: Option<mpsc::Sender<IndexEvent>>) ->
         });
     }
 
-    let extractor = Extractor::new();
+    let extractor = Extractor::new(strict);
 
     let processed_files = AtomicUsize::new(0);
     let mut tmp_files: Vec<ParsedFile> = files
         .par_iter()
         .enumerate()
         .map(|(file_id, path)| {
-            let mmap = mmap_file(path);
-            if mmap.is_err() {
-                return (path.clone(), Vec::new(), Vec::new(), BTreeSet::new());
+            let report_progress = || {
+                if let Some(tx) = &progress_tx {
+                    let current = processed_files.fetch_add(1, Ordering::SeqCst) + 1;
+                    let _ = tx.send(IndexEvent::Progress {
+                        processed: current.min(total_files),
+                        total: total_files,
+                    });
+                }
+            };
+            if cancel.load(Ordering::Relaxed) {
+                // Skip the remaining files; the result is thrown away below.
+                return (path.clone(), Vec::new(), Vec::new(), BTreeSet::new(), None, None);
             }
-            let mmap = mmap.unwrap();
+            let mmap = match mmap_file(path) {
+                Ok(mmap) => mmap,
+                Err(error) => {
+                    // Keep an empty entry so file ids stay aligned, and remember why it is empty.
+                    report_progress();
+                    return (path.clone(), Vec::new(), Vec::new(), BTreeSet::new(), None, Some(format!("{error:#}")));
+                }
+            };
             let lines = scan_lines(&mmap);
             let sanitized_lines = sanitize_lines(&mmap, &lines);
             let (rows, columns) = parse_rows(file_id, path, &lines, &sanitized_lines, &extractor);
-            if let Some(tx) = &progress_tx {
-                let current = processed_files.fetch_add(1, Ordering::SeqCst) + 1;
-                let _ = tx.send(IndexEvent::Progress {
-                    processed: current.min(total_files),
-                    total: total_files,
-                });
-            }
-            (path.clone(), sanitized_lines, rows, columns)
+            let cursor = AppendCursor::new(&mmap, &lines, &rows, 0, 0);
+            report_progress();
+            (path.clone(), sanitized_lines, rows, columns, Some(cursor), None)
         })
         .collect();
 
+    if cancel.load(Ordering::SeqCst) {
+        return Ok(None);
+    }
+
     tmp_files.sort_by(|a, b| a.0.cmp(&b.0));
 
     let mut column_set = BTreeSet::new();
-    for (path, sanitized_lines, mut rows, cols) in tmp_files {
+    let mut all_rows = Vec::new();
+    for (path, sanitized_lines, mut rows, cols, cursor, error) in tmp_files {
+        if let Some(message) = error {
+            catalog.warnings.push(IndexWarning { path: path.clone(), message });
+        }
         column_set.extend(cols);
-        catalog.files.push(FileEntry { path, sanitized_lines });
-        catalog.rows.append(&mut rows);
+        catalog.files.push(FileEntry { path, sanitized_lines, cursor });
+        all_rows.append(&mut rows);
     }
 
-    catalog.rows.sort_by(|left, right| {
-        left.ts
-            .cmp(&right.ts)
-            .then_with(|| left.file_id.cmp(&right.file_id))
-            .then_with(|| left.line_start.cmp(&right.line_start))
-    });
+    all_rows.sort_by(row_order);
+    catalog.rows = Arc::new(all_rows);
 
     catalog.columns = column_set.into_iter().collect();
 
-    catalog.duckdb_path = Some(populate_duckdb(&catalog.rows)?);
+    if use_duckdb {
+        let Some(duckdb_path) = populate_duckdb_cancellable(&catalog.rows, cancel)? else {
+            return Ok(None);
+        };
+        catalog.duckdb_path = Some(duckdb_path);
+    }
 
-    Ok(catalog)
+    Ok(Some(catalog))
 }
 
+/// Writes `rows` to a new DuckDB file.
 fn populate_duckdb(rows: &[Row]) -> Result<PathBuf> {
+    let (conn, db_path) = create_duckdb()?;
+    // Nothing sets this flag, so every row is written.
+    append_duckdb_rows(&conn, rows, 0, &AtomicBool::new(false))?;
+    Ok(db_path)
+}
+
+/// Writes `rows` to a new DuckDB file. Returns `None`, with the partial file removed, if
+/// `cancel` is set part way through.
+fn populate_duckdb_cancellable(rows: &[Row], cancel: &AtomicBool) -> Result<Option<PathBuf>> {
+    let (conn, db_path) = create_duckdb()?;
+    if !append_duckdb_rows(&conn, rows, 0, cancel)? {
+        drop(conn);
+        let _ = std::fs::remove_file(&db_path);
+        let _ = std::fs::remove_file(db_path.with_extension("duckdb.wal"));
+        return Ok(None);
+    }
+    Ok(Some(db_path))
+}
+
+/// Creates a DuckDB file in the temp directory with an empty `logs` table.
+fn create_duckdb() -> Result<(Connection, PathBuf)> {
     let mut db_path = std::env::temp_dir();
     let unique = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis();
     db_path.push(format!("smooai-log-viewer-{unique}.duckdb"));
@@ -1864,17 +4340,31 @@ fn populate_duckdb(rows: &[Row]) -> Result<PathBuf> {
         )",
         [],
     )?;
+    Ok((conn, db_path))
+}
 
-    for (row_id, row) in rows.iter().enumerate() {
-        let ts_string = row.ts.map(|t| t.to_rfc3339());
-        let flat_json = serde_json::to_string(&row.flat).unwrap_or_else(|_| "{}".into());
-        conn.execute(
-            "INSERT INTO logs (
-                row_id, file_id, line_start, line_end, ts, ts_text, level, corr, name, msg,
-                service, namespace, trace_id, request_id, raw_json, flat_json
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
-            params![
-                row_id as i64,
+/// Appends `rows` to the `logs` table, numbering them from `first_row_id`. Returns `false`
+/// if `cancel` was set before every row was written.
+fn append_duckdb_rows(conn: &Connection, rows: &[Row], first_row_id: usize, cancel: &AtomicBool) -> Result<bool> {
+    // Rows go through the appender in chunks: each chunk's text columns are prepared in
+    // parallel, and only one chunk of prepared values is held at a time.
+    let mut appender = conn.appender("logs").context("open duckdb appender")?;
+    for (chunk_index, chunk) in rows.chunks(DUCKDB_INSERT_CHUNK).enumerate() {
+        if cancel.load(Ordering::Relaxed) {
+            return Ok(false);
+        }
+        let prepared: Vec<(Option<String>, String)> = chunk
+            .par_iter()
+            .map(|row| {
+                let ts_string = row.ts.map(|t| t.to_rfc3339());
+                let flat_json = serde_json::to_string(&row.flat).unwrap_or_else(|_| "{}".into());
+                (ts_string, flat_json)
+            })
+            .collect();
+        let chunk_row_id = first_row_id + chunk_index * DUCKDB_INSERT_CHUNK;
+        for (offset, (row, (ts_string, flat_json))) in chunk.iter().zip(&prepared).enumerate() {
+            appender.append_row(params![
+                (chunk_row_id + offset) as i64,
                 row.file_id as i64,
                 row.line_start as i64,
                 row.line_end as i64,
@@ -1890,11 +4380,78 @@ fn populate_duckdb(rows: &[Row]) -> Result<PathBuf> {
                 row.request_id.as_deref(),
                 row.raw_json,
                 flat_json,
-            ],
-        )?;
+            ])?;
+        }
     }
+    appender.flush().context("flush duckdb appender")?;
+    Ok(true)
+}
 
-    Ok(db_path)
+/// Makes the `logs` table match `rows`, where `rows[i]` has `row_id` `row_offset + i`:
+/// ids below the offset are deleted and the rows from `changed_from` on are rewritten.
+fn update_duckdb_rows(conn: &Connection, rows: &[Row], row_offset: usize, changed_from: usize) -> Result<()> {
+    conn.execute(
+        "DELETE FROM logs WHERE row_id < ? OR row_id >= ?",
+        params![row_offset as i64, (row_offset + changed_from) as i64],
+    )
+    .context("delete replaced duckdb rows")?;
+    append_duckdb_rows(conn, &rows[changed_from.min(rows.len())..], row_offset + changed_from, &AtomicBool::new(false))?;
+    Ok(())
+}
+
+fn execute_sql(conn: &Connection, sql: &str) -> std::result::Result<SqlResult, String> {
+    let started = Instant::now();
+    let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
+    let mut rows = stmt.query([]).map_err(|e| e.to_string())?;
+    let columns = rows.as_ref().map(|stmt| stmt.column_names()).unwrap_or_default();
+
+    let mut values = Vec::new();
+    let mut truncated = false;
+    while let Some(row) = rows.next().map_err(|e| e.to_string())? {
+        if values.len() >= SQL_MAX_ROWS {
+            truncated = true;
+            break;
+        }
+        let cells = (0..columns.len())
+            .map(|idx| match row.get::<_, duckdb::types::Value>(idx) {
+                Ok(value) => duckdb_value_to_string(value),
+                Err(error) => format!("<{error}>"),
+            })
+            .collect();
+        values.push(cells);
+    }
+
+    Ok(SqlResult {
+        columns,
+        rows: values,
+        truncated,
+        elapsed: started.elapsed(),
+    })
+}
+
+fn duckdb_value_to_string(value: duckdb::types::Value) -> String {
+    use duckdb::types::Value as Duck;
+    match value {
+        Duck::Null => "NULL".into(),
+        Duck::Boolean(b) => b.to_string(),
+        Duck::TinyInt(n) => n.to_string(),
+        Duck::SmallInt(n) => n.to_string(),
+        Duck::Int(n) => n.to_string(),
+        Duck::BigInt(n) => n.to_string(),
+        Duck::HugeInt(n) => n.to_string(),
+        Duck::UTinyInt(n) => n.to_string(),
+        Duck::USmallInt(n) => n.to_string(),
+        Duck::UInt(n) => n.to_string(),
+        Duck::UBigInt(n) => n.to_string(),
+        Duck::Float(n) => n.to_string(),
+        Duck::Double(n) => n.to_string(),
+        Duck::Decimal(n) => n.to_string(),
+        Duck::Text(text) | Duck::Enum(text) => text,
+        Duck::Timestamp(unit, raw) => DateTime::<Utc>::from_timestamp_micros(unit.to_micros(raw))
+            .map(|ts| ts.to_rfc3339())
+            .unwrap_or_else(|| raw.to_string()),
+        other => format!("{other:?}"),
+    }
 }
 
 fn parse_rows(file_id: usize, _path: &Path, lines: &[LineHeader], sanitized_lines: &[String], extractor: &Extractor) -> (Vec<Row>, BTreeSet<String>) {
@@ -1937,7 +4494,11 @@ fn parse_rows(file_id: usize, _path: &Path, lines: &[LineHeader], sanitized_line
             (json!({ "msg": raw.clone() }), raw)
         };
 
-        let (ts, level, corr, name, msg, service, namespace, trace_id, request_id) = extractor.extract(&value);
+        let (fields, error_details) = match extractor.parse_strict(&value) {
+            Some(line) => extractor.extract_typed(line),
+            None => (extractor.extract(&value), Vec::new()),
+        };
+        let (ts, level, corr, name, msg, service, namespace, trace_id, request_id) = fields;
         let flat = flatten_json_map(&value);
         for key in flat.keys() {
             columns.insert(key.clone());
@@ -1958,6 +4519,7 @@ fn parse_rows(file_id: usize, _path: &Path, lines: &[LineHeader], sanitized_line
             request_id,
             flat,
             raw_json: raw_text.clone(),
+            error_details,
         });
 
         idx = end_idx + 1;
@@ -1974,6 +4536,48 @@ fn shorten_for_display(input: &str, max: usize) -> String {
     format!("{}...", trimmed)
 }
 
+/// The text the search box matches against: the base fields, then every flattened value.
+fn row_haystack(row: &Row) -> String {
+    let mut haystack = String::new();
+    let base = [&row.msg, &row.corr, &row.level, &row.service, &row.namespace, &row.trace_id, &row.request_id];
+    for value in base.into_iter().flatten().chain(row.flat.values()) {
+        haystack.push_str(value);
+        haystack.push(' ');
+    }
+    haystack
+}
+
+/// fzf-style score of `query` (lowercase) in `haystack`, or `None` unless all of its
+/// characters appear in order. Runs of consecutive matches and matches at the start of a
+/// word score higher; gaps between matches cost a little.
+fn fuzzy_score(query: &str, haystack: &str) -> Option<i64> {
+    let mut wanted = query.chars().peekable();
+    let mut score = 0i64;
+    let mut streak = 0i64;
+    let mut gap: Option<i64> = None;
+    let mut previous: Option<char> = None;
+    for ch in haystack.chars() {
+        let Some(&next) = wanted.peek() else {
+            break;
+        };
+        if ch.to_lowercase().eq(std::iter::once(next)) {
+            wanted.next();
+            streak += 1;
+            score += 10 + 5 * streak;
+            if previous.is_none_or(|previous| !previous.is_alphanumeric()) {
+                score += 8;
+            }
+            score -= gap.unwrap_or(0).min(10);
+            gap = Some(0);
+        } else {
+            streak = 0;
+            gap = gap.map(|gap| gap + 1);
+        }
+        previous = Some(ch);
+    }
+    wanted.peek().is_none().then_some(score)
+}
+
 fn levenshtein(left: &str, right: &str) -> usize {
     if left == right {
         return 0;
@@ -2026,8 +4630,7 @@ fn strip_ansi_codes(input: &[u8]) -> Vec<u8> {
     output
 }
 
-fn sanitize_lines(mmap: &Mmap, headers: &[LineHeader]) -> Vec<String> {
-    let bytes = &mmap[..];
+fn sanitize_lines(bytes: &[u8], headers: &[LineHeader]) -> Vec<String> {
     let mut lines = Vec::with_capacity(headers.len());
     for header in headers {
         let start = header.offset as usize;
@@ -2052,25 +4655,47 @@ fn find_smooai_log_dirs(root: &Path) -> Vec<PathBuf> {
         .collect()
 }
 
+/// Expands opened paths and glob patterns (`*`, `?`, `[...]`) to the files that exist now.
+fn resolve_file_patterns(patterns: &[String]) -> Vec<PathBuf> {
+    let mut files = BTreeSet::new();
+    for pattern in patterns {
+        if pattern.contains(['*', '?', '[']) {
+            if let Ok(paths) = glob::glob(pattern) {
+                files.extend(paths.filter_map(|path| path.ok()).filter(|path| path.is_file()));
+            }
+        } else {
+            let path = PathBuf::from(pattern);
+            if path.is_file() {
+                files.insert(path);
+            }
+        }
+    }
+    files.into_iter().collect()
+}
+
 fn list_log_files(dir: &Path) -> Vec<PathBuf> {
     WalkDir::new(dir)
         .max_depth(1)
         .into_iter()
         .filter_map(|entry| entry.ok())
-        .filter(|entry| {
-            entry.file_type().is_file()
-                && entry
-                    .path()
-                    .extension()
-                    .map(|ext| ext == "ansi" || ext == "log" || ext == "json" || ext == "jsonl")
-                    .unwrap_or(false)
-        })
+        .filter(|entry| entry.file_type().is_file() && has_log_extension(entry.path()))
         .map(|entry| entry.path().to_path_buf())
         .collect()
 }
 
-fn scan_lines(mmap: &Mmap) -> Vec<LineHeader> {
-    let bytes = &mmap[..];
+/// `.ansi`, `.log`, `.json` and `.jsonl` files, plus their gzipped `.gz` rotations.
+fn has_log_extension(path: &Path) -> bool {
+    let path = if is_gzip(path) {
+        Path::new(path.file_stem().unwrap_or_default())
+    } else {
+        path
+    };
+    path.extension()
+        .map(|ext| ext == "ansi" || ext == "log" || ext == "json" || ext == "jsonl")
+        .unwrap_or(false)
+}
+
+fn scan_lines(bytes: &[u8]) -> Vec<LineHeader> {
     let mut lines = Vec::with_capacity(1024);
     let mut start = 0usize;
 
@@ -2096,9 +4721,39 @@ fn scan_lines(mmap: &Mmap) -> Vec<LineHeader> {
     lines
 }
 
-fn mmap_file(path: &Path) -> Result<Mmap> {
+/// Contents of a log file: memory-mapped, or decompressed into memory for `.gz` files.
+enum LogBytes {
+    Mapped(Mmap),
+    Decompressed(Vec<u8>),
+}
+
+impl std::ops::Deref for LogBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            LogBytes::Mapped(mmap) => mmap,
+            LogBytes::Decompressed(bytes) => bytes,
+        }
+    }
+}
+
+fn mmap_file(path: &Path) -> Result<LogBytes> {
     let file = File::open(path).with_context(|| format!("open {path:?}"))?;
-    unsafe { Mmap::map(&file).context("mmap") }
+    if is_gzip(path) {
+        // Stream straight from the file so the compressed bytes are never held in memory too.
+        let capacity = file.metadata().map(|metadata| metadata.len() as usize * 4).unwrap_or(0);
+        let mut bytes = Vec::with_capacity(capacity);
+        MultiGzDecoder::new(BufReader::new(file))
+            .read_to_end(&mut bytes)
+            .with_context(|| format!("decompress {path:?}"))?;
+        return Ok(LogBytes::Decompressed(bytes));
+    }
+    unsafe { Mmap::map(&file).map(LogBytes::Mapped).context("mmap") }
+}
+
+fn is_gzip(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "gz")
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -2142,7 +4797,94 @@ fn render_json_node(ui: &mut egui::Ui, label: String, value: &Value) {
             });
         }
         _ => {
-            ui.label(format!("{label}: {}", value_to_string(value)));
+            let color = match value {
+                Value::String(_) => theme::smoo::GREEN,
+                Value::Number(_) => theme::smoo::ORANGE,
+                Value::Bool(_) => theme::smoo::BLUE_400,
+                _ => theme::smoo::GRAY_500,
+            };
+            ui.horizontal_wrapped(|ui| {
+                ui.spacing_mut().item_spacing.x = 0.0;
+                ui.label(format!("{label}: "));
+                ui.label(RichText::new(value_to_string(value)).color(color));
+            });
+        }
+    }
+}
+
+fn inline_json_is_open(depth: usize, max_depth: usize) -> bool {
+    max_depth == 0 || depth < max_depth
+}
+
+fn inline_json_placeholder(value: &Value) -> String {
+    match value {
+        Value::Object(_) => "{…}".to_string(),
+        Value::Array(items) => format!("[…{}]", items.len()),
+        _ => value.to_string(),
+    }
+}
+
+/// Counts the lines the inline JSON tree occupies with its default open state, used to size expanded rows.
+fn inline_json_line_count(value: &Value, depth: usize, max_depth: usize) -> usize {
+    let children: Vec<&Value> = match value {
+        Value::Object(map) => map.values().collect(),
+        Value::Array(items) => items.iter().collect(),
+        _ => return 1,
+    };
+    let mut lines = 0;
+    for child in children {
+        lines += 1;
+        if matches!(child, Value::Object(_) | Value::Array(_)) && inline_json_is_open(depth + 1, max_depth) {
+            lines += inline_json_line_count(child, depth + 1, max_depth);
+        }
+    }
+    lines.max(1)
+}
+
+fn render_inline_json(ui: &mut egui::Ui, id: egui::Id, value: &Value, max_depth: usize) {
+    match value {
+        Value::Object(map) => {
+            for (key, val) in map {
+                render_inline_json_node(ui, id.with(key), key, val, 1, max_depth);
+            }
+        }
+        Value::Array(items) => {
+            for (idx, val) in items.iter().enumerate() {
+                render_inline_json_node(ui, id.with(idx), &format!("[{idx}]"), val, 1, max_depth);
+            }
+        }
+        _ => {
+            ui.monospace(value.to_string());
+        }
+    }
+}
+
+fn render_inline_json_node(ui: &mut egui::Ui, id: egui::Id, label: &str, value: &Value, depth: usize, max_depth: usize) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            let header = RichText::new(format!("{label}: {}", inline_json_placeholder(value))).monospace();
+            egui::CollapsingHeader::new(header)
+                .id_source(id)
+                .default_open(inline_json_is_open(depth, max_depth))
+                .show(ui, |ui| {
+                    for (key, val) in map {
+                        render_inline_json_node(ui, id.with(key), key, val, depth + 1, max_depth);
+                    }
+                });
+        }
+        Value::Array(items) if !items.is_empty() => {
+            let header = RichText::new(format!("{label}: {}", inline_json_placeholder(value))).monospace();
+            egui::CollapsingHeader::new(header)
+                .id_source(id)
+                .default_open(inline_json_is_open(depth, max_depth))
+                .show(ui, |ui| {
+                    for (idx, val) in items.iter().enumerate() {
+                        render_inline_json_node(ui, id.with(idx), &format!("[{idx}]"), val, depth + 1, max_depth);
+                    }
+                });
+        }
+        _ => {
+            ui.monospace(format!("{label}: {value}"));
         }
     }
 }
@@ -2193,6 +4935,347 @@ fn main() -> Result<()> {
         viewport,
         ..Default::default()
     };
-    eframe::run_native("Smoo AI Log Viewer", native_options, Box::new(|_cc| Ok(Box::new(App::default())))).map_err(|err| anyhow!(err.to_string()))?;
+    eframe::run_native("Smoo AI Log Viewer", native_options, Box::new(|cc| Ok(Box::new(App::new(cc))))).map_err(|err| anyhow!(err.to_string()))?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log_line(second: u32, msg: &str) -> String {
+        format!("{{\"time\":\"2026-01-01T00:00:{second:02}Z\",\"level\":\"info\",\"msg\":\"{msg}\"}}\n")
+    }
+
+    fn parse(bytes: &[u8], file_id: usize) -> (Vec<LineHeader>, Vec<Row>) {
+        let lines = scan_lines(bytes);
+        let sanitized = sanitize_lines(bytes, &lines);
+        let (rows, _columns) = parse_rows(file_id, Path::new("test.log"), &lines, &sanitized, &Extractor::new(false));
+        (lines, rows)
+    }
+
+    fn row(json: &str) -> Row {
+        let (_, mut rows) = parse(json.as_bytes(), 0);
+        assert_eq!(rows.len(), 1, "{json}");
+        rows.remove(0)
+    }
+
+    #[test]
+    fn append_cursor_resumes_at_the_last_row() {
+        let bytes = [log_line(1, "a"), log_line(2, "b"), log_line(3, "c")].concat();
+        let (lines, rows) = parse(bytes.as_bytes(), 0);
+        assert_eq!(rows.len(), 3);
+
+        let cursor = AppendCursor::new(bytes.as_bytes(), &lines, &rows, 0, 0);
+        assert_eq!(cursor.len, bytes.len() as u64);
+        assert_eq!(cursor.resume_line, 2);
+        assert_eq!(cursor.resume_offset, lines[2].offset);
+
+        // A cursor taken from a parsed tail keeps absolute lines and offsets.
+        let mut shifted = rows.clone();
+        for row in &mut shifted {
+            row.line_start += 10;
+            row.line_end += 10;
+        }
+        let cursor = AppendCursor::new(bytes.as_bytes(), &lines, &shifted, 10, 500);
+        assert_eq!(cursor.resume_line, 12);
+        assert_eq!(cursor.resume_offset, 500 + lines[2].offset);
+
+        let cursor = AppendCursor::new(b"", &[], &[], 4, 80);
+        assert_eq!((cursor.resume_line, cursor.resume_offset), (4, 80));
+    }
+
+    #[test]
+    fn append_cursor_falls_back_when_the_prefix_changes() {
+        let original = [log_line(1, "a"), log_line(2, "b")].concat();
+        let (lines, rows) = parse(original.as_bytes(), 0);
+        let cursor = AppendCursor::new(original.as_bytes(), &lines, &rows, 0, 0);
+
+        let appended = original.clone() + &log_line(3, "c");
+        assert!(cursor.can_resume(appended.as_bytes()));
+
+        assert!(!cursor.can_resume(original.as_bytes()), "unchanged file");
+        assert!(!cursor.can_resume(&original.as_bytes()[..10]), "truncated file");
+        let rewritten = [log_line(1, "x"), log_line(2, "b"), log_line(3, "c")].concat();
+        assert!(!cursor.can_resume(rewritten.as_bytes()), "rewritten file");
+    }
+
+    #[test]
+    fn merge_sorted_rows_matches_a_full_sort() {
+        let first = [log_line(1, "a"), log_line(3, "b"), log_line(5, "c")].concat();
+        let second = [log_line(2, "d"), log_line(3, "e"), log_line(6, "f")].concat();
+        let (_, mut rows) = parse(first.as_bytes(), 0);
+        let (_, new_rows) = parse(second.as_bytes(), 1);
+
+        let mut expected: Vec<Row> = rows.iter().chain(&new_rows).cloned().collect();
+        expected.sort_by(row_order);
+
+        assert_eq!(merge_sorted_rows(&mut rows, new_rows), Some(1));
+        let key = |row: &Row| (row.file_id, row.line_start);
+        assert_eq!(rows.iter().map(key).collect::<Vec<_>>(), expected.iter().map(key).collect::<Vec<_>>());
+        assert_eq!(merge_sorted_rows(&mut rows, Vec::new()), None);
+    }
+
+    #[test]
+    fn update_duckdb_rows_rewrites_the_tail_and_drops_evicted_ids() {
+        let bytes = [log_line(1, "a"), log_line(2, "b"), log_line(4, "c")].concat();
+        let (_, mut rows) = parse(bytes.as_bytes(), 0);
+        let db_path = populate_duckdb(&rows).unwrap();
+        let conn = Connection::open(&db_path).unwrap();
+
+        let (_, new_rows) = parse(log_line(3, "d").as_bytes(), 1);
+        let changed_from = merge_sorted_rows(&mut rows, new_rows).unwrap();
+        rows.remove(0);
+        update_duckdb_rows(&conn, &rows, 1, changed_from - 1).unwrap();
+
+        let mut stmt = conn.prepare("SELECT row_id, msg FROM logs ORDER BY row_id").unwrap();
+        let stored: Vec<(i64, String)> = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?))).unwrap().map(Result::unwrap).collect();
+        assert_eq!(stored, [(1, "b".to_string()), (2, "d".to_string()), (3, "c".to_string())]);
+
+        drop(stmt);
+        drop(conn);
+        let _ = std::fs::remove_file(&db_path);
+        let _ = std::fs::remove_file(db_path.with_extension("duckdb.wal"));
+    }
+
+    #[test]
+    fn path_filter_parses_operators_and_quoted_values() {
+        let parsed = PathFilter::parse(r#"  context.userId == "123"  "#).unwrap();
+        assert_eq!((parsed.path.as_str(), parsed.op, parsed.value.as_str()), ("context.userId", PathOp::Eq, "123"));
+        let parsed = PathFilter::parse("http.response.statusCode>=500").unwrap();
+        assert_eq!((parsed.op, parsed.value.as_str()), (PathOp::Ge, "500"));
+        let parsed = PathFilter::parse("msg contains 'time out'").unwrap();
+        assert_eq!((parsed.op, parsed.value.as_str()), (PathOp::Contains, "time out"));
+        assert_eq!(PathFilter::parse("context.user exists").unwrap().op, PathOp::Exists);
+
+        assert!(PathFilter::parse("== 1").is_err());
+        assert!(PathFilter::parse("level ~ info").is_err());
+        assert!(PathFilter::parse("level ==").is_err());
+        assert!(PathFilter::parse(r#"level == """#).is_err());
+    }
+
+    #[test]
+    fn path_filter_quote_round_trips_through_parse() {
+        for value in ["plain", r#"say "hi""#, r"C:\logs\", r#"\""#, "it's"] {
+            let parsed = PathFilter::parse(&format!("msg == {}", PathFilter::quote(value))).unwrap();
+            assert_eq!(parsed.value, value);
+        }
+        assert_eq!(PathFilter::parse(r#"msg == "a\"b""#).unwrap().value, r#"a"b"#);
+        assert_eq!(PathFilter::parse(r"msg == 'a\b'").unwrap().value, r"a\b", "single quotes are raw");
+    }
+
+    #[test]
+    fn path_filter_compares_numbers_numerically_and_text_lexicographically() {
+        let row = row(r#"{"time":"2026-01-01T00:00:00Z","level":"warn","msg":"Request Timed Out","http":{"status":95},"tags":["a"]}"#);
+        let matches = |expr: &str| PathFilter::parse(expr).unwrap().matches(&row);
+
+        assert!(matches("http.status < 100"), "95 < 100 as numbers");
+        assert!(!matches("http.status >= 100"));
+        assert!(matches("http.status == 95.0"));
+        assert!(matches("level > error"), "warn sorts after error");
+        assert!(matches("level != info"));
+        assert!(matches("msg contains timed out"), "contains ignores case");
+
+        assert!(matches("http exists"), "objects exist through their leaves");
+        assert!(matches("tags exists"), "arrays exist through their items");
+        assert!(!matches("context exists"));
+        assert!(matches("context.userId != 1"), "a missing field is not equal to anything");
+        assert!(!matches("context.userId == 1"));
+    }
+
+    #[test]
+    fn fuzzy_score_needs_every_character_in_order() {
+        assert!(fuzzy_score("tmo", "request timed out").is_some());
+        assert_eq!(fuzzy_score("omt", "request timed out"), None);
+        assert_eq!(fuzzy_score("tmox", "request timed out"), None);
+        assert!(fuzzy_score("rto", "Request Timed Out").is_some(), "the haystack is matched case-insensitively");
+        assert!(fuzzy_score("", "anything").is_some());
+    }
+
+    #[test]
+    fn fuzzy_score_prefers_runs_and_word_starts() {
+        let score = |haystack: &str| fuzzy_score("time", haystack).unwrap();
+        assert!(score("timeout") > score("t-i-m-e"), "a consecutive run beats scattered matches");
+        assert!(score("a timer") > score("anytime"), "a match at a word start beats one mid-word");
+        assert!(score("tim e") > score("tim            e"), "longer gaps cost more");
+    }
+
+    #[test]
+    fn column_transforms_format_parsable_values_and_pass_others_through() {
+        assert_eq!(ColumnTransform::EpochTimestamp.apply("1767225600"), "2026-01-01T00:00:00.000Z");
+        assert_eq!(ColumnTransform::EpochTimestamp.apply("1767225600123"), "2026-01-01T00:00:00.123Z");
+        assert_eq!(ColumnTransform::Bytes.apply("2048"), "2.0 KB");
+        assert_eq!(ColumnTransform::DurationMs.apply(" 1500 "), "1.50 s");
+        assert_eq!(ColumnTransform::Format("{} req/s".into()).apply("12"), "12 req/s");
+        assert_eq!(ColumnTransform::Format("[{}]".into()).apply("n/a"), "[n/a]");
+
+        for transform in ColumnTransform::PRESETS {
+            assert_eq!(transform.apply("n/a"), "n/a");
+            assert_eq!(transform.apply("NaN"), "NaN");
+        }
+    }
+
+    #[test]
+    fn format_bytes_and_durations_pick_a_readable_unit() {
+        assert_eq!(format_bytes(512.0), "512 B");
+        assert_eq!(format_bytes(1536.0), "1.5 KB");
+        assert_eq!(format_bytes(5.0 * 1024.0 * 1024.0), "5.0 MB");
+        assert_eq!(format_bytes(2048.0_f64.powi(5)), "32768.0 TB");
+        assert_eq!(format_bytes(-2048.0), "-2.0 KB");
+
+        assert_eq!(format_duration_ms(250.0), "250 ms");
+        assert_eq!(format_duration_ms(12_345.0), "12.35 s");
+        assert_eq!(format_duration_ms(125_000.0), "2m 5s");
+        assert_eq!(format_duration_ms(5_400_000.0), "1h 30m");
+    }
+
+    #[test]
+    fn curl_rebuilds_the_request_without_per_request_headers() {
+        let request = json!({
+            "method": "post",
+            "protocol": "https:",
+            "hostname": "api.example.com",
+            "path": "/users",
+            "queryString": "page=2",
+            "headers": {"content-type": "application/json", "X-Correlation-Id": "abc", "x-count": 3},
+            "body": {"name": "O'Brien"},
+        });
+        assert_eq!(
+            curl_from_http_request(&request).unwrap(),
+            "curl -X POST 'https://api.example.com/users?page=2' \\\n  -H 'content-type: application/json' \\\n  -H 'x-count: 3' \\\n  --data '{\"name\":\"O'\\''Brien\"}'"
+        );
+
+        let minimal = json!({"path": "/health", "queryString": "?full=1", "body": null});
+        assert_eq!(curl_from_http_request(&minimal).unwrap(), "curl -X GET 'https://localhost/health?full=1'");
+        assert_eq!(curl_from_http_request(&json!({"method": "GET"})), None);
+        assert_eq!(curl_from_http_request(&json!({"path": ""})), None);
+    }
+
+    #[test]
+    fn shell_quote_survives_single_quotes() {
+        assert_eq!(shell_quote("plain"), "'plain'");
+        assert_eq!(shell_quote("it's $HOME"), r"'it'\''s $HOME'");
+        assert_eq!(shell_quote(""), "''");
+    }
+
+    #[test]
+    fn csv_field_quotes_only_when_needed() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+        assert_eq!(csv_field("cr\r"), "\"cr\r\"");
+        assert_eq!(csv_field(""), "");
+    }
+
+    /// A fresh directory under the system temp dir, removed by the caller.
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("smooai-log-viewer-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn resolve_file_patterns_expands_globs_to_existing_files() {
+        let dir = scratch_dir("patterns");
+        for name in ["a.log", "b.log", "c.jsonl"] {
+            std::fs::write(dir.join(name), "").unwrap();
+        }
+        std::fs::create_dir(dir.join("d.log")).unwrap();
+        let pattern = |suffix: &str| dir.join(suffix).to_string_lossy().into_owned();
+
+        assert_eq!(
+            resolve_file_patterns(&[pattern("*.log")]),
+            [dir.join("a.log"), dir.join("b.log")],
+            "directories are skipped"
+        );
+        assert_eq!(
+            resolve_file_patterns(&[pattern("c.jsonl"), pattern("?.log"), pattern("a.log"), pattern("missing.log")]),
+            [dir.join("a.log"), dir.join("b.log"), dir.join("c.jsonl")],
+            "sorted, deduplicated, missing paths dropped"
+        );
+        assert!(resolve_file_patterns(&[pattern("*.txt")]).is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn has_log_extension_accepts_gzipped_rotations() {
+        for name in ["a.ansi", "a.log", "a.json", "a.jsonl", "2026-01/output-1.ansi.gz", "a.jsonl.gz"] {
+            assert!(has_log_extension(Path::new(name)), "{name}");
+        }
+        for name in ["a.txt", "a.gz", "a.txt.gz", "log", "a.log.bak"] {
+            assert!(!has_log_extension(Path::new(name)), "{name}");
+        }
+    }
+
+    #[test]
+    fn gzipped_logs_are_decompressed_including_concatenated_members() {
+        use flate2::write::GzEncoder;
+
+        let dir = scratch_dir("gzip");
+        let path = dir.join("output.ansi.gz");
+        let mut bytes = Vec::new();
+        for line in [log_line(1, "a"), log_line(2, "b")] {
+            let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(line.as_bytes()).unwrap();
+            bytes.extend(encoder.finish().unwrap());
+        }
+        std::fs::write(&path, bytes).unwrap();
+
+        let decoded = mmap_file(&path).unwrap();
+        assert!(matches!(decoded, LogBytes::Decompressed(_)));
+        let (_, rows) = parse(&decoded, 0);
+        assert_eq!(rows.iter().map(|row| row.msg.as_deref().unwrap()).collect::<Vec<_>>(), ["a", "b"]);
+
+        std::fs::write(&path, b"not gzip").unwrap();
+        assert!(mmap_file(&path).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn error_details_are_read_leniently() {
+        let value = json!({"errorDetails": [
+            {"name": "TypeError", "message": "boom", "stack": "at x", "causes": ["io", {"message": "disk"}, 7], "code": 500},
+            {"message": "no name", "code": "E_FAIL"},
+            "not an object",
+        ]});
+        let errors = error_details_from_json(&value);
+        assert_eq!(errors.len(), 2);
+        assert_eq!(
+            (errors[0].name.as_str(), errors[0].message.as_str(), errors[0].stack.as_deref()),
+            ("TypeError", "boom", Some("at x"))
+        );
+        assert_eq!(errors[0].causes, ["io", "disk", "7"]);
+        assert_eq!(errors[0].code.as_deref(), Some("500"));
+        assert_eq!(
+            (errors[1].name.as_str(), errors[1].code.as_deref(), errors[1].stack.as_deref()),
+            ("Error", Some("E_FAIL"), None)
+        );
+
+        assert!(error_details_from_json(&json!({"msg": "fine"})).is_empty());
+        assert!(error_details_from_json(&json!({"errorDetails": "boom"})).is_empty());
+    }
+
+    #[test]
+    fn compare_rows_by_column_orders_numbers_numerically() {
+        let early = row(r#"{"time":"2026-01-01T00:00:09Z","msg":"b","status":"200","durationMs":"9"}"#);
+        let late = row(r#"{"time":"2026-01-01T00:00:10Z","msg":"a","status":"abc","durationMs":"10"}"#);
+        use std::cmp::Ordering::*;
+
+        assert_eq!(compare_rows_by_column(&early, &late, "time"), Less);
+        assert_eq!(compare_rows_by_column(&early, &late, "durationMs"), Less, "9 < 10, not \"9\" > \"10\"");
+        assert_eq!(compare_rows_by_column(&early, &late, "msg"), Greater);
+        assert_eq!(compare_rows_by_column(&early, &late, "status"), Less, "text when either side is not a number");
+        assert_eq!(compare_rows_by_column(&early, &early, "missing"), Equal);
+    }
+
+    #[test]
+    fn parse_permalink_splits_at_the_last_colon() {
+        assert_eq!(parse_permalink("a.log:12"), Some((PathBuf::from("a.log"), 12)));
+        assert_eq!(parse_permalink(r"C:\x\a.log:3"), Some((PathBuf::from(r"C:\x\a.log"), 3)));
+        assert_eq!(parse_permalink(":5"), None);
+        assert_eq!(parse_permalink("a.log:0"), None);
+    }
+}
@@ -153,6 +153,23 @@ pub fn header_background(dark: bool) -> Color32 {
     lerp(theme.background, theme.muted, if dark { 0.12 } else { 0.10 })
 }
 
+/// Tighter spacing and smaller text for the log table's compact mode.
+pub fn compact_table_style(style: &mut egui::Style) {
+    style.spacing.item_spacing = egui::vec2(6.0, 2.0);
+    style.spacing.button_padding = egui::vec2(2.0, 0.0);
+    for text_style in [egui::TextStyle::Body, egui::TextStyle::Button, egui::TextStyle::Monospace] {
+        if let Some(font) = style.text_styles.get_mut(&text_style) {
+            font.size = (font.size - 2.0).max(9.0);
+        }
+    }
+}
+
+/// Background behind search matches highlighted in the table and context panel.
+pub fn match_background(dark: bool) -> Color32 {
+    let theme = if dark { dark_theme() } else { light_theme() };
+    lerp(theme.accent, theme.background, if dark { 0.45 } else { 0.55 })
+}
+
 pub fn grid_stroke(dark: bool) -> Stroke {
     let color = if dark {
         lerp(smoo::GRAY_800, smoo::GRAY_600, 0.35)
@@ -162,17 +179,28 @@ pub fn grid_stroke(dark: bool) -> Stroke {
     Stroke { width: 1.0, color }
 }
 
+/// Uses the logger's [`Level::rgb`] so the viewer matches the pretty console output.
 pub fn level_color(level: &str) -> Color32 {
     match Level::parse_level(level) {
-        Some(Level::Error) | Some(Level::Fatal) => smoo::RED,
-        Some(Level::Warn) => smoo::ORANGE,
-        Some(Level::Info) => smoo::BLUE_400,
-        Some(Level::Debug) => smoo::GRAY_500,
-        Some(Level::Trace) => smoo::GRAY_400,
-        _ => smoo::GRAY_400,
+        Some(level) => {
+            let (r, g, b) = level.rgb();
+            Color32::from_rgb(r, g, b)
+        }
+        None => smoo::GRAY_400,
     }
 }
 
+/// Whether `level` is `error` or `fatal`, the levels whose rows get [`error_row_background`].
+pub fn is_error_level(level: &str) -> bool {
+    matches!(Level::parse_level(level), Some(Level::Error | Level::Fatal))
+}
+
+/// Faint red wash behind error/fatal rows, blended with the current background.
+pub fn error_row_background(dark: bool) -> Color32 {
+    let theme = if dark { dark_theme() } else { light_theme() };
+    lerp(theme.background, smoo::RED, if dark { 0.14 } else { 0.10 })
+}
+
 pub fn lerp(a: Color32, b: Color32, t: f32) -> Color32 {
     let to_f = |c: Color32| (c.r() as f32, c.g() as f32, c.b() as f32);
     let (ar, ag, ab) = to_f(a);
@@ -1,4 +1,7 @@
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::marker::PhantomData;
 
 use once_cell::sync::Lazy;
 use parking_lot::RwLock;
@@ -17,6 +20,7 @@ pub enum ContextKey {
     CorrelationId,
     RequestId,
     TraceId,
+    SpanId,
     Namespace,
     Service,
     Duration,
@@ -25,6 +29,7 @@ pub enum ContextKey {
     Context,
     User,
     Http,
+    Breadcrumbs,
 }
 
 impl ContextKey {
@@ -38,6 +43,7 @@ impl ContextKey {
             ContextKey::CorrelationId => "correlationId",
             ContextKey::RequestId => "requestId",
             ContextKey::TraceId => "traceId",
+            ContextKey::SpanId => "spanId",
             ContextKey::Namespace => "namespace",
             ContextKey::Service => "service",
             ContextKey::Duration => "duration",
@@ -46,6 +52,7 @@ impl ContextKey {
             ContextKey::Context => "context",
             ContextKey::User => "user",
             ContextKey::Http => "http",
+            ContextKey::Breadcrumbs => "breadcrumbs",
         }
     }
 }
@@ -131,8 +138,12 @@ pub enum ContextConfig {
     Deny,
     /// Keep only the listed keys at this level.
     OnlyKeys(Vec<String>),
+    /// Keep only the listed keys at this level and apply each key's configuration to its value.
+    OnlyKeysNested(HashMap<String, ContextConfig>),
     /// Apply nested configuration rules to object children.
     Nested(HashMap<String, ContextConfig>),
+    /// Keep the key but replace its value with the given token.
+    Redact(String),
 }
 
 pub static CONFIG_MINIMAL: Lazy<ContextConfig> = Lazy::new(|| {
@@ -158,6 +169,117 @@ pub static CONFIG_MINIMAL: Lazy<ContextConfig> = Lazy::new(|| {
 
 pub const CONFIG_FULL: ContextConfig = ContextConfig::AllowAll;
 
+/// Object key marking a [`ContextConfig::Redact`] node in the JSON form.
+const REDACT_KEY: &str = "$redact";
+/// Object key marking a [`ContextConfig::OnlyKeysNested`] node in the JSON form.
+const ONLY_KEY: &str = "$only";
+
+/// Error returned when a JSON value does not describe a [`ContextConfig`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigError {
+    /// Dotted path to the offending node (empty for the root).
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "invalid context config: {}", self.message)
+        } else {
+            write!(f, "invalid context config at `{}`: {}", self.path, self.message)
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl ContextConfig {
+    /// Parses the JSON form of a config:
+    ///
+    /// - `"*"` or `true` is [`AllowAll`](ContextConfig::AllowAll)
+    /// - `false` or `null` is [`Deny`](ContextConfig::Deny)
+    /// - an array of strings is [`OnlyKeys`](ContextConfig::OnlyKeys)
+    /// - an object is [`Nested`](ContextConfig::Nested), its values parsed recursively
+    /// - `{"$redact": "token"}` is [`Redact`](ContextConfig::Redact)
+    /// - `{"$only": {...}}` is [`OnlyKeysNested`](ContextConfig::OnlyKeysNested)
+    pub fn from_json(value: &Value) -> Result<ContextConfig, ConfigError> {
+        parse_config(value, "")
+    }
+
+    /// Converts the config to the JSON form accepted by [`ContextConfig::from_json`].
+    pub fn to_json(&self) -> Value {
+        match self {
+            ContextConfig::AllowAll => Value::String("*".into()),
+            ContextConfig::Deny => Value::Bool(false),
+            ContextConfig::OnlyKeys(keys) => Value::Array(keys.iter().cloned().map(Value::String).collect()),
+            ContextConfig::OnlyKeysNested(children) => json!({ ONLY_KEY: children_to_json(children) }),
+            ContextConfig::Nested(children) => children_to_json(children),
+            ContextConfig::Redact(token) => json!({ REDACT_KEY: token }),
+        }
+    }
+}
+
+fn children_to_json(children: &HashMap<String, ContextConfig>) -> Value {
+    let mut keys: Vec<&String> = children.keys().collect();
+    keys.sort();
+    Value::Object(keys.into_iter().map(|key| (key.clone(), children[key].to_json())).collect())
+}
+
+fn parse_config(value: &Value, path: &str) -> Result<ContextConfig, ConfigError> {
+    let error = |message: String| ConfigError {
+        path: path.to_string(),
+        message,
+    };
+    match value {
+        Value::String(s) if s == "*" => Ok(ContextConfig::AllowAll),
+        Value::Bool(true) => Ok(ContextConfig::AllowAll),
+        Value::Bool(false) | Value::Null => Ok(ContextConfig::Deny),
+        Value::Array(items) => items
+            .iter()
+            .map(|item| {
+                item.as_str()
+                    .map(str::to_string)
+                    .ok_or_else(|| error(format!("expected a key name, found {item}")))
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(ContextConfig::OnlyKeys),
+        Value::Object(map) if map.len() == 1 && map.contains_key(REDACT_KEY) => match &map[REDACT_KEY] {
+            Value::String(token) => Ok(ContextConfig::Redact(token.clone())),
+            other => Err(error(format!("`{REDACT_KEY}` expects a string token, found {other}"))),
+        },
+        Value::Object(map) if map.len() == 1 && map.contains_key(ONLY_KEY) => match &map[ONLY_KEY] {
+            Value::Object(children) => parse_children(children, path).map(ContextConfig::OnlyKeysNested),
+            other => Err(error(format!("`{ONLY_KEY}` expects an object, found {other}"))),
+        },
+        Value::Object(children) => parse_children(children, path).map(ContextConfig::Nested),
+        other => Err(error(format!("unsupported value {other}"))),
+    }
+}
+
+fn parse_children(children: &Map<String, Value>, path: &str) -> Result<HashMap<String, ContextConfig>, ConfigError> {
+    children
+        .iter()
+        .map(|(key, child)| {
+            let child_path = if path.is_empty() { key.clone() } else { format!("{path}.{key}") };
+            parse_config(child, &child_path).map(|config| (key.clone(), config))
+        })
+        .collect()
+}
+
+impl Serialize for ContextConfig {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_json().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ContextConfig {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        ContextConfig::from_json(&value).map_err(serde::de::Error::custom)
+    }
+}
+
 /// Default list of context keys whose values are replaced with `"[REDACTED]"`
 /// before logging. Matching is case-insensitive.
 pub fn default_redact_keys() -> Vec<String> {
@@ -210,8 +332,241 @@ pub fn redact_sensitive_values(value: &mut Value, redact_keys: &std::collections
     }
 }
 
+/// One step of a [`RedactPath`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+    /// `*`: any key of an object or any element of an array.
+    Any,
+}
+
+/// Dotted key path such as `http.request.headers.authorization` or `items[0].ssn`.
+/// A `*` segment matches any single key or index and a trailing `.*` redacts every leaf
+/// under the matched value instead of replacing the value itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedactPath {
+    segments: Vec<PathSegment>,
+    subtree: bool,
+}
+
+impl RedactPath {
+    pub fn parse(path: &str) -> Self {
+        let mut segments = Vec::new();
+        for part in path.split('.').filter(|part| !part.is_empty()) {
+            let (key, mut rest) = match part.find('[') {
+                Some(pos) => part.split_at(pos),
+                None => (part, ""),
+            };
+            match key {
+                "" => {}
+                "*" => segments.push(PathSegment::Any),
+                key => segments.push(PathSegment::Key(key.to_string())),
+            }
+            while let Some(end) = rest.find(']') {
+                let index = &rest[1..end];
+                segments.push(match index.parse() {
+                    Ok(index) => PathSegment::Index(index),
+                    Err(_) if index == "*" => PathSegment::Any,
+                    Err(_) => PathSegment::Key(index.to_string()),
+                });
+                rest = &rest[end + 1..];
+            }
+        }
+
+        let subtree = segments.len() > 1 && path.ends_with(".*");
+        if subtree {
+            segments.pop();
+        }
+        Self { segments, subtree }
+    }
+}
+
+/// Replaces the values at each of `paths` with `REDACTED_VALUE`. Paths that point at
+/// missing keys or out-of-range indices are ignored.
+pub fn redact_paths(value: &mut Value, paths: &[RedactPath]) {
+    for path in paths {
+        redact_path(value, &path.segments, path.subtree);
+    }
+}
+
+fn redact_path(value: &mut Value, segments: &[PathSegment], subtree: bool) {
+    let Some((segment, rest)) = segments.split_first() else {
+        if subtree {
+            redact_leaves(value);
+        } else {
+            *value = Value::String(REDACTED_VALUE.to_string());
+        }
+        return;
+    };
+
+    match (segment, value) {
+        (PathSegment::Key(key), Value::Object(map)) => {
+            if let Some(child) = map.get_mut(key) {
+                redact_path(child, rest, subtree);
+            }
+        }
+        (PathSegment::Index(index), Value::Array(items)) => {
+            if let Some(child) = items.get_mut(*index) {
+                redact_path(child, rest, subtree);
+            }
+        }
+        (PathSegment::Any, Value::Object(map)) => {
+            for child in map.values_mut() {
+                redact_path(child, rest, subtree);
+            }
+        }
+        (PathSegment::Any, Value::Array(items)) => {
+            for child in items.iter_mut() {
+                redact_path(child, rest, subtree);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn redact_leaves(value: &mut Value) {
+    match value {
+        Value::Object(map) => map.values_mut().for_each(redact_leaves),
+        Value::Array(items) => items.iter_mut().for_each(redact_leaves),
+        leaf => *leaf = Value::String(REDACTED_VALUE.to_string()),
+    }
+}
+
+/// Placeholder substituted for objects and arrays nested deeper than the depth limit.
+pub const TRUNCATED_VALUE: &str = "[truncated]";
+
+/// Last-resort size guard for a finished payload. Objects and arrays nested more than
+/// `max_depth` levels below the root become `TRUNCATED_VALUE`; once `max_fields` leaf values
+/// have been seen, later nested leaves are dropped (top-level scalars such as `msg` and
+/// `time` are always kept). If anything was cut, `"_truncated": true` is added at the top.
+pub fn truncate_payload(value: &mut Value, max_depth: Option<usize>, max_fields: Option<usize>) {
+    let mut truncated = false;
+    if let Some(max_depth) = max_depth {
+        truncated |= truncate_depth(value, max_depth);
+    }
+    if let (Some(max_fields), Value::Object(map)) = (max_fields, &mut *value) {
+        let scalars = map.values().filter(|v| !is_nonempty_container(v)).count();
+        let mut budget = max_fields.saturating_sub(scalars);
+        map.retain(|_, v| {
+            if !is_nonempty_container(v) {
+                return true;
+            }
+            truncated |= keep_leaves(v, &mut budget);
+            is_nonempty_container(v)
+        });
+    }
+    if truncated {
+        if let Value::Object(map) = value {
+            map.insert("_truncated".into(), Value::Bool(true));
+        }
+    }
+}
+
+fn is_nonempty_container(value: &Value) -> bool {
+    match value {
+        Value::Object(map) => !map.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        _ => false,
+    }
+}
+
+fn truncate_depth(value: &mut Value, remaining: usize) -> bool {
+    let children: Vec<&mut Value> = match value {
+        Value::Object(map) => map.values_mut().collect(),
+        Value::Array(items) => items.iter_mut().collect(),
+        _ => return false,
+    };
+    let mut truncated = false;
+    for child in children {
+        if !matches!(child, Value::Object(_) | Value::Array(_)) {
+            continue;
+        }
+        if remaining == 0 {
+            *child = Value::String(TRUNCATED_VALUE.to_string());
+            truncated = true;
+        } else {
+            truncated |= truncate_depth(child, remaining - 1);
+        }
+    }
+    truncated
+}
+
+/// Keeps leaves of `value` while `budget` lasts and drops the rest, removing containers
+/// emptied along the way. Returns whether anything was dropped.
+fn keep_leaves(value: &mut Value, budget: &mut usize) -> bool {
+    let mut dropped = false;
+    let mut keep = |child: &mut Value| {
+        if is_nonempty_container(child) {
+            dropped |= keep_leaves(child, budget);
+            is_nonempty_container(child)
+        } else if *budget > 0 {
+            *budget -= 1;
+            true
+        } else {
+            dropped = true;
+            false
+        }
+    };
+    match value {
+        Value::Object(map) => map.retain(|_, child| keep(child)),
+        Value::Array(items) => items.retain_mut(|child| keep(child)),
+        _ => {}
+    }
+    dropped
+}
+
 static GLOBAL_CONTEXT: Lazy<RwLock<ContextValue>> = Lazy::new(|| RwLock::new(Value::Object(default_context_map())));
 
+/// Caps applied to the nested `context` object of the global context so long-running
+/// processes that keep adding varying keys don't grow it without bound. When a cap is
+/// exceeded the oldest keys (by first insertion) are evicted first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ContextLimits {
+    /// Maximum number of top-level keys kept under `context`.
+    pub max_keys: Option<usize>,
+    /// Maximum serialized JSON size, in bytes, of the `context` object.
+    pub max_bytes: Option<usize>,
+}
+
+static CONTEXT_LIMITS: Lazy<RwLock<ContextLimits>> = Lazy::new(|| RwLock::new(ContextLimits::default()));
+
+pub fn context_limits() -> ContextLimits {
+    *CONTEXT_LIMITS.read()
+}
+
+pub fn set_context_limits(limits: ContextLimits) {
+    *CONTEXT_LIMITS.write() = limits;
+}
+
+fn enforce_context_limits(nested: &mut ContextMap, limits: ContextLimits) {
+    if let Some(max_keys) = limits.max_keys {
+        while nested.len() > max_keys {
+            if !evict_oldest(nested) {
+                break;
+            }
+        }
+    }
+    if let Some(max_bytes) = limits.max_bytes {
+        while serialized_len(nested) > max_bytes {
+            if !evict_oldest(nested) {
+                break;
+            }
+        }
+    }
+}
+
+fn evict_oldest(map: &mut ContextMap) -> bool {
+    match map.keys().next().cloned() {
+        Some(key) => map.shift_remove(&key).is_some(),
+        None => false,
+    }
+}
+
+fn serialized_len(map: &ContextMap) -> usize {
+    serde_json::to_vec(map).map(|bytes| bytes.len()).unwrap_or(0)
+}
+
 fn default_context_map() -> ContextMap {
     let mut map = Map::new();
     let id = Uuid::new_v4().to_string();
@@ -233,14 +588,217 @@ where
     func(object)
 }
 
+thread_local! {
+    static SCOPE_STACK: RefCell<Vec<ContextMap>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Pops the scope pushed by [`push_scope`] when dropped. Scopes are per-thread, so the
+/// guard cannot be sent to another thread.
+#[must_use = "the scope is popped as soon as the guard is dropped"]
+pub struct ScopeGuard {
+    depth: usize,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl ScopeGuard {
+    /// Merges `patch` into this guard's scope, for context that becomes known after the
+    /// scope was pushed.
+    #[cfg(feature = "tracing")]
+    pub(crate) fn merge(&self, patch: &ContextMap) {
+        SCOPE_STACK.with(|stack| {
+            if let Some(scope) = stack.borrow_mut().get_mut(self.depth) {
+                merge_maps(scope, patch);
+            }
+        });
+    }
+}
+
+impl Drop for ScopeGuard {
+    fn drop(&mut self) {
+        SCOPE_STACK.with(|stack| stack.borrow_mut().truncate(self.depth));
+        if self.depth == 0 {
+            BREADCRUMBS.with(|crumbs| crumbs.borrow_mut().clear());
+        }
+    }
+}
+
+/// Pushes `context` onto the current thread's scope stack. Until the guard is dropped,
+/// [`global_context`] on this thread returns the process-wide context with every scope
+/// merged over it (innermost last); other threads are unaffected. Non-object values are
+/// ignored.
+pub fn push_scope(context: ContextValue) -> ScopeGuard {
+    SCOPE_STACK.with(|stack| {
+        let mut stack = stack.borrow_mut();
+        let depth = stack.len();
+        if depth == 0 {
+            BREADCRUMBS.with(|crumbs| crumbs.borrow_mut().clear());
+        }
+        let mut context = context;
+        remove_nulls(&mut context);
+        stack.push(match context {
+            Value::Object(map) => map,
+            _ => Map::new(),
+        });
+        ScopeGuard { depth, _not_send: PhantomData }
+    })
+}
+
+fn merge_scopes(context: &mut ContextValue) {
+    SCOPE_STACK.with(|stack| {
+        let stack = stack.borrow();
+        if stack.is_empty() {
+            return;
+        }
+        if !context.is_object() {
+            *context = Value::Object(Map::new());
+        }
+        let object = context.as_object_mut().expect("context must be an object");
+        for scope in stack.iter() {
+            merge_maps(object, scope);
+        }
+    });
+}
+
+#[cfg(feature = "tokio")]
+tokio::task_local! {
+    static TASK_CONTEXT: RefCell<ContextMap>;
+    static TASK_BREADCRUMBS: RefCell<VecDeque<Value>>;
+}
+
+thread_local! {
+    static BREADCRUMBS: RefCell<VecDeque<Value>> = const { RefCell::new(VecDeque::new()) };
+}
+
+/// Runs `f` on the breadcrumb trail of the current task (inside [`scope`]) or else of the
+/// current thread. The thread trail starts empty whenever the outermost [`push_scope`] is
+/// pushed or popped, so each request sees only its own breadcrumbs.
+#[cfg(feature = "tokio")]
+fn with_breadcrumbs<R>(f: impl FnOnce(&mut VecDeque<Value>) -> R) -> R {
+    let mut f = Some(f);
+    if let Ok(result) = TASK_BREADCRUMBS.try_with(|crumbs| f.take().expect("called once")(&mut crumbs.borrow_mut())) {
+        return result;
+    }
+    BREADCRUMBS.with(|crumbs| f.take().expect("called once")(&mut crumbs.borrow_mut()))
+}
+
+#[cfg(not(feature = "tokio"))]
+fn with_breadcrumbs<R>(f: impl FnOnce(&mut VecDeque<Value>) -> R) -> R {
+    BREADCRUMBS.with(|crumbs| f(&mut crumbs.borrow_mut()))
+}
+
+/// Appends `crumb`, dropping the oldest entries to keep at most `cap`.
+pub(crate) fn push_breadcrumb(crumb: Value, cap: usize) {
+    if cap == 0 {
+        return;
+    }
+    with_breadcrumbs(|crumbs| {
+        while crumbs.len() >= cap {
+            crumbs.pop_front();
+        }
+        crumbs.push_back(crumb);
+    });
+}
+
+/// Removes and returns the current breadcrumbs, oldest first.
+pub(crate) fn take_breadcrumbs() -> Vec<Value> {
+    with_breadcrumbs(|crumbs| crumbs.drain(..).collect())
+}
+
+/// Runs `future` with `context` as task-local context. While it runs, [`global_context`]
+/// merges the task context over the process-wide and thread-scoped context, so concurrent
+/// tasks on the same thread each see their own correlation ID. Nested scopes start from
+/// a copy of the enclosing scope.
+///
+/// Task-locals are not inherited by `tokio::spawn`; use [`spawn_scoped`] to give a child
+/// task a snapshot of the current scope.
+#[cfg(feature = "tokio")]
+pub async fn scope<F: std::future::Future>(context: ContextValue, future: F) -> F::Output {
+    let mut merged = current_task_context().unwrap_or_default();
+    let mut context = context;
+    remove_nulls(&mut context);
+    if let Value::Object(map) = &context {
+        merge_maps(&mut merged, map);
+    }
+    // The outermost scope owns the task's breadcrumb trail; nested scopes add to it.
+    if TASK_BREADCRUMBS.try_with(|_| ()).is_ok() {
+        TASK_CONTEXT.scope(RefCell::new(merged), future).await
+    } else {
+        TASK_BREADCRUMBS
+            .scope(RefCell::new(VecDeque::new()), TASK_CONTEXT.scope(RefCell::new(merged), future))
+            .await
+    }
+}
+
+/// Returns a copy of the current task-local context, or `None` outside [`scope`].
+#[cfg(feature = "tokio")]
+pub fn current_task_context() -> Option<ContextMap> {
+    TASK_CONTEXT.try_with(|context| context.borrow().clone()).ok()
+}
+
+/// Spawns `future` on the current runtime inside a [`scope`] holding a snapshot of the
+/// current task-local context. Changes made by either task afterwards are not shared, and
+/// the child starts with no breadcrumbs.
+#[cfg(feature = "tokio")]
+pub fn spawn_scoped<F>(future: F) -> tokio::task::JoinHandle<F::Output>
+where
+    F: std::future::Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let snapshot = current_task_context().unwrap_or_default();
+    tokio::spawn(TASK_BREADCRUMBS.scope(RefCell::new(VecDeque::new()), TASK_CONTEXT.scope(RefCell::new(snapshot), future)))
+}
+
+fn merge_task_context(context: &mut ContextValue) {
+    #[cfg(feature = "tokio")]
+    let _ = TASK_CONTEXT.try_with(|task| {
+        if !context.is_object() {
+            *context = Value::Object(Map::new());
+        }
+        merge_maps(context.as_object_mut().expect("context must be an object"), &task.borrow());
+    });
+    #[cfg(not(feature = "tokio"))]
+    let _ = context;
+}
+
+fn has_scoped_context() -> bool {
+    #[cfg(feature = "tokio")]
+    if TASK_CONTEXT.try_with(|_| ()).is_ok() {
+        return true;
+    }
+    SCOPE_STACK.with(|stack| !stack.borrow().is_empty())
+}
+
 pub fn global_context() -> ContextValue {
-    GLOBAL_CONTEXT.read().clone()
+    let mut context = GLOBAL_CONTEXT.read().clone();
+    merge_scopes(&mut context);
+    merge_task_context(&mut context);
+    context
 }
 
 pub fn reset_global_context() {
+    reset_global_context_with(true);
+}
+
+/// Clears the global context, re-seeding `correlationId`/`requestId`/`traceId` with a fresh
+/// UUID only when `seed_ids` is true.
+pub fn reset_global_context_with(seed_ids: bool) {
     with_global_context(|object| {
         object.clear();
-        object.extend(default_context_map());
+        if seed_ids {
+            object.extend(default_context_map());
+        }
+    });
+}
+
+/// Keys set together by [`set_correlation_id`].
+const REQUEST_ID_KEYS: [ContextKey; 3] = [ContextKey::CorrelationId, ContextKey::RequestId, ContextKey::TraceId];
+
+/// Removes `correlationId`, `requestId` and `traceId` from the global context.
+pub fn clear_request_ids() {
+    with_global_context(|object| {
+        for key in REQUEST_ID_KEYS {
+            object.shift_remove(key.as_str());
+        }
     });
 }
 
@@ -253,30 +811,47 @@ pub fn set_global_context(context: ContextValue) {
 }
 
 pub fn update_global_context(context: &ContextValue) {
+    update_global_context_with(context, MergeStrategy::Replace);
+}
+
+pub fn update_global_context_with(context: &ContextValue, strategy: MergeStrategy) {
     with_global_context(|object| {
         if let Value::Object(incoming) = context {
-            merge_maps(object, incoming);
+            merge_maps_with(object, incoming, strategy);
         }
     });
 }
 
 pub fn base_context_key(key: &str) -> Option<ContextValue> {
-    GLOBAL_CONTEXT.read().as_object()?.get(key).cloned()
+    if !has_scoped_context() {
+        return GLOBAL_CONTEXT.read().as_object()?.get(key).cloned();
+    }
+    global_context().as_object()?.get(key).cloned()
 }
 
 pub fn add_base_context(context: &ContextValue) {
     update_global_context(context);
 }
 
+pub fn add_base_context_with(context: &ContextValue, strategy: MergeStrategy) {
+    update_global_context_with(context, strategy);
+}
+
 pub fn add_nested_context(context: &ContextValue) {
+    add_nested_context_with(context, MergeStrategy::Replace);
+}
+
+pub fn add_nested_context_with(context: &ContextValue, strategy: MergeStrategy) {
+    let limits = context_limits();
     with_global_context(|object| {
         let nested = object
             .entry(ContextKey::Context.as_str().to_string())
             .or_insert_with(|| Value::Object(Map::new()));
         if let Value::Object(nested_map) = nested {
             if let Value::Object(new_map) = context {
-                merge_maps(nested_map, new_map);
+                merge_maps_with(nested_map, new_map, strategy);
             }
+            enforce_context_limits(nested_map, limits);
         }
     });
 }
@@ -290,25 +865,143 @@ pub fn set_correlation_id(id: &str) {
     });
 }
 
+/// Overrides `correlationId`, `requestId` and `traceId` for the current task (inside
+/// `scope`, with the `tokio` feature) or else the current thread, until dropped. Other
+/// threads and tasks keep their own IDs. Created by
+/// [`Logger::scoped_correlation`](crate::Logger::scoped_correlation).
+#[must_use = "the previous correlation ID is restored as soon as the guard is dropped"]
+pub struct CorrelationGuard {
+    // Only read by the task-context `Drop`; the thread scope pops itself.
+    #[cfg_attr(not(feature = "tokio"), allow(dead_code))]
+    restore: CorrelationRestore,
+}
+
+enum CorrelationRestore {
+    /// Popping the scope restores the thread's previous IDs.
+    Thread { _scope: ScopeGuard },
+    /// The task context's previous values, put back on drop; unset keys are removed.
+    #[cfg(feature = "tokio")]
+    Task(Vec<(ContextKey, Option<Value>)>),
+}
+
+impl CorrelationGuard {
+    /// Sets `correlationId`, `requestId` and `traceId` to `id` in the innermost context.
+    pub fn set(id: &str) -> Self {
+        #[cfg(feature = "tokio")]
+        if let Ok(previous) = TASK_CONTEXT.try_with(|context| {
+            let mut context = context.borrow_mut();
+            REQUEST_ID_KEYS
+                .iter()
+                .map(|&key| (key, context.insert(key.as_str().into(), Value::String(id.to_string()))))
+                .collect()
+        }) {
+            return Self {
+                restore: CorrelationRestore::Task(previous),
+            };
+        }
+        let ids = REQUEST_ID_KEYS
+            .iter()
+            .map(|key| (key.as_str().to_string(), Value::String(id.to_string())))
+            .collect();
+        Self {
+            restore: CorrelationRestore::Thread {
+                _scope: push_scope(Value::Object(ids)),
+            },
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl Drop for CorrelationGuard {
+    fn drop(&mut self) {
+        let CorrelationRestore::Task(previous) = &mut self.restore else {
+            return;
+        };
+        let previous = std::mem::take(previous);
+        let _ = TASK_CONTEXT.try_with(|context| {
+            let mut context = context.borrow_mut();
+            for (key, value) in previous {
+                match value {
+                    Some(value) => {
+                        context.insert(key.as_str().into(), value);
+                    }
+                    None => {
+                        context.shift_remove(key.as_str());
+                    }
+                }
+            }
+        });
+    }
+}
+
+/// How a merge treats a key present on both sides when neither value is an object
+/// (objects are always merged key by key).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MergeStrategy {
+    /// The incoming value wins.
+    #[default]
+    Replace,
+    /// Arrays on both sides are joined, existing items first; other values are replaced.
+    ConcatArrays,
+    /// Like `ConcatArrays`, but incoming items already present are skipped.
+    UnionArrays,
+}
+
 pub fn merge_maps(target: &mut ContextMap, patch: &ContextMap) {
+    merge_maps_with(target, patch, MergeStrategy::Replace);
+}
+
+pub fn merge_maps_with(target: &mut ContextMap, patch: &ContextMap, strategy: MergeStrategy) {
     for (key, value) in patch.iter() {
-        merge_value(target.entry(key.clone()).or_insert(Value::Null), value);
+        merge_value(target.entry(key.clone()).or_insert(Value::Null), value, strategy);
     }
 }
 
-fn merge_value(target: &mut Value, patch: &Value) {
-    if let Value::Object(target_map) = target {
-        if let Value::Object(patch_map) = patch {
-            for (key, value) in patch_map {
-                merge_value(target_map.entry(key.clone()).or_insert(Value::Null), value);
+fn merge_value(target: &mut Value, patch: &Value, strategy: MergeStrategy) {
+    match (&mut *target, patch) {
+        (Value::Object(target_map), Value::Object(patch_map)) => {
+            merge_maps_with(target_map, patch_map, strategy);
+            return;
+        }
+        (Value::Array(items), Value::Array(incoming)) if strategy != MergeStrategy::Replace => {
+            for item in incoming {
+                if strategy == MergeStrategy::ConcatArrays || !items.contains(item) {
+                    items.push(item.clone());
+                }
             }
             return;
         }
+        _ => {}
     }
 
     *target = patch.clone();
 }
 
+/// Placeholder for a `null` that should be logged, see [`explicit_null`].
+pub const EXPLICIT_NULL: &str = "\u{0}smooai-logger:null";
+
+/// A value that survives [`remove_nulls`] and is written as `null`. Nulls are normally
+/// dropped, which keeps unset context fields (such as a `None` field of a serialized struct)
+/// out of the output; use this where a `null` carries meaning, e.g.
+/// `json!({"result": explicit_null()})` for "no result" rather than "not recorded".
+pub fn explicit_null() -> ContextValue {
+    Value::String(EXPLICIT_NULL.to_string())
+}
+
+/// Turns every [`explicit_null`] placeholder in `value` back into `null`; the last step
+/// before a payload is written.
+pub(crate) fn restore_explicit_nulls(value: &mut Value) {
+    match value {
+        Value::Object(map) => map.values_mut().for_each(restore_explicit_nulls),
+        Value::Array(items) => items.iter_mut().for_each(restore_explicit_nulls),
+        Value::String(text) if text == EXPLICIT_NULL => *value = Value::Null,
+        _ => {}
+    }
+}
+
+/// Drops `null`s, and the objects and arrays left empty by doing so, from `value`.
+/// Returns whether `value` itself is now empty or `null`. [`explicit_null`]
+/// placeholders are kept.
 pub fn remove_nulls(value: &mut Value) -> bool {
     match value {
         Value::Object(map) => {
@@ -336,6 +1029,7 @@ pub fn apply_context_config(value: &Value, config: &ContextConfig) -> Value {
     match config {
         ContextConfig::AllowAll => value.clone(),
         ContextConfig::Deny => Value::Null,
+        ContextConfig::Redact(token) => Value::String(token.clone()),
         ContextConfig::OnlyKeys(keys) => {
             if let Value::Object(map) = value {
                 let mut filtered = Map::new();
@@ -349,6 +1043,22 @@ pub fn apply_context_config(value: &Value, config: &ContextConfig) -> Value {
                 Value::Null
             }
         }
+        ContextConfig::OnlyKeysNested(children) => {
+            if let Value::Object(map) = value {
+                let mut filtered = Map::new();
+                for (key, val) in map {
+                    if let Some(child_config) = children.get(key) {
+                        let filtered_value = apply_context_config(val, child_config);
+                        if !is_effectively_empty(&filtered_value) {
+                            filtered.insert(key.clone(), filtered_value);
+                        }
+                    }
+                }
+                Value::Object(filtered)
+            } else {
+                Value::Null
+            }
+        }
         ContextConfig::Nested(children) => {
             if let Value::Object(map) = value {
                 let mut filtered = Map::new();
@@ -422,4 +1132,316 @@ mod tests {
         assert!(response.get("body").is_none());
         assert_eq!(http.get("other").unwrap(), "keep");
     }
+
+    #[test]
+    fn only_keys_nested_filters_within_headers() {
+        let value = json!({
+            "http": {
+                "request": {
+                    "method": "POST",
+                    "path": "/users",
+                    "body": {"secret": true},
+                    "headers": {
+                        "content-type": "application/json",
+                        "user-agent": "smoo/1.0",
+                        "x-internal": "hidden"
+                    }
+                }
+            }
+        });
+
+        let mut request = HashMap::new();
+        request.insert("method".to_string(), ContextConfig::AllowAll);
+        request.insert("path".to_string(), ContextConfig::AllowAll);
+        request.insert("headers".to_string(), ContextConfig::OnlyKeys(vec!["content-type".into()]));
+        let mut http = HashMap::new();
+        http.insert("request".to_string(), ContextConfig::OnlyKeysNested(request));
+        let mut root = HashMap::new();
+        root.insert("http".to_string(), ContextConfig::Nested(http));
+
+        let filtered = apply_context_config(&value, &ContextConfig::Nested(root));
+        let request = filtered.pointer("/http/request").and_then(|v| v.as_object()).unwrap();
+        assert_eq!(request.get("method").unwrap(), "POST");
+        assert_eq!(request.get("path").unwrap(), "/users");
+        assert!(request.get("body").is_none());
+        let headers = request.get("headers").and_then(|v| v.as_object()).unwrap();
+        assert_eq!(headers.len(), 1);
+        assert_eq!(headers.get("content-type").unwrap(), "application/json");
+    }
+
+    #[test]
+    fn only_keys_nested_drops_keys_emptied_by_sub_config() {
+        let value = json!({"headers": {"x-internal": "hidden"}, "keep": 1});
+        let mut keys = HashMap::new();
+        keys.insert("headers".to_string(), ContextConfig::OnlyKeys(vec!["content-type".into()]));
+        keys.insert("keep".to_string(), ContextConfig::AllowAll);
+
+        let filtered = apply_context_config(&value, &ContextConfig::OnlyKeysNested(keys));
+        assert_eq!(filtered, json!({"keep": 1}));
+    }
+
+    #[test]
+    fn context_limits_evict_oldest_keys() {
+        let _guard = crate::TEST_GLOBAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        reset_global_context();
+        set_context_limits(ContextLimits {
+            max_keys: Some(2),
+            max_bytes: None,
+        });
+        add_nested_context(&json!({"a": 1}));
+        add_nested_context(&json!({"b": 2}));
+        add_nested_context(&json!({"c": 3}));
+        let context = global_context();
+        assert_eq!(context.get("context").unwrap(), &json!({"b": 2, "c": 3}));
+
+        set_context_limits(ContextLimits {
+            max_keys: None,
+            max_bytes: Some(24),
+        });
+        add_nested_context(&json!({"big": "0123456789"}));
+        let context = global_context();
+        assert_eq!(context.get("context").unwrap(), &json!({"big": "0123456789"}));
+
+        set_context_limits(ContextLimits::default());
+        reset_global_context();
+    }
+
+    #[test]
+    fn merge_strategies_treat_arrays_differently() {
+        let base = json!({"tags": ["a", "b"], "user": {"roles": ["admin"]}, "count": 1});
+        let patch = json!({"tags": ["b", "c"], "user": {"roles": ["dev"]}, "count": 2});
+        let merged = |strategy| {
+            let mut target = base.as_object().unwrap().clone();
+            merge_maps_with(&mut target, patch.as_object().unwrap(), strategy);
+            Value::Object(target)
+        };
+
+        assert_eq!(
+            merged(MergeStrategy::Replace),
+            json!({"tags": ["b", "c"], "user": {"roles": ["dev"]}, "count": 2})
+        );
+        assert_eq!(
+            merged(MergeStrategy::ConcatArrays),
+            json!({"tags": ["a", "b", "b", "c"], "user": {"roles": ["admin", "dev"]}, "count": 2})
+        );
+        assert_eq!(
+            merged(MergeStrategy::UnionArrays),
+            json!({"tags": ["a", "b", "c"], "user": {"roles": ["admin", "dev"]}, "count": 2})
+        );
+    }
+
+    #[test]
+    fn truncate_payload_caps_depth_and_field_count() {
+        let mut value = json!({
+            "msg": "hello",
+            "context": {"a": {"b": {"c": 1}}, "list": [[1, 2]]}
+        });
+        truncate_payload(&mut value, Some(2), None);
+        assert_eq!(value["context"]["a"]["b"], TRUNCATED_VALUE);
+        assert_eq!(value["context"]["list"][0], TRUNCATED_VALUE);
+        assert_eq!(value["_truncated"], true);
+
+        let mut value = json!({
+            "msg": "hello",
+            "time": "now",
+            "context": {"a": 1, "b": 2, "nested": {"c": 3}},
+            "tags": ["x", "y"]
+        });
+        truncate_payload(&mut value, None, Some(3));
+        assert_eq!(value, json!({"msg": "hello", "time": "now", "context": {"a": 1}, "_truncated": true}));
+
+        let mut value = json!({"msg": "small", "context": {"a": 1}});
+        truncate_payload(&mut value, Some(5), Some(10));
+        assert!(value.get("_truncated").is_none());
+    }
+
+    #[test]
+    fn redact_paths_handles_wildcards_indices_and_missing_keys() {
+        let mut value = json!({
+            "user": {"email": "a@b.c", "id": "u1"},
+            "items": [{"ssn": "123"}, {"ssn": "456"}],
+            "http": {"request": {"headers": {"x": "1"}, "method": "GET"}},
+            "a": {"token": "t1"},
+            "b": {"token": "t2"}
+        });
+        let paths: Vec<RedactPath> = ["user.email", "items[0].ssn", "http.request.*", "*.token", "missing.key", "items[9].ssn"]
+            .iter()
+            .map(|path| RedactPath::parse(path))
+            .collect();
+        redact_paths(&mut value, &paths);
+        assert_eq!(
+            value,
+            json!({
+                "user": {"email": REDACTED_VALUE, "id": "u1"},
+                "items": [{"ssn": REDACTED_VALUE}, {"ssn": "456"}],
+                "http": {"request": {"headers": {"x": REDACTED_VALUE}, "method": REDACTED_VALUE}},
+                "a": {"token": REDACTED_VALUE},
+                "b": {"token": REDACTED_VALUE}
+            })
+        );
+    }
+
+    #[test]
+    fn redact_config_masks_value_and_keeps_siblings() {
+        let value = json!({
+            "http": {"request": {"method": "GET", "headers": {"authorization": {"scheme": "Bearer"}, "accept": "*/*"}}}
+        });
+        let headers = ContextConfig::Nested(HashMap::from([("authorization".to_string(), ContextConfig::Redact(REDACTED_VALUE.into()))]));
+        let request = ContextConfig::Nested(HashMap::from([("headers".to_string(), headers)]));
+        let http = ContextConfig::Nested(HashMap::from([("request".to_string(), request)]));
+        let config = ContextConfig::Nested(HashMap::from([("http".to_string(), http)]));
+        let filtered = apply_context_config(&value, &config);
+        assert_eq!(
+            filtered,
+            json!({
+                "http": {"request": {"method": "GET", "headers": {"authorization": REDACTED_VALUE, "accept": "*/*"}}}
+            })
+        );
+    }
+
+    #[test]
+    fn context_config_round_trips_through_json() {
+        let value = json!({
+            "http": {
+                "request": ["method", "path"],
+                "response": {"headers": {"set-cookie": {"$redact": "***"}}}
+            },
+            "user": false,
+            "context": "*",
+            "error": {"$only": {"message": "*"}}
+        });
+        let config = ContextConfig::from_json(&value).unwrap();
+        let ContextConfig::Nested(root) = &config else {
+            panic!("expected nested config")
+        };
+        assert_eq!(root["user"], ContextConfig::Deny);
+        assert_eq!(root["context"], ContextConfig::AllowAll);
+
+        let serialized = serde_json::to_value(&config).unwrap();
+        let reparsed: ContextConfig = serde_json::from_value(serialized).unwrap();
+        assert_eq!(reparsed, config);
+    }
+
+    #[test]
+    fn context_config_from_json_reports_path() {
+        let err = ContextConfig::from_json(&json!({"http": {"request": 5}})).unwrap_err();
+        assert_eq!(err.path, "http.request");
+        assert!(ContextConfig::from_json(&json!({"a": {"$redact": 1}})).is_err());
+    }
+
+    #[test]
+    fn scopes_are_thread_local_and_pop_on_drop() {
+        let _guard = crate::TEST_GLOBAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        reset_global_context();
+        add_base_context(&json!({"service": "api"}));
+
+        fn scoped(id: &str) -> Option<String> {
+            let _scope = push_scope(json!({"correlationId": id, "context": {"route": "/users"}}));
+            let other = std::thread::scope(|s| s.spawn(|| base_context_key("correlationId")).join().unwrap());
+            assert_ne!(other, Some(Value::String(id.to_string())));
+            let context = global_context();
+            assert_eq!(context.get("service").unwrap(), "api");
+            assert_eq!(context.pointer("/context/route").unwrap(), "/users");
+            base_context_key("correlationId")?.as_str().map(str::to_string)
+        }
+
+        assert_eq!(scoped("scope-1"), Some("scope-1".to_string()));
+        assert!(global_context().get("context").is_none());
+        assert_ne!(base_context_key("correlationId").unwrap(), "scope-1");
+        reset_global_context();
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn task_scopes_isolate_tasks_and_snapshot_into_children() {
+        let _guard = crate::TEST_GLOBAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        reset_global_context();
+
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let (outer, child) = runtime.block_on(scope(json!({"correlationId": "task-1", "context": {"a": 1}}), async {
+            let child = spawn_scoped(scope(json!({"context": {"b": 2}}), async { global_context() }));
+            (global_context(), child.await.unwrap())
+        }));
+
+        assert_eq!(outer.get("correlationId").unwrap(), "task-1");
+        assert_eq!(outer.get("context").unwrap(), &json!({"a": 1}));
+        assert_eq!(child.get("correlationId").unwrap(), "task-1");
+        assert_eq!(child.get("context").unwrap(), &json!({"a": 1, "b": 2}));
+        assert_ne!(base_context_key("correlationId").unwrap(), "task-1");
+        reset_global_context();
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn task_scopes_keep_their_own_breadcrumbs() {
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let (outer, child) = runtime.block_on(scope(json!({}), async {
+            push_breadcrumb(json!("outer"), 5);
+            let child = spawn_scoped(async {
+                push_breadcrumb(json!("child"), 5);
+                take_breadcrumbs()
+            });
+            let child = child.await.unwrap();
+            (take_breadcrumbs(), child)
+        }));
+        assert_eq!(outer, vec![json!("outer")]);
+        assert_eq!(child, vec![json!("child")]);
+        assert!(take_breadcrumbs().is_empty());
+    }
+
+    #[test]
+    fn correlation_guards_stay_on_their_own_thread() {
+        let _guard = crate::TEST_GLOBAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        reset_global_context();
+        set_correlation_id("process");
+
+        let barrier = std::sync::Barrier::new(2);
+        let seen: Vec<(Value, Value)> = std::thread::scope(|s| {
+            let handles: Vec<_> = ["request-1", "request-2"]
+                .into_iter()
+                .map(|id| {
+                    let barrier = &barrier;
+                    s.spawn(move || {
+                        let correlation = CorrelationGuard::set(id);
+                        // Both guards are live before either thread reads its ID.
+                        barrier.wait();
+                        let inside = base_context_key("correlationId").unwrap();
+                        barrier.wait();
+                        drop(correlation);
+                        (inside, base_context_key("correlationId").unwrap())
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+        });
+
+        assert_eq!(seen, [(json!("request-1"), json!("process")), (json!("request-2"), json!("process"))]);
+        assert_eq!(base_context_key("correlationId").unwrap(), "process");
+        reset_global_context();
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn correlation_guards_stay_on_their_own_task() {
+        let _guard = crate::TEST_GLOBAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        reset_global_context();
+        set_correlation_id("process");
+
+        let request = |id: &'static str| {
+            scope(json!({"correlationId": "task"}), async move {
+                let correlation = CorrelationGuard::set(id);
+                // Let the other request set its ID before this one reads.
+                tokio::task::yield_now().await;
+                let inside = base_context_key("correlationId").unwrap();
+                drop(correlation);
+                (inside, base_context_key("correlationId").unwrap())
+            })
+        };
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let seen = runtime.block_on(async { tokio::join!(request("request-1"), request("request-2")) });
+
+        assert_eq!(seen, ((json!("request-1"), json!("task")), (json!("request-2"), json!("task"))));
+        assert_eq!(base_context_key("correlationId").unwrap(), "process");
+        reset_global_context();
+    }
 }
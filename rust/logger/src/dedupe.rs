@@ -0,0 +1,99 @@
+//! Collapsing of consecutive identical log lines, enabled with
+//! [`LoggerOptions::dedupe`](crate::LoggerOptions::dedupe).
+
+use std::time::Duration;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use parking_lot::Mutex;
+use serde_json::Value;
+
+use crate::context::ContextKey;
+use crate::logger::{order_keys, Level};
+
+/// Collapses runs of lines with the same level, `msg` and `error` (time and context are
+/// ignored). The first line of a run is written as usual; the repeats are held back and
+/// reported as one line, the last repeat with `repeated` set to their count, when a
+/// different line arrives, when a line arrives after `window` has passed since the run
+/// began, on [`Logger::flush`](crate::Logger::flush), or when the last logger sharing the
+/// run is dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DedupeConfig {
+    /// Longest a run is collapsed before its repeats are reported and a new run begins.
+    pub window: Duration,
+}
+
+impl Default for DedupeConfig {
+    fn default() -> Self {
+        Self {
+            window: Duration::from_secs(10),
+        }
+    }
+}
+
+type RunKey = (Level, Option<Value>, Option<Value>);
+
+struct Run {
+    key: RunKey,
+    started: DateTime<Utc>,
+    repeats: u64,
+    last: Option<(Level, Value)>,
+}
+
+impl Run {
+    /// The held-back repeats as one line, if there were any.
+    fn report(&mut self) -> Option<(Level, Value)> {
+        let (level, payload) = self.last.take()?;
+        let repeats = std::mem::take(&mut self.repeats);
+        let Value::Object(mut map) = payload else {
+            return Some((level, payload));
+        };
+        map.insert("repeated".into(), Value::from(repeats));
+        Some((level, Value::Object(order_keys(map))))
+    }
+}
+
+/// Shared run state behind [`DedupeConfig`]; child loggers share their parent's.
+pub(crate) struct Deduper {
+    window: TimeDelta,
+    run: Mutex<Option<Run>>,
+}
+
+impl Deduper {
+    pub(crate) fn new(config: DedupeConfig) -> Self {
+        Self {
+            window: TimeDelta::from_std(config.window).unwrap_or(TimeDelta::MAX),
+            run: Mutex::new(None),
+        }
+    }
+
+    /// Lines to write for `payload`, oldest first: the report of the run it ends, if any,
+    /// then `payload` itself unless it repeats the current run.
+    pub(crate) fn admit(&self, level: Level, payload: Value) -> Vec<(Level, Value)> {
+        let field = |key: ContextKey| payload.get(key.as_str()).cloned();
+        let key = (level, field(ContextKey::Message), field(ContextKey::Error));
+        let now = Utc::now();
+
+        let mut run = self.run.lock();
+        if let Some(current) = run.as_mut().filter(|current| current.key == key && now - current.started < self.window) {
+            current.repeats += 1;
+            current.last = Some((level, payload));
+            return Vec::new();
+        }
+
+        let mut lines: Vec<_> = run.as_mut().and_then(Run::report).into_iter().collect();
+        *run = Some(Run {
+            key,
+            started: now,
+            repeats: 0,
+            last: None,
+        });
+        lines.push((level, payload));
+        lines
+    }
+
+    /// Reports the repeats held back so far. The run stays open, so further repeats are
+    /// collapsed into the next report.
+    pub(crate) fn take_pending(&self) -> Option<(Level, Value)> {
+        self.run.lock().as_mut().and_then(Run::report)
+    }
+}
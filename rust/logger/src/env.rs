@@ -1,17 +1,85 @@
 use std::env;
 
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+
 const MAIN_ENVIRONMENTS: [&str; 3] = ["development", "staging", "production"];
 
+/// Names of the environment variables behind [`is_local`], [`is_build`] and
+/// [`environment`]. The default matches the SST / GitHub Actions / `NODE_ENV` setup.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvConfig {
+    /// Any of these being set means a local run.
+    pub local_vars: Vec<String>,
+    /// A local run is also assumed when this is set to anything other than `"true"`.
+    pub deployed_stage_var: Option<String>,
+    /// Any of these being set means a build / CI run.
+    pub build_vars: Vec<String>,
+    /// Variable holding the environment name.
+    pub environment_var: String,
+    /// Values of `environment_var` that [`environment`] reports; others are ignored.
+    pub environments: Vec<String>,
+}
+
+impl Default for EnvConfig {
+    fn default() -> Self {
+        Self {
+            local_vars: vec!["SST_DEV".into(), "IS_LOCAL".into()],
+            deployed_stage_var: Some("IS_DEPLOYED_STAGE".into()),
+            build_vars: vec!["GITHUB_ACTIONS".into()],
+            environment_var: "NODE_ENV".into(),
+            environments: MAIN_ENVIRONMENTS.iter().map(|name| name.to_string()).collect(),
+        }
+    }
+}
+
+static ENV_CONFIG: Lazy<RwLock<EnvConfig>> = Lazy::new(|| RwLock::new(EnvConfig::default()));
+
+pub fn env_config() -> EnvConfig {
+    ENV_CONFIG.read().clone()
+}
+
+pub fn set_env_config(config: EnvConfig) {
+    *ENV_CONFIG.write() = config;
+}
+
 pub fn is_build() -> bool {
-    env::var("GITHUB_ACTIONS").is_ok()
+    ENV_CONFIG.read().build_vars.iter().any(|var| env::var_os(var).is_some())
 }
 
 pub fn is_local() -> bool {
-    env::var("SST_DEV").is_ok() || env::var("IS_LOCAL").is_ok() || matches!(env::var("IS_DEPLOYED_STAGE"), Ok(value) if value != "true")
+    let config = ENV_CONFIG.read();
+    config.local_vars.iter().any(|var| env::var_os(var).is_some())
+        || config
+            .deployed_stage_var
+            .as_ref()
+            .is_some_and(|var| matches!(env::var(var), Ok(value) if value != "true"))
+}
+
+/// Running inside AWS Lambda.
+pub fn is_lambda() -> bool {
+    env::var_os("AWS_LAMBDA_FUNCTION_NAME").is_some()
+}
+
+/// Running inside an ECS task.
+pub fn is_ecs() -> bool {
+    env::var_os("ECS_CONTAINER_METADATA_URI").is_some() || env::var_os("ECS_CONTAINER_METADATA_URI_V4").is_some()
+}
+
+/// Running under a CI system: GitHub Actions, GitLab CI, Buildkite or anything setting `CI`.
+pub fn is_ci() -> bool {
+    is_build() || ["CI", "GITLAB_CI", "BUILDKITE"].iter().any(|var| env::var_os(var).is_some())
+}
+
+/// Running on managed AWS compute (Lambda or ECS), where logs go to CloudWatch as plain
+/// JSON and the working directory may be read-only.
+pub fn is_aws_runtime() -> bool {
+    is_lambda() || is_ecs()
 }
 
 pub fn environment() -> Option<String> {
-    env::var("NODE_ENV").ok().filter(|value| MAIN_ENVIRONMENTS.contains(&value.as_str()))
+    let config = ENV_CONFIG.read();
+    env::var(&config.environment_var).ok().filter(|value| config.environments.contains(value))
 }
 
 #[cfg(test)]
@@ -59,4 +127,47 @@ mod tests {
             None => env::remove_var("IS_DEPLOYED_STAGE"),
         }
     }
+
+    #[test]
+    fn runtime_detection_reads_env() {
+        let _guard = ENV_LOCK.lock().unwrap();
+        let saved_lambda = env::var("AWS_LAMBDA_FUNCTION_NAME").ok();
+        let saved_gitlab = env::var("GITLAB_CI").ok();
+
+        env::set_var("AWS_LAMBDA_FUNCTION_NAME", "handler");
+        assert!(is_lambda());
+        assert!(is_aws_runtime());
+        env::remove_var("AWS_LAMBDA_FUNCTION_NAME");
+        assert!(!is_lambda());
+
+        env::set_var("GITLAB_CI", "true");
+        assert!(is_ci());
+
+        match saved_lambda {
+            Some(val) => env::set_var("AWS_LAMBDA_FUNCTION_NAME", val),
+            None => env::remove_var("AWS_LAMBDA_FUNCTION_NAME"),
+        }
+        match saved_gitlab {
+            Some(val) => env::set_var("GITLAB_CI", val),
+            None => env::remove_var("GITLAB_CI"),
+        }
+    }
+
+    #[test]
+    fn env_config_overrides_variable_names() {
+        let _guard = ENV_LOCK.lock().unwrap();
+        set_env_config(EnvConfig {
+            local_vars: vec!["MY_DEV_FLAG".into()],
+            deployed_stage_var: None,
+            ..Default::default()
+        });
+
+        env::remove_var("MY_DEV_FLAG");
+        assert!(!is_local());
+        env::set_var("MY_DEV_FLAG", "1");
+        assert!(is_local());
+        env::remove_var("MY_DEV_FLAG");
+
+        set_env_config(EnvConfig::default());
+    }
 }
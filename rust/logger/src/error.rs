@@ -1,10 +1,11 @@
 use std::any::type_name;
+use std::backtrace::{Backtrace, BacktraceStatus};
 use std::error::Error;
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct LoggedError {
     pub message: String,
     #[serde(rename = "name")]
@@ -13,14 +14,129 @@ pub struct LoggedError {
     pub stack: Option<String>,
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
     pub causes: Vec<String>,
+    /// Machine-readable error code for alerting, e.g. `"E1001"` or `"404"`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub code: Option<String>,
+}
+
+/// Errors that carry a code to surface as [`LoggedError::code`]. They convert into a
+/// [`LoggedError`] with the code filled in, so they can be passed to `log_args!` as they
+/// are or through `LoggedError::from`:
+///
+/// ```
+/// use smooai_logger::{log_args, HasErrorCode, LoggedError};
+///
+/// #[derive(Debug)]
+/// struct NotFound;
+///
+/// impl std::fmt::Display for NotFound {
+///     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+///         write!(f, "user not found")
+///     }
+/// }
+///
+/// impl std::error::Error for NotFound {}
+///
+/// impl HasErrorCode for NotFound {
+///     fn error_code(&self) -> Option<String> {
+///         Some("404".into())
+///     }
+/// }
+///
+/// assert_eq!(LoggedError::from(NotFound).code.as_deref(), Some("404"));
+/// let args = log_args!("lookup failed", NotFound);
+/// ```
+pub trait HasErrorCode {
+    fn error_code(&self) -> Option<String>;
 }
 
 impl LoggedError {
+    /// An error built by hand, for failures that aren't a [`std::error::Error`] (an FFI
+    /// status, an error parsed from JSON). `name` is `"Error"` until set with
+    /// [`LoggedError::with_name`].
+    ///
+    /// ```
+    /// use smooai_logger::LoggedError;
+    ///
+    /// let error = LoggedError::new("write failed").with_name("FfiError").with_cause("status -5").with_code("EIO");
+    /// assert_eq!(error.causes, ["status -5"]);
+    /// ```
+    pub fn new<M: Into<String>>(message: M) -> Self {
+        Self::from_parts("Error", message)
+    }
+
+    /// Like [`LoggedError::new`], with the name given up front.
+    pub fn from_parts<N: Into<String>, M: Into<String>>(name: N, message: M) -> Self {
+        LoggedError {
+            message: message.into(),
+            name: name.into(),
+            stack: None,
+            causes: Vec::new(),
+            code: None,
+        }
+    }
+
+    pub fn with_name<N: Into<String>>(mut self, name: N) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    pub fn with_stack<S: Into<String>>(mut self, stack: S) -> Self {
+        self.stack = Some(stack.into());
+        self
+    }
+
+    /// Appends `cause` to the end of the cause chain.
+    pub fn with_cause<C: Into<String>>(mut self, cause: C) -> Self {
+        self.causes.push(cause.into());
+        self
+    }
+
+    pub fn with_code<C: Into<String>>(mut self, code: C) -> Self {
+        self.code = Some(code.into());
+        self
+    }
+
+    /// Wraps this error with `message` as added context: the result's `message` is
+    /// `message`, and its `causes` start with this error's message followed by its causes.
+    /// Name, stack and code carry over, so the original stack is kept.
+    ///
+    /// ```
+    /// use smooai_logger::LoggedError;
+    ///
+    /// let error = LoggedError::new("connection reset").with_cause("tls alert").wrap("loading profile");
+    /// assert_eq!(error.message, "loading profile");
+    /// assert_eq!(error.causes, ["connection reset", "tls alert"]);
+    /// ```
+    pub fn wrap(self, message: &str) -> LoggedError {
+        let mut causes = Vec::with_capacity(self.causes.len() + 1);
+        causes.push(self.message);
+        causes.extend(self.causes);
+        LoggedError {
+            message: message.to_string(),
+            causes,
+            ..self
+        }
+    }
+
+    /// Appends `source` (its message, then its causes) to the end of this error's cause
+    /// chain. `source`'s stack and code fill in only where this error has none.
+    pub fn with_source(mut self, source: LoggedError) -> Self {
+        self.causes.push(source.message);
+        self.causes.extend(source.causes);
+        self.stack = self.stack.or(source.stack);
+        self.code = self.code.or(source.code);
+        self
+    }
+
     pub fn to_value(&self) -> Value {
         serde_json::to_value(self).unwrap_or(Value::Null)
     }
 }
 
+/// Converts `error`, recording its type name, Debug representation and `source` chain.
+/// The generic bound cannot see [`HasErrorCode`], so coded errors keep their code by going
+/// through `LoggedError::from` (or straight into `log_args!`) instead.
 pub fn log_error<E>(error: E) -> LoggedError
 where
     E: Error + Send + Sync + 'static,
@@ -41,6 +157,68 @@ where
         name,
         stack: Some(debug_stack),
         causes,
+        code: None,
+    }
+}
+
+/// Like [`log_error`], with `code` set explicitly.
+pub fn log_error_with_code<E, C>(error: E, code: C) -> LoggedError
+where
+    E: Error + Send + Sync + 'static,
+    C: Into<String>,
+{
+    LoggedError {
+        code: Some(code.into()),
+        ..log_error(error)
+    }
+}
+
+/// Like [`log_error`], with `code` taken from [`HasErrorCode::error_code`].
+impl<E> From<E> for LoggedError
+where
+    E: Error + HasErrorCode + Send + Sync + 'static,
+{
+    fn from(error: E) -> Self {
+        let code = error.error_code();
+        LoggedError { code, ..log_error(error) }
+    }
+}
+
+/// Like [`log_error`], but stores a backtrace captured at the call site in `stack`, with
+/// symbol names resolved. Capture follows `RUST_BACKTRACE` / `RUST_LIB_BACKTRACE`; when
+/// backtraces are disabled the Debug representation is stored as `log_error` does.
+pub fn log_error_with_backtrace<E>(error: E) -> LoggedError
+where
+    E: Error + Send + Sync + 'static,
+{
+    with_backtrace(log_error(error), Backtrace::capture())
+}
+
+fn with_backtrace(mut logged: LoggedError, backtrace: Backtrace) -> LoggedError {
+    if backtrace.status() == BacktraceStatus::Captured {
+        logged.stack = Some(backtrace.to_string());
+    }
+    logged
+}
+
+/// Converts an `anyhow::Error`: `message` is the top-level display, `causes` the rest of
+/// the chain and `stack` the captured backtrace when one is available (otherwise the
+/// Debug representation). anyhow erases the concrete type, so `name` is always `"Error"`.
+#[cfg(feature = "anyhow")]
+pub fn log_anyhow(error: &anyhow::Error) -> LoggedError {
+    let backtrace = error.backtrace();
+    let stack = if backtrace.status() == BacktraceStatus::Captured {
+        backtrace.to_string()
+    } else {
+        format!("{:?}", error)
+    };
+
+    LoggedError {
+        message: error.to_string(),
+        name: "Error".to_string(),
+        stack: Some(stack),
+        causes: error.chain().skip(1).map(|cause| cause.to_string()).collect(),
+        code: None,
     }
 }
 
@@ -63,6 +241,17 @@ mod tests {
 
     impl Error for SampleError {}
 
+    #[test]
+    fn logged_error_round_trips_through_json() {
+        let mut logged = log_error(SampleError);
+        logged.causes = vec!["io failure".into()];
+        let value = logged.to_value();
+        let parsed: LoggedError = serde_json::from_value(value).unwrap();
+        assert_eq!(parsed, logged);
+        let minimal: LoggedError = serde_json::from_str(r#"{"message":"boom","name":"Error"}"#).unwrap();
+        assert!(minimal.stack.is_none() && minimal.causes.is_empty());
+    }
+
     #[test]
     fn log_error_captures_message() {
         let logged = log_error(SampleError);
@@ -70,4 +259,62 @@ mod tests {
         assert_eq!(logged.name, "SampleError");
         assert!(logged.stack.is_some());
     }
+
+    #[test]
+    fn backtrace_replaces_stack_only_when_captured() {
+        let disabled = with_backtrace(log_error(SampleError), Backtrace::disabled());
+        assert_eq!(disabled.stack.as_deref(), Some("SampleError"));
+
+        let captured = with_backtrace(log_error(SampleError), Backtrace::force_capture());
+        assert!(captured.stack.unwrap().contains("backtrace_replaces_stack_only_when_captured"));
+    }
+
+    #[cfg(feature = "anyhow")]
+    #[test]
+    fn log_anyhow_walks_chain() {
+        let error = anyhow::Error::new(SampleError).context("loading config");
+        let logged = log_anyhow(&error);
+        assert_eq!(logged.message, "loading config");
+        assert_eq!(logged.name, "Error");
+        assert_eq!(logged.causes, vec!["sample error".to_string()]);
+        assert!(logged.stack.is_some());
+    }
+
+    impl HasErrorCode for SampleError {
+        fn error_code(&self) -> Option<String> {
+            Some("E_SAMPLE".into())
+        }
+    }
+
+    #[test]
+    fn builder_sets_each_part() {
+        let built = LoggedError::new("status -5").with_stack("ffi.c:12").with_cause("disk").with_cause("controller");
+        assert_eq!(built.name, "Error");
+        assert_eq!(built.stack.as_deref(), Some("ffi.c:12"));
+        assert_eq!(built.causes, ["disk", "controller"]);
+        assert_eq!(LoggedError::from_parts("FfiError", "status -5").with_code("EIO").to_value()["code"], "EIO");
+    }
+
+    #[test]
+    fn wrap_and_with_source_extend_the_cause_chain() {
+        let original = log_error(SampleError).with_cause("io failure");
+        let wrapped = original.clone().wrap("reading config").wrap("starting up");
+        assert_eq!(wrapped.message, "starting up");
+        assert_eq!(wrapped.causes, ["reading config", "sample error", "io failure"]);
+        assert_eq!(wrapped.name, "SampleError");
+        assert_eq!(wrapped.stack, original.stack);
+
+        let merged = LoggedError::new("request failed").with_source(original);
+        assert_eq!(merged.causes, ["sample error", "io failure"]);
+        assert_eq!(merged.stack.as_deref(), Some("SampleError"));
+    }
+
+    #[test]
+    fn error_code_serializes_only_when_set() {
+        assert!(log_error(SampleError).to_value().get("code").is_none());
+        assert_eq!(log_error_with_code(SampleError, "42").to_value().get("code").unwrap(), "42");
+        assert_eq!(LoggedError::from(SampleError).code.as_deref(), Some("E_SAMPLE"));
+        let args = crate::log_args!("coded", SampleError);
+        assert_eq!(args.errors().next().unwrap().code.as_deref(), Some("E_SAMPLE"));
+    }
 }
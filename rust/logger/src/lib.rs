@@ -6,16 +6,45 @@
 
 pub mod aws;
 pub mod context;
+pub mod dedupe;
 pub mod env;
 pub mod error;
+#[cfg(feature = "log")]
+pub mod log_bridge;
 pub mod logger;
+pub mod prelude;
 pub mod pretty;
+#[cfg(feature = "native")]
 pub mod rotation;
+pub mod sink;
+pub mod span;
+#[cfg(feature = "tracing")]
+pub mod tracing_layer;
 
-pub use crate::context::{default_redact_keys, ContextConfig, ContextKey, ContextValue, CONFIG_FULL, CONFIG_MINIMAL, REDACTED_VALUE};
-pub use crate::error::{log_error, LoggedError};
-pub use crate::logger::{Level, LogArgs, Logger, LoggerOptions};
-pub use crate::rotation::RotationOptions;
+pub use crate::context::{
+    default_redact_keys, explicit_null, push_scope, ConfigError, ContextConfig, ContextKey, ContextLimits, ContextValue, CorrelationGuard, MergeStrategy,
+    RedactPath, ScopeGuard, CONFIG_FULL, CONFIG_MINIMAL, EXPLICIT_NULL, REDACTED_VALUE, TRUNCATED_VALUE,
+};
+pub use crate::dedupe::DedupeConfig;
+#[cfg(feature = "anyhow")]
+pub use crate::error::log_anyhow;
+pub use crate::error::{log_error, log_error_with_backtrace, log_error_with_code, HasErrorCode, LoggedError};
+#[cfg(feature = "log")]
+pub use crate::log_bridge::LogBridge;
+#[cfg(feature = "native")]
+pub use crate::logger::FileSinkOptions;
+pub use crate::logger::{ErrorCallback, Level, LogArgs, LogFormat, LogStats, Logger, LoggerOptions, TimeFormat, Timezone};
+pub use crate::pretty::PrettyConfig;
+#[cfg(feature = "native")]
+pub use crate::rotation::{RotationInterval, RotationOptions, SyncMode};
+#[cfg(feature = "wasm")]
+pub use crate::sink::ConsoleSink;
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub use crate::sink::JsCallbackSink;
+pub use crate::sink::{CaptureSink, LogSink, MemorySink, WriterSink};
+pub use crate::span::Span;
+#[cfg(feature = "tracing")]
+pub use crate::tracing_layer::SmooLayer;
 
 pub use serde_json::json;
 
@@ -29,3 +58,15 @@ pub use serde_json::json;
 /// SMOODEV-942 / SMOODEV-943 release pipeline.
 #[cfg(test)]
 pub(crate) static TEST_GLOBAL_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+/// A logger for crate tests that writes compact JSON lines to `sink` and nothing to files.
+/// The output settings of `options` are overridden; everything else is kept.
+#[cfg(test)]
+pub(crate) fn memory_logger(sink: &MemorySink, options: LoggerOptions) -> Logger {
+    Logger::new(LoggerOptions {
+        pretty_print: Some(false),
+        log_to_file: Some(false),
+        sink: Some(std::sync::Arc::new(sink.clone())),
+        ..options
+    })
+}
@@ -0,0 +1,117 @@
+//! Forwards records from the [`log`] crate facade into a [`Logger`].
+
+use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
+use serde_json::{Map, Value};
+
+use crate::context::ContextKey;
+use crate::logger::{Level, LogArgs, Logger};
+
+/// A [`log::Log`] implementation that writes each record through a [`Logger`], with the
+/// record's target as `namespace` and its formatted message as `msg`.
+pub struct LogBridge {
+    logger: Logger,
+}
+
+impl LogBridge {
+    pub fn new(logger: Logger) -> Self {
+        Self { logger }
+    }
+
+    /// The `log` max level matching the wrapped logger's level.
+    pub fn max_level(&self) -> LevelFilter {
+        level_filter(self.logger.level())
+    }
+}
+
+impl Log for LogBridge {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.logger.is_enabled(from_log_level(metadata.level()))
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let mut fields = Map::new();
+        fields.insert(ContextKey::Namespace.as_str().to_string(), Value::String(record.target().to_string()));
+        let args = LogArgs::from(record.args().to_string());
+        let _ = self.logger.log_with_fields(from_log_level(record.level()), args, fields);
+    }
+
+    fn flush(&self) {
+        let _ = self.logger.flush();
+    }
+}
+
+impl Logger {
+    /// Installs this logger as the global `log` crate logger, so `log::info!` and friends
+    /// from dependencies end up in the same output. The `log` max level is set from this
+    /// logger's level; after lowering the level with [`Logger::set_level`], raise it with
+    /// `log::set_max_level` as well. Fails if another global logger was already installed.
+    pub fn init_log_bridge(self) -> Result<(), SetLoggerError> {
+        let bridge = LogBridge::new(self);
+        let max_level = bridge.max_level();
+        log::set_boxed_logger(Box::new(bridge))?;
+        log::set_max_level(max_level);
+        Ok(())
+    }
+}
+
+fn from_log_level(level: log::Level) -> Level {
+    match level {
+        log::Level::Error => Level::Error,
+        log::Level::Warn => Level::Warn,
+        log::Level::Info => Level::Info,
+        log::Level::Debug => Level::Debug,
+        log::Level::Trace => Level::Trace,
+    }
+}
+
+fn level_filter(level: Level) -> LevelFilter {
+    match level {
+        Level::Trace => LevelFilter::Trace,
+        Level::Debug => LevelFilter::Debug,
+        Level::Info => LevelFilter::Info,
+        Level::Warn => LevelFilter::Warn,
+        Level::Error | Level::Fatal => LevelFilter::Error,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logger::LoggerOptions;
+    use crate::sink::MemorySink;
+
+    #[test]
+    fn forwards_records_with_target_as_namespace() {
+        let _guard = crate::TEST_GLOBAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let sink = MemorySink::new();
+        let logger = crate::memory_logger(
+            &sink,
+            LoggerOptions {
+                level: Some(Level::Info),
+                ..Default::default()
+            },
+        );
+        logger.reset_context();
+        let bridge = LogBridge::new(logger);
+        assert_eq!(bridge.max_level(), LevelFilter::Info);
+
+        bridge.log(&Record::builder().level(log::Level::Debug).target("hyper").args(format_args!("skipped")).build());
+        bridge.log(
+            &Record::builder()
+                .level(log::Level::Warn)
+                .target("hyper::client")
+                .args(format_args!("retrying {}", 2))
+                .build(),
+        );
+
+        let lines = sink.lines();
+        assert_eq!(lines.len(), 1);
+        let payload: Value = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!(payload["msg"], "retrying 2");
+        assert_eq!(payload["namespace"], "hyper::client");
+        assert_eq!(payload["LogLevel"], "warn");
+    }
+}
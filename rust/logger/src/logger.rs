@@ -1,23 +1,45 @@
+use std::cell::Cell;
 use std::collections::HashMap;
 use std::error::Error;
 use std::fmt;
+use std::future::Future;
 use std::io::{self, Write};
+#[cfg(feature = "native")]
+use std::io::{BufRead, BufReader};
+#[cfg(feature = "native")]
+use std::path::Path;
+#[cfg(feature = "native")]
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
 use std::sync::Arc;
-
-use chrono::{SecondsFormat, Utc};
+#[cfg(feature = "native")]
+use std::sync::Once;
+#[cfg(feature = "native")]
+use std::thread;
+
+use chrono::format::StrftimeItems;
+use chrono::{DateTime, FixedOffset, Local, SecondsFormat, TimeZone, Utc};
+use parking_lot::Mutex;
+use regex::RegexSet;
+use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
 use url::Url;
 use uuid::Uuid;
 
 use crate::context::{
-    self, add_base_context, add_nested_context, apply_context_config, base_context_key, context_value, default_redact_keys, redact_sensitive_values,
-    remove_nulls, reset_global_context, set_correlation_id, ContextConfig, ContextKey, HttpRequest, HttpResponse, TelemetryFields, User, CONFIG_FULL,
-    CONFIG_MINIMAL,
+    self, add_base_context, add_base_context_with, add_nested_context, add_nested_context_with, apply_context_config, base_context_key, clear_request_ids,
+    context_value, default_redact_keys, push_breadcrumb, redact_paths, redact_sensitive_values, remove_nulls, reset_global_context_with,
+    restore_explicit_nulls, set_context_limits, set_correlation_id, take_breadcrumbs, truncate_payload, ContextConfig, ContextKey, ContextLimits,
+    CorrelationGuard, HttpRequest, HttpResponse, MergeStrategy, RedactPath, TelemetryFields, User, CONFIG_FULL, CONFIG_MINIMAL,
 };
-use crate::env::{is_build, is_local};
-use crate::error::{log_error, LoggedError};
-use crate::pretty;
+use crate::dedupe::{DedupeConfig, Deduper};
+use crate::env::{is_aws_runtime, is_build, is_local};
+use crate::error::{log_error, HasErrorCode, LoggedError};
+use crate::pretty::{self, PrettyConfig};
+#[cfg(feature = "native")]
 use crate::rotation::{RotatingFileWriter, RotationOptions};
+use crate::sink::{CaptureSink, LogSink, WriterSink};
+use crate::span::Span;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Level {
@@ -41,6 +63,18 @@ impl Level {
         }
     }
 
+    /// Display color for the level, shared by pretty output and the log viewer: red for
+    /// error/fatal, orange for warn, blue for info and grays below.
+    pub const fn rgb(&self) -> (u8, u8, u8) {
+        match self {
+            Level::Error | Level::Fatal => (0xff, 0x6b, 0x6c),
+            Level::Warn => (0xf4, 0x9f, 0x0a),
+            Level::Info => (0x5f, 0xb1, 0xdc),
+            Level::Debug => (0x86, 0x86, 0x86),
+            Level::Trace => (0xa3, 0xa3, 0xa3),
+        }
+    }
+
     pub fn code(&self) -> u32 {
         match self {
             Level::Trace => 10,
@@ -52,6 +86,19 @@ impl Level {
         }
     }
 
+    /// Inverse of [`code`](Self::code).
+    pub fn from_code(code: u32) -> Option<Self> {
+        match code {
+            10 => Some(Level::Trace),
+            20 => Some(Level::Debug),
+            30 => Some(Level::Info),
+            40 => Some(Level::Warn),
+            50 => Some(Level::Error),
+            60 => Some(Level::Fatal),
+            _ => None,
+        }
+    }
+
     pub fn parse_level(level: &str) -> Option<Self> {
         match level.to_ascii_lowercase().as_str() {
             "trace" => Some(Level::Trace),
@@ -65,12 +112,253 @@ impl Level {
     }
 }
 
+const DEFAULT_MAX_BREADCRUMBS: usize = 20;
+
+/// Guards the stderr warning printed when a log file cannot be opened.
+#[cfg(feature = "native")]
+static FILE_WARNING: Once = Once::new();
+
+/// Keys that lead each log line, in this order.
+const LEADING_KEYS: [ContextKey; 7] = [
+    ContextKey::Time,
+    ContextKey::Level,
+    ContextKey::LogLevel,
+    ContextKey::Name,
+    ContextKey::Namespace,
+    ContextKey::CorrelationId,
+    ContextKey::Message,
+];
+
+/// Keys that close each log line, in this order.
+const TRAILING_KEYS: [ContextKey; 2] = [ContextKey::Error, ContextKey::ErrorDetails];
+
+/// Rebuilds a payload with a stable key order: [`LEADING_KEYS`], then every other key
+/// alphabetically, then [`TRAILING_KEYS`]. Nested objects keep their own order.
+pub(crate) fn order_keys(mut map: Map<String, Value>) -> Map<String, Value> {
+    let mut ordered = Map::with_capacity(map.len());
+    for key in LEADING_KEYS {
+        if let Some(value) = map.shift_remove(key.as_str()) {
+            ordered.insert(key.as_str().to_string(), value);
+        }
+    }
+    let trailing: Vec<_> = TRAILING_KEYS
+        .iter()
+        .filter_map(|key| map.shift_remove(key.as_str()).map(|value| (key.as_str(), value)))
+        .collect();
+    let mut rest: Vec<_> = map.into_iter().collect();
+    rest.sort_by(|(a, _), (b, _)| a.cmp(b));
+    ordered.extend(rest);
+    ordered.extend(trailing.into_iter().map(|(key, value)| (key.to_string(), value)));
+    ordered
+}
+
 impl fmt::Display for Level {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.as_str())
     }
 }
 
+impl Serialize for Level {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+/// Accepts the same names as [`Level::parse_level`], so `"warning"` reads as `Warn`.
+impl<'de> Deserialize<'de> for Level {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Level::parse_level(&name).ok_or_else(|| serde::de::Error::custom(format!("unknown log level `{name}`")))
+    }
+}
+
+/// Output format for each log line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// One JSON object per line.
+    Json,
+    /// Indented, optionally colored JSON followed by separators.
+    Pretty,
+    /// `key=value` pairs with nested keys flattened (see [`pretty::logfmt`]).
+    Logfmt,
+}
+
+/// How the `time` field is written.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum TimeFormat {
+    /// RFC 3339 with millisecond precision, e.g. `2024-05-01T12:00:00.123Z`.
+    #[default]
+    Rfc3339Millis,
+    /// RFC 3339 with nanosecond precision.
+    Rfc3339Nanos,
+    /// Milliseconds since the Unix epoch, as a number.
+    EpochMillis,
+    /// Seconds since the Unix epoch, as a number.
+    EpochSeconds,
+    /// A `strftime` pattern (see [`chrono::format::strftime`]). Invalid patterns fall back
+    /// to [`TimeFormat::Rfc3339Millis`].
+    Custom(String),
+}
+
+/// Time zone for the string [`TimeFormat`]s; epoch formats are zone-independent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Timezone {
+    #[default]
+    Utc,
+    /// The system's local time zone.
+    Local,
+    Fixed(FixedOffset),
+}
+
+impl TimeFormat {
+    fn timestamp(&self, now: DateTime<Utc>, timezone: Timezone) -> Value {
+        match (self, timezone) {
+            (TimeFormat::EpochMillis, _) => Value::from(now.timestamp_millis()),
+            (TimeFormat::EpochSeconds, _) => Value::from(now.timestamp()),
+            (_, Timezone::Utc) => Value::String(self.render(now)),
+            (_, Timezone::Local) => Value::String(self.render(now.with_timezone(&Local))),
+            (_, Timezone::Fixed(offset)) => Value::String(self.render(now.with_timezone(&offset))),
+        }
+    }
+
+    fn render<Tz: TimeZone>(&self, time: DateTime<Tz>) -> String
+    where
+        Tz::Offset: fmt::Display,
+    {
+        match self {
+            TimeFormat::Rfc3339Nanos => time.to_rfc3339_opts(SecondsFormat::Nanos, true),
+            TimeFormat::Custom(pattern) => match StrftimeItems::new(pattern).parse() {
+                Ok(items) => time.format_with_items(items.iter()).to_string(),
+                Err(_) => time.to_rfc3339_opts(SecondsFormat::Millis, true),
+            },
+            _ => time.to_rfc3339_opts(SecondsFormat::Millis, true),
+        }
+    }
+}
+
+/// An additional rotating log file written alongside the main output, in its own format.
+#[cfg(feature = "native")]
+#[derive(Debug, Clone)]
+pub struct FileSinkOptions {
+    pub format: LogFormat,
+    /// Where and how this file rotates; retention applies to this file set only, so give
+    /// each sink its own `path` or `filename_prefix`/`extension`.
+    pub rotation: RotationOptions,
+    /// Colors for [`LogFormat::Pretty`] output. When `None`, follows the main log file.
+    /// Other formats never contain ANSI codes.
+    pub color: Option<bool>,
+}
+
+/// Called with each error from writing a line or opening, rotating, writing or compressing a log file,
+/// e.g. to alert when the disk is full. Set through [`LoggerOptions::on_error`].
+#[derive(Clone)]
+pub struct ErrorCallback(Arc<dyn Fn(&io::Error) + Send + Sync>);
+
+impl ErrorCallback {
+    pub fn new<F: Fn(&io::Error) + Send + Sync + 'static>(callback: F) -> Self {
+        Self(Arc::new(callback))
+    }
+
+    pub(crate) fn call(&self, error: &io::Error) {
+        (self.0)(error)
+    }
+}
+
+/// Per-level line counts and bytes written since the logger was created or since the last
+/// [`Logger::reset_stats`]. Returned by [`Logger::stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct LogStats {
+    pub trace: u64,
+    pub debug: u64,
+    pub info: u64,
+    pub warn: u64,
+    pub error: u64,
+    pub fatal: u64,
+    /// Bytes of rendered output written to the primary output (sink or stdout).
+    pub bytes: u64,
+}
+
+impl LogStats {
+    /// Number of lines emitted at `level`.
+    pub fn count(&self, level: Level) -> u64 {
+        match level {
+            Level::Trace => self.trace,
+            Level::Debug => self.debug,
+            Level::Info => self.info,
+            Level::Warn => self.warn,
+            Level::Error => self.error,
+            Level::Fatal => self.fatal,
+        }
+    }
+
+    /// Number of lines emitted across all levels.
+    pub fn total(&self) -> u64 {
+        self.trace + self.debug + self.info + self.warn + self.error + self.fatal
+    }
+}
+
+/// Compiled [`LoggerOptions::include_namespaces`] and [`LoggerOptions::exclude_namespaces`].
+struct NamespaceFilter {
+    include: Option<RegexSet>,
+    exclude: Option<RegexSet>,
+}
+
+impl NamespaceFilter {
+    /// `None` when both lists are empty.
+    fn new(include: &[String], exclude: &[String]) -> Result<Option<Self>, regex::Error> {
+        let compile = |patterns: &[String]| (!patterns.is_empty()).then(|| RegexSet::new(patterns)).transpose();
+        let filter = NamespaceFilter {
+            include: compile(include)?,
+            exclude: compile(exclude)?,
+        };
+        Ok((filter.include.is_some() || filter.exclude.is_some()).then_some(filter))
+    }
+
+    fn allows(&self, namespace: &str) -> bool {
+        self.include.as_ref().is_none_or(|include| include.is_match(namespace)) && !self.exclude.as_ref().is_some_and(|exclude| exclude.is_match(namespace))
+    }
+}
+
+/// Relaxed counters behind [`Logger::stats`], shared between a logger and its children.
+#[derive(Default)]
+struct StatCounters {
+    lines: [AtomicU64; 6],
+    bytes: AtomicU64,
+}
+
+impl StatCounters {
+    fn record(&self, level: Level, bytes: usize) {
+        self.lines[level as usize].fetch_add(1, Ordering::Relaxed);
+        self.bytes.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> LogStats {
+        let count = |level: Level| self.lines[level as usize].load(Ordering::Relaxed);
+        LogStats {
+            trace: count(Level::Trace),
+            debug: count(Level::Debug),
+            info: count(Level::Info),
+            warn: count(Level::Warn),
+            error: count(Level::Error),
+            fatal: count(Level::Fatal),
+            bytes: self.bytes.load(Ordering::Relaxed),
+        }
+    }
+
+    fn reset(&self) {
+        for line in &self.lines {
+            line.store(0, Ordering::Relaxed);
+        }
+        self.bytes.store(0, Ordering::Relaxed);
+    }
+}
+
+impl fmt::Debug for ErrorCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ErrorCallback")
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct LoggerOptions {
     pub name: Option<String>,
@@ -78,12 +366,132 @@ pub struct LoggerOptions {
     pub level: Option<Level>,
     pub context_config: Option<ContextConfig>,
     pub pretty_print: Option<bool>,
+    /// Output format. When `None`, `pretty_print` picks between `Pretty` and `Json`.
+    pub format: Option<LogFormat>,
+    /// Format of the `time` field. Defaults to [`TimeFormat::Rfc3339Millis`].
+    pub time_format: Option<TimeFormat>,
+    /// Time zone of the `time` field for string formats. Defaults to [`Timezone::Utc`].
+    pub timezone: Option<Timezone>,
+    /// Forces ANSI colors in pretty output on or off, for the console and log files alike.
+    /// When `None`, console colors follow `NO_COLOR`, `TERM=dumb` and whether stdout is a
+    /// terminal, while log files are always colored.
+    pub force_color: Option<bool>,
+    /// Separator and key colors for pretty output. Defaults to [`PrettyConfig::default`].
+    pub pretty_config: Option<PrettyConfig>,
+    /// Ignored without the `native` feature, which provides file output.
     pub log_to_file: Option<bool>,
+    #[cfg(feature = "native")]
     pub rotation: Option<RotationOptions>,
+    /// Further files to write every line to, each rendered in its own format (e.g. pretty
+    /// ANSI in one and JSONL for tooling in another). These are written whenever configured,
+    /// regardless of `log_to_file`.
+    #[cfg(feature = "native")]
+    pub extra_file_sinks: Vec<FileSinkOptions>,
     pub config_settings: Option<HashMap<String, ContextConfig>>,
     /// Optional override for the redact-keys list. When `None`, defaults from
     /// [`default_redact_keys`] are used.
     pub redact_keys: Option<Vec<String>>,
+    /// Dotted key paths (e.g. `user.email`, `items[0].ssn`, `*.token`, `http.request.*`)
+    /// whose values are replaced with `"[REDACTED]"`. See [`RedactPath`].
+    pub redact: Vec<String>,
+    /// Caps on the keys and size of the shared `context` object. The limits are process-wide
+    /// because the context is; when `None`, the current limits are left unchanged.
+    pub context_limits: Option<ContextLimits>,
+    /// Destination for formatted lines in place of stdout. The rotating file output, when
+    /// enabled, is written as well.
+    pub sink: Option<Arc<dyn LogSink>>,
+    /// Fraction (0.0–1.0) of `trace`/`debug`/`info` lines to emit. `warn` and above are
+    /// never sampled. Lines sampled out are counted in [`Logger::dropped_count`].
+    pub sample_rate: Option<f64>,
+    /// Make sampling decisions from the correlation ID rather than at random, so every line
+    /// of a sampled-in request is kept together. The ID is hashed with 64-bit FNV-1a, so
+    /// every process and release keeps or drops the same requests.
+    pub sample_by_correlation: bool,
+    /// Regexes matched against each line's `namespace`; matching lines are dropped and
+    /// counted in [`Logger::dropped_count`]. Lines without a namespace are never filtered.
+    pub exclude_namespaces: Vec<String>,
+    /// When non-empty, only lines whose `namespace` matches one of these regexes are kept
+    /// (exclusions still apply). Lines without a namespace are never filtered.
+    pub include_namespaces: Vec<String>,
+    /// Seed `correlationId`/`requestId`/`traceId` with a generated UUID (default `true`).
+    /// When `false`, IDs already seeded into the shared context are removed and
+    /// [`Logger::reset_context`] leaves them unset until they are set explicitly.
+    pub seed_ids: Option<bool>,
+    /// Objects and arrays nested more than this many levels below the root of a line are
+    /// replaced with `"[truncated]"`. Applied after config filtering and redaction.
+    pub max_context_depth: Option<usize>,
+    /// Maximum number of leaf values per line; nested fields past the limit are dropped.
+    /// Lines cut by either limit get `"_truncated": true`. See [`truncate_payload`].
+    pub max_field_count: Option<usize>,
+    /// How many recent [`Logger::breadcrumb`] entries to keep (default 20; `0` disables them).
+    pub max_breadcrumbs: Option<usize>,
+    /// Invoked whenever an output fails. Failed writes still return `Err` from the log call,
+    /// but the remaining outputs are written first.
+    pub on_error: Option<ErrorCallback>,
+    /// Collapse consecutive identical lines into one line with a `repeated` count.
+    pub dedupe: Option<DedupeConfig>,
+}
+
+/// Where lines go when no [`LogSink`] is configured: stdout with the `native` feature, the
+/// browser console with `wasm` alone, and nowhere otherwise.
+#[cfg(feature = "native")]
+fn write_default_output(_level: Level, output: &str) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    stdout.write_all(output.as_bytes())?;
+    stdout.flush()
+}
+
+#[cfg(all(not(feature = "native"), feature = "wasm"))]
+fn write_default_output(level: Level, output: &str) -> io::Result<()> {
+    crate::sink::ConsoleSink.write(level, output)
+}
+
+#[cfg(not(any(feature = "native", feature = "wasm")))]
+fn write_default_output(_level: Level, _output: &str) -> io::Result<()> {
+    Ok(())
+}
+
+/// Where a line logged from inside an output goes instead of recursing: stderr with the
+/// `native` feature, the browser console with `wasm` alone, and nowhere otherwise.
+#[cfg(feature = "native")]
+fn write_nested_output(_level: Level, output: &str) -> io::Result<()> {
+    io::stderr().write_all(output.as_bytes())
+}
+
+#[cfg(all(not(feature = "native"), feature = "wasm"))]
+fn write_nested_output(level: Level, output: &str) -> io::Result<()> {
+    crate::sink::ConsoleSink.write(level, output)
+}
+
+#[cfg(not(any(feature = "native", feature = "wasm")))]
+fn write_nested_output(_level: Level, _output: &str) -> io::Result<()> {
+    Ok(())
+}
+
+/// Looks up a header by name, ignoring ASCII case.
+fn header_value<'a>(headers: &'a Map<String, Value>, name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .and_then(|(_, value)| value.as_str())
+}
+
+/// The `service` a logger falls back to: `SERVICE_NAME`, then the OpenTelemetry
+/// `OTEL_SERVICE_NAME`. Variables are read through `lookup` so tests need not touch the
+/// process environment.
+fn default_service(lookup: fn(&str) -> Option<String>) -> Option<String> {
+    ["SERVICE_NAME", "OTEL_SERVICE_NAME"]
+        .into_iter()
+        .filter_map(lookup)
+        .find(|service| !service.trim().is_empty())
+}
+
+/// 64-bit FNV-1a. Unlike `DefaultHasher`, its output is fixed, so correlation sampling
+/// agrees across processes and Rust versions.
+fn fnv1a_64(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+    bytes.iter().fold(OFFSET_BASIS, |hash, &byte| (hash ^ u64::from(byte)).wrapping_mul(PRIME))
 }
 
 fn default_config_settings() -> HashMap<String, ContextConfig> {
@@ -94,16 +502,104 @@ fn default_config_settings() -> HashMap<String, ContextConfig> {
     settings
 }
 
+enum TraceParent<'a> {
+    Parsed { trace_id: &'a str, span_id: &'a str },
+    Opaque(&'a str),
+}
+
+fn parse_traceparent(header: &str) -> Option<TraceParent<'_>> {
+    let header = header.trim();
+    if header.is_empty() {
+        return None;
+    }
+
+    let is_hex = |field: &str, len: usize| field.len() == len && field.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b));
+    let is_zero = |field: &str| field.bytes().all(|b| b == b'0');
+
+    let mut fields = header.split('-');
+    let version = fields.next()?;
+    if !is_hex(version, 2) || version == "ff" {
+        return Some(TraceParent::Opaque(header));
+    }
+
+    let (trace_id, span_id, flags) = (fields.next()?, fields.next()?, fields.next()?);
+    if version == "00" && fields.next().is_some() {
+        return None;
+    }
+    if !is_hex(trace_id, 32) || is_zero(trace_id) || !is_hex(span_id, 16) || is_zero(span_id) || !is_hex(flags, 2) {
+        return None;
+    }
+    Some(TraceParent::Parsed { trace_id, span_id })
+}
+
+thread_local! {
+    static IN_EMIT: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Marks the current thread as emitting for as long as the guard lives. A second
+/// `enter` on the same thread returns `None`, which is how [`Logger`] detects a sink
+/// that logs back through the logger while a line is being written.
+struct EmitGuard;
+
+impl EmitGuard {
+    fn enter() -> Option<Self> {
+        IN_EMIT.with(|flag| if flag.replace(true) { None } else { Some(EmitGuard) })
+    }
+}
+
+impl Drop for EmitGuard {
+    fn drop(&mut self) {
+        IN_EMIT.with(|flag| flag.set(false));
+    }
+}
+
 pub struct Logger {
     name: String,
-    level: Level,
+    /// [`Level::code`] of the minimum level, atomic so [`Logger::set_level`] works through `&self`.
+    level: AtomicU32,
+    /// Set by [`Logger::disable`]; silences every level, including `fatal`.
+    disabled: AtomicBool,
     context_config: Option<ContextConfig>,
     config_settings: HashMap<String, ContextConfig>,
-    pretty_print: bool,
+    format: LogFormat,
+    time_format: TimeFormat,
+    timezone: Timezone,
+    /// Colors for the sink or stdout, which follow the terminal.
+    color: bool,
+    /// Colors for the main log file, which do not depend on stdout.
+    #[cfg(feature = "native")]
+    file_color: bool,
+    pretty_config: PrettyConfig,
     log_to_file: bool,
+    #[cfg(feature = "native")]
     rotation: RotationOptions,
+    #[cfg(feature = "native")]
     file_writer: Option<Arc<RotatingFileWriter>>,
+    #[cfg(feature = "native")]
+    extra_file_writers: Vec<(LogFormat, bool, Arc<RotatingFileWriter>)>,
+    sink: Option<Arc<dyn LogSink>>,
     redact_keys: std::collections::HashSet<String>,
+    redact_paths: Vec<RedactPath>,
+    sample_rate: Option<f64>,
+    sample_by_correlation: bool,
+    namespace_filter: Option<Arc<NamespaceFilter>>,
+    seed_ids: bool,
+    max_context_depth: Option<usize>,
+    max_field_count: Option<usize>,
+    max_breadcrumbs: usize,
+    on_error: Option<ErrorCallback>,
+    /// Replaces [`write_nested_output`] for lines logged from inside an output, so tests can
+    /// capture them.
+    nested_sink: Option<Arc<dyn LogSink>>,
+    dropped: Arc<AtomicU64>,
+    dedupe: Option<Arc<Deduper>>,
+    stats: Arc<StatCounters>,
+    /// Base context owned by this instance (see [`Logger::child`]), merged over the global
+    /// context when building each log object.
+    instance_context: Map<String, Value>,
+    /// `service` for lines whose context names none, read from the environment by
+    /// [`Logger::new`].
+    default_service: Option<String>,
 }
 
 impl Default for Logger {
@@ -112,22 +608,65 @@ impl Default for Logger {
     }
 }
 
+impl Drop for Logger {
+    fn drop(&mut self) {
+        // Children share the deduper; only the last one reports the open run.
+        if let Some(dedupe) = self.dedupe.take().filter(|dedupe| Arc::strong_count(dedupe) == 1) {
+            if let Some((level, payload)) = dedupe.take_pending() {
+                let _ = self.emit(level, payload);
+            }
+        }
+    }
+}
+
 impl Logger {
-    pub fn new(mut options: LoggerOptions) -> Self {
+    /// Creates a logger from `options`.
+    ///
+    /// # Panics
+    ///
+    /// If a pattern in `include_namespaces` or `exclude_namespaces` is not a valid regex;
+    /// use [`Logger::try_new`] to handle that as an error.
+    pub fn new(options: LoggerOptions) -> Self {
+        match NamespaceFilter::new(&options.include_namespaces, &options.exclude_namespaces) {
+            Ok(namespace_filter) => Self::with_namespace_filter(options, namespace_filter),
+            Err(error) => {
+                let pattern = options
+                    .include_namespaces
+                    .iter()
+                    .chain(&options.exclude_namespaces)
+                    .find(|pattern| regex::Regex::new(pattern).is_err())
+                    .map_or_else(String::new, |pattern| format!(" {pattern:?}"));
+                panic!("smooai-logger: invalid namespace filter{pattern} (use Logger::try_new to handle this as an error): {error}")
+            }
+        }
+    }
+
+    /// Like [`Logger::new`], returning an error for an invalid namespace filter pattern.
+    pub fn try_new(options: LoggerOptions) -> Result<Self, regex::Error> {
+        let namespace_filter = NamespaceFilter::new(&options.include_namespaces, &options.exclude_namespaces)?;
+        Ok(Self::with_namespace_filter(options, namespace_filter))
+    }
+
+    fn with_namespace_filter(mut options: LoggerOptions, namespace_filter: Option<NamespaceFilter>) -> Self {
+        let namespace_filter = namespace_filter.map(Arc::new);
         let name = options.name.take().unwrap_or_else(|| "Logger".to_string());
         let level = options
             .level
             .or_else(|| std::env::var("LOG_LEVEL").ok().and_then(|lvl| Level::parse_level(&lvl)))
             .unwrap_or(Level::Info);
-        let pretty_print = options.pretty_print.unwrap_or_else(|| is_local() || is_build());
-
-        let rotation = options.rotation.unwrap_or_default();
+        let pretty_print = options.pretty_print.unwrap_or_else(|| (is_local() || is_build()) && !is_aws_runtime());
 
         let mut config_settings = options.config_settings.unwrap_or_else(default_config_settings);
 
         let context_config = options
             .context_config
             .take()
+            .or_else(|| {
+                std::env::var("LOGGER_CONTEXT_CONFIG_JSON")
+                    .ok()
+                    .and_then(|raw| serde_json::from_str::<Value>(&raw).ok())
+                    .and_then(|value| ContextConfig::from_json(&value).ok())
+            })
             .or_else(|| std::env::var("LOGGER_CONTEXT_CONFIG").ok().and_then(|key| config_settings.get(&key).cloned()));
 
         if !config_settings.contains_key("FULL") {
@@ -138,12 +677,47 @@ impl Logger {
             config_settings.insert("MINIMAL".into(), (*CONFIG_MINIMAL).clone());
         }
 
-        let log_to_file = options.log_to_file.unwrap_or_else(is_local);
-        let file_writer = if log_to_file {
-            RotatingFileWriter::new(rotation.clone()).ok().map(Arc::new)
+        let on_error = options.on_error.take();
+        let color = pretty::color_enabled(options.force_color);
+        #[cfg(feature = "native")]
+        let file_color = options.force_color.unwrap_or(true);
+
+        #[cfg(feature = "native")]
+        let rotation = options.rotation.unwrap_or_default();
+        #[cfg(feature = "native")]
+        let open_file = |rotation: RotationOptions| match RotatingFileWriter::new(rotation.clone()) {
+            Ok(writer) => Some(Arc::new(writer.with_on_error(on_error.clone()))),
+            Err(error) => {
+                FILE_WARNING.call_once(|| {
+                    eprintln!("smooai-logger: file logging disabled, cannot write to {}: {error}", rotation.path.display());
+                });
+                if let Some(on_error) = &on_error {
+                    on_error.call(&error);
+                }
+                None
+            }
+        };
+        #[cfg(feature = "native")]
+        let file_writer = if options.log_to_file.unwrap_or_else(|| is_local() && !is_aws_runtime()) {
+            open_file(rotation.clone())
         } else {
             None
         };
+        #[cfg(feature = "native")]
+        let extra_file_writers = options
+            .extra_file_sinks
+            .drain(..)
+            .filter_map(|extra| Some((extra.format, extra.color.unwrap_or(file_color), open_file(extra.rotation)?)))
+            .collect();
+
+        if let Some(limits) = options.context_limits.take() {
+            set_context_limits(limits);
+        }
+
+        let seed_ids = options.seed_ids.unwrap_or(true);
+        if !seed_ids {
+            clear_request_ids();
+        }
 
         if let Some(context) = options.context.take() {
             let mut context = context;
@@ -163,17 +737,117 @@ impl Logger {
 
         Self {
             name,
-            level,
+            level: AtomicU32::new(level.code()),
+            disabled: AtomicBool::new(false),
             context_config,
             config_settings,
-            pretty_print,
+            format: options.format.unwrap_or(if pretty_print { LogFormat::Pretty } else { LogFormat::Json }),
+            time_format: options.time_format.take().unwrap_or_default(),
+            timezone: options.timezone.unwrap_or_default(),
+            color,
+            #[cfg(feature = "native")]
+            file_color,
+            pretty_config: options.pretty_config.unwrap_or_default(),
+            #[cfg(feature = "native")]
             log_to_file: file_writer.is_some(),
+            #[cfg(not(feature = "native"))]
+            log_to_file: false,
+            #[cfg(feature = "native")]
             rotation,
+            #[cfg(feature = "native")]
             file_writer,
+            #[cfg(feature = "native")]
+            extra_file_writers,
+            sink: options.sink,
             redact_keys,
+            redact_paths: options.redact.iter().map(|path| RedactPath::parse(path)).collect(),
+            sample_rate: options.sample_rate.map(|rate| rate.clamp(0.0, 1.0)),
+            sample_by_correlation: options.sample_by_correlation,
+            namespace_filter,
+            seed_ids,
+            max_context_depth: options.max_context_depth,
+            max_field_count: options.max_field_count,
+            max_breadcrumbs: options.max_breadcrumbs.unwrap_or(DEFAULT_MAX_BREADCRUMBS),
+            on_error,
+            nested_sink: None,
+            dropped: Arc::new(AtomicU64::new(0)),
+            dedupe: options.dedupe.map(|config| Arc::new(Deduper::new(config))),
+            stats: Arc::new(StatCounters::default()),
+            instance_context: Map::new(),
+            default_service: default_service(|var| std::env::var(var).ok()),
         }
     }
 
+    /// Creates a logger that writes formatted lines to `writer` instead of stdout.
+    pub fn with_writer<W>(mut options: LoggerOptions, writer: W) -> Self
+    where
+        W: Write + Send + 'static,
+    {
+        options.sink = Some(Arc::new(WriterSink::new(writer)));
+        Logger::new(options)
+    }
+
+    /// Creates a sub-logger that shares this logger's configuration and file writer and
+    /// adds `context` as base context for its own logs only. The global context is not
+    /// modified, so sibling children never see each other's context.
+    pub fn child(&self, mut context: Value) -> Logger {
+        remove_nulls(&mut context);
+        let mut instance_context = self.instance_context.clone();
+        if let Value::Object(extra) = &context {
+            context::merge_maps(&mut instance_context, extra);
+        }
+
+        Logger {
+            name: self.name.clone(),
+            level: AtomicU32::new(self.level().code()),
+            disabled: AtomicBool::new(self.is_disabled()),
+            context_config: self.context_config.clone(),
+            config_settings: self.config_settings.clone(),
+            format: self.format,
+            time_format: self.time_format.clone(),
+            timezone: self.timezone,
+            color: self.color,
+            #[cfg(feature = "native")]
+            file_color: self.file_color,
+            pretty_config: self.pretty_config.clone(),
+            log_to_file: self.log_to_file,
+            #[cfg(feature = "native")]
+            rotation: self.rotation.clone(),
+            #[cfg(feature = "native")]
+            file_writer: self.file_writer.clone(),
+            #[cfg(feature = "native")]
+            extra_file_writers: self.extra_file_writers.clone(),
+            sink: self.sink.clone(),
+            redact_keys: self.redact_keys.clone(),
+            redact_paths: self.redact_paths.clone(),
+            sample_rate: self.sample_rate,
+            sample_by_correlation: self.sample_by_correlation,
+            namespace_filter: self.namespace_filter.clone(),
+            seed_ids: self.seed_ids,
+            max_context_depth: self.max_context_depth,
+            max_field_count: self.max_field_count,
+            max_breadcrumbs: self.max_breadcrumbs,
+            on_error: self.on_error.clone(),
+            nested_sink: self.nested_sink.clone(),
+            dropped: self.dropped.clone(),
+            dedupe: self.dedupe.clone(),
+            stats: self.stats.clone(),
+            instance_context,
+            default_service: self.default_service.clone(),
+        }
+    }
+
+    /// Appends `:suffix` to the logger's name, e.g. `Parent` becomes `Parent:child`.
+    pub fn with_name_suffix(mut self, suffix: &str) -> Self {
+        self.name = format!("{}:{}", self.name, suffix);
+        self
+    }
+
+    /// Returns the base context owned by this instance.
+    pub fn instance_context(&self) -> Value {
+        Value::Object(self.instance_context.clone())
+    }
+
     /// Returns the current redact-keys list (lowercased).
     pub fn redact_keys(&self) -> Vec<String> {
         let mut keys: Vec<String> = self.redact_keys.iter().cloned().collect();
@@ -202,22 +876,90 @@ impl Logger {
         &self.name
     }
 
+    /// A logger for unit tests: every line, at any level, is parsed back into a [`Value`] and
+    /// pushed onto the returned list instead of going to stdout or files. The shared context
+    /// is left as it is.
+    ///
+    /// ```
+    /// use smooai_logger::Logger;
+    ///
+    /// let (logger, captured) = Logger::testing();
+    /// logger.info("expected").unwrap();
+    /// assert_eq!(captured.lock()[0]["msg"], "expected");
+    /// ```
+    pub fn testing() -> (Logger, Arc<Mutex<Vec<Value>>>) {
+        let sink = CaptureSink::new();
+        let captured = sink.values();
+        let mut logger = Logger::new(LoggerOptions {
+            level: Some(Level::Trace),
+            format: Some(LogFormat::Json),
+            log_to_file: Some(false),
+            sink: Some(Arc::new(sink)),
+            ..Default::default()
+        });
+        logger.default_service = None;
+        (logger, captured)
+    }
+
     pub fn set_name<S: Into<String>>(&mut self, name: S) {
         self.name = name.into();
     }
 
     pub fn level(&self) -> Level {
-        self.level
+        Level::from_code(self.level.load(Ordering::Relaxed)).unwrap_or(Level::Info)
+    }
+
+    /// Changes the minimum level. Takes `&self`, so a logger shared behind an `Arc` can be
+    /// turned up to `debug` (and back) from a signal handler or admin endpoint.
+    pub fn set_level(&self, level: Level) {
+        self.level.store(level.code(), Ordering::Relaxed);
+    }
+
+    /// Silences the logger: nothing is written at any level until [`enable`](Self::enable) is
+    /// called. Works on a shared reference, so a logger behind an `Arc` can be muted at runtime.
+    pub fn disable(&self) {
+        self.disabled.store(true, Ordering::Relaxed);
+    }
+
+    /// Undoes [`disable`](Self::disable); lines at or above the logger's level are written again.
+    pub fn enable(&self) {
+        self.disabled.store(false, Ordering::Relaxed);
     }
 
-    pub fn set_level(&mut self, level: Level) {
-        self.level = level;
+    pub fn is_disabled(&self) -> bool {
+        self.disabled.load(Ordering::Relaxed)
     }
 
+    #[cfg(feature = "native")]
     pub fn rotation_options(&self) -> &RotationOptions {
         &self.rotation
     }
 
+    pub fn format(&self) -> LogFormat {
+        self.format
+    }
+
+    /// Writes any repeats held back by [`LoggerOptions::dedupe`], then flushes buffered file
+    /// output (see `RotationOptions::buffered`) and the sink, if any.
+    pub fn flush(&self) -> io::Result<()> {
+        if let Some((level, payload)) = self.dedupe.as_ref().and_then(|dedupe| dedupe.take_pending()) {
+            self.emit(level, payload)?;
+        }
+        #[cfg(feature = "native")]
+        {
+            if let Some(writer) = &self.file_writer {
+                writer.flush()?;
+            }
+            for (_, _, writer) in &self.extra_file_writers {
+                writer.flush()?;
+            }
+        }
+        if let Some(sink) = &self.sink {
+            sink.flush()?;
+        }
+        Ok(())
+    }
+
     pub fn logs_to_file(&self) -> bool {
         self.log_to_file
     }
@@ -226,6 +968,12 @@ impl Logger {
         self.add_base_context_key(ContextKey::Namespace.as_str(), Value::String(namespace.into()));
     }
 
+    /// Sets the `service` base-context key. Without it, [`Logger::new`] loggers fall back to
+    /// `SERVICE_NAME`, then `OTEL_SERVICE_NAME`; [`Logger::testing`] loggers do not.
+    pub fn set_service<S: Into<String>>(&self, service: S) {
+        self.add_base_context_key(ContextKey::Service.as_str(), Value::String(service.into()));
+    }
+
     pub fn context(&self) -> Value {
         context::global_context()
     }
@@ -251,8 +999,10 @@ impl Logger {
     }
 
     pub fn reset_context(&self) {
-        reset_global_context();
-        self.reset_correlation_id();
+        reset_global_context_with(self.seed_ids);
+        if self.seed_ids {
+            self.reset_correlation_id();
+        }
     }
 
     pub fn add_base_context_key<V: Into<Value>>(&self, key: &str, value: V) {
@@ -265,10 +1015,36 @@ impl Logger {
         add_nested_context(&context);
     }
 
+    /// Like [`add_context`](Self::add_context), with control over how arrays already in the
+    /// context are merged, e.g. [`MergeStrategy::UnionArrays`] to accumulate `tags`.
+    pub fn add_context_with(&self, context: Value, strategy: MergeStrategy) {
+        add_nested_context_with(&context, strategy);
+    }
+
     pub fn add_base_context(&self, context: Value) {
         add_base_context(&context);
     }
 
+    pub fn add_base_context_with(&self, context: Value, strategy: MergeStrategy) {
+        add_base_context_with(&context, strategy);
+    }
+
+    /// Records a step leading up to a possible failure without logging it. The most recent
+    /// breadcrumbs are attached as `breadcrumbs` to the next `error` or `fatal` line and then
+    /// cleared. The trail belongs to the current task inside `context::scope` (with the `tokio`
+    /// feature), otherwise to
+    /// the current thread, and is reset when the outermost [`push_scope`](crate::push_scope)
+    /// begins or ends.
+    pub fn breadcrumb(&self, message: &str, data: Value) {
+        let mut crumb = Map::new();
+        crumb.insert(ContextKey::Time.as_str().into(), self.time_format.timestamp(Utc::now(), self.timezone));
+        crumb.insert("message".into(), Value::String(message.to_string()));
+        if !data.is_null() {
+            crumb.insert("data".into(), data);
+        }
+        push_breadcrumb(Value::Object(crumb), self.max_breadcrumbs);
+    }
+
     pub fn correlation_id(&self) -> Option<String> {
         base_context_key(ContextKey::CorrelationId.as_str()).and_then(|value| value.as_str().map(|s| s.to_string()))
     }
@@ -282,6 +1058,29 @@ impl Logger {
         set_correlation_id(id);
     }
 
+    /// Sets the correlation ID (and `requestId`/`traceId`) to `id` for the current task or
+    /// thread until the returned guard is dropped, then restores the previous IDs. Avoids one
+    /// request's ID bleeding into another, whether it runs concurrently or a reset is forgotten.
+    pub fn scoped_correlation(&self, id: &str) -> CorrelationGuard {
+        CorrelationGuard::set(id)
+    }
+
+    /// Sets `traceId` and `spanId` from a W3C `traceparent` header
+    /// (`00-<trace_id>-<span_id>-<flags>`). A header whose version field is malformed is
+    /// kept whole as an opaque `traceId`; any other invalid header is ignored.
+    pub fn set_trace_context(&self, traceparent: &str) {
+        match parse_traceparent(traceparent) {
+            Some(TraceParent::Parsed { trace_id, span_id }) => {
+                let mut map = Map::new();
+                map.insert(ContextKey::TraceId.as_str().into(), Value::String(trace_id.into()));
+                map.insert(ContextKey::SpanId.as_str().into(), Value::String(span_id.into()));
+                add_base_context(&Value::Object(map));
+            }
+            Some(TraceParent::Opaque(trace_id)) => self.add_base_context_key(ContextKey::TraceId.as_str(), trace_id),
+            None => {}
+        }
+    }
+
     pub fn add_user_context(&self, user: User) {
         let value = context_value(user);
         let mut wrapper = Map::new();
@@ -299,6 +1098,9 @@ impl Logger {
             if let Some(correlation) = headers.get("X-Correlation-Id").or_else(|| headers.get("x-correlation-id")) {
                 self.set_correlation_id(correlation.as_str());
             }
+            if let Some((_, traceparent)) = headers.iter().find(|(name, _)| name.eq_ignore_ascii_case("traceparent")) {
+                self.set_trace_context(traceparent);
+            }
         }
 
         self.add_http_context(Some(http_request), None);
@@ -332,30 +1134,62 @@ impl Logger {
         base_context_key(key)
     }
 
-    pub fn http_request_origin_domain(&self) -> Option<String> {
+    fn http_request_context(&self) -> Option<Map<String, Value>> {
         let http_value = self.base_context_key(ContextKey::Http.as_str())?;
-        let http_obj = http_value.as_object()?;
-        let request = http_obj.get("request")?.as_object()?;
+        http_value.as_object()?.get("request")?.as_object().cloned()
+    }
+
+    /// Host of the request's `Origin` header, falling back to `Referer` (or the `Referrer`
+    /// misspelling). Header names match case-insensitively; an unparseable URL gives `None`.
+    pub fn http_request_origin_domain(&self) -> Option<String> {
+        let request = self.http_request_context()?;
         let headers = request.get("headers")?.as_object()?;
-        let origin = headers.get("origin").or_else(|| headers.get("referrer")).and_then(|value| value.as_str())?;
+        let origin = ["origin", "referer", "referrer"].into_iter().find_map(|name| header_value(headers, name))?;
         Url::parse(origin).ok().and_then(|url| url.host_str().map(|host| host.to_string()))
     }
 
+    /// Client address of the current request: the first hop of `X-Forwarded-For`, falling
+    /// back to the request's `sourceIp`.
+    pub fn http_request_client_ip(&self) -> Option<String> {
+        let request = self.http_request_context()?;
+        let forwarded = request
+            .get("headers")
+            .and_then(Value::as_object)
+            .and_then(|headers| header_value(headers, "x-forwarded-for"))
+            .and_then(|value| value.split(',').next())
+            .map(str::trim)
+            .filter(|ip| !ip.is_empty());
+        match forwarded {
+            Some(ip) => Some(ip.to_string()),
+            None => request.get("sourceIp")?.as_str().map(str::to_string),
+        }
+    }
+
     pub fn build_log_object(&self, level: Level, args: &LogArgs) -> Value {
+        self.build_log_object_with_fields(level, args, Map::new())
+    }
+
+    /// [`build_log_object`](Self::build_log_object) with `fields` set at the top level before
+    /// the payload is filtered, redacted, truncated and ordered.
+    fn build_log_object_with_fields(&self, level: Level, args: &LogArgs, fields: Map<String, Value>) -> Value {
         let mut payload = context::global_context();
         if !payload.is_object() {
             payload = Value::Object(Map::new());
         }
         let map = payload.as_object_mut().expect("log payload should be object");
+        context::merge_maps(map, &self.instance_context);
+        if let Some(service) = &self.default_service {
+            map.entry(ContextKey::Service.as_str()).or_insert_with(|| Value::String(service.clone()));
+        }
 
         if let Some(msg) = args.message() {
             map.insert(ContextKey::Message.as_str().into(), Value::String(msg));
         }
 
-        if !args.contexts.is_empty() {
+        if args.contexts().next().is_some() {
             let entry = map.entry(ContextKey::Context.as_str().to_string()).or_insert_with(|| Value::Object(Map::new()));
             if let Value::Object(context_map) = entry {
-                for ctx in &args.contexts {
+                for ctx in args.contexts() {
                     if let Value::Object(obj) = ctx {
                         context::merge_maps(context_map, obj);
                     }
@@ -363,10 +1197,10 @@ impl Logger {
             }
         }
 
-        if !args.errors.is_empty() {
-            let error_message = args.errors.iter().map(|err| err.message.clone()).collect::<Vec<_>>().join("; ");
+        if args.errors().next().is_some() {
+            let error_message = args.errors().map(|err| err.message.clone()).collect::<Vec<_>>().join("; ");
             map.insert(ContextKey::Error.as_str().into(), Value::String(error_message));
-            let details = args.errors.iter().map(|err| err.to_value()).collect::<Vec<_>>();
+            let details = args.errors().map(|err| err.to_value()).collect::<Vec<_>>();
             map.insert(ContextKey::ErrorDetails.as_str().into(), Value::Array(details));
         }
 
@@ -381,11 +1215,16 @@ impl Logger {
             Value::Number(serde_json::Number::from(u64::from(level.code()))),
         );
         map.insert(ContextKey::LogLevel.as_str().into(), Value::String(level.as_str().into()));
-        map.insert(
-            ContextKey::Time.as_str().into(),
-            Value::String(Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true)),
-        );
+        map.insert(ContextKey::Time.as_str().into(), self.time_format.timestamp(Utc::now(), self.timezone));
         map.insert(ContextKey::Name.as_str().into(), Value::String(self.name.clone()));
+        map.extend(fields);
+
+        if level.code() >= Level::Error.code() {
+            let breadcrumbs = take_breadcrumbs();
+            if !breadcrumbs.is_empty() {
+                map.insert(ContextKey::Breadcrumbs.as_str().into(), Value::Array(breadcrumbs));
+            }
+        }
 
         remove_nulls(&mut payload);
 
@@ -393,92 +1232,369 @@ impl Logger {
             payload = apply_context_config(&payload, config);
         }
 
+        redact_paths(&mut payload, &self.redact_paths);
         redact_sensitive_values(&mut payload, &self.redact_keys);
+        truncate_payload(&mut payload, self.max_context_depth, self.max_field_count);
+        restore_explicit_nulls(&mut payload);
 
-        payload
+        match payload {
+            Value::Object(map) => Value::Object(order_keys(map)),
+            other => other,
+        }
     }
 
-    fn emit(&self, payload: Value) -> io::Result<()> {
-        let output = if self.pretty_print {
-            pretty::pretty_json(&payload)
-        } else {
+    /// Writes a built payload to every output. Logging from inside an output (for example
+    /// a sink that reports its own failures through the same logger) does not recurse:
+    /// the nested line is written as plain JSON to stderr (the browser console under `wasm`)
+    /// and dropped from the outputs.
+    fn emit(&self, level: Level, payload: Value) -> io::Result<()> {
+        let Some(_guard) = EmitGuard::enter() else {
             let mut line = pretty::plain_json(&payload);
             line.push('\n');
-            line
+            return match &self.nested_sink {
+                Some(sink) => sink.write(level, &line),
+                None => write_nested_output(level, &line),
+            };
+        };
+
+        let output = self.render(&payload, self.format, self.color);
+        self.stats.record(level, output.len());
+
+        // Every output is attempted; each failure goes to `on_error` and the first is returned.
+        let mut first_error = None;
+        let mut check = |result: io::Result<()>| {
+            if let Err(error) = result {
+                if let Some(on_error) = &self.on_error {
+                    on_error.call(&error);
+                }
+                first_error.get_or_insert(error);
+            }
         };
 
-        let mut stdout = io::stdout();
-        stdout.write_all(output.as_bytes())?;
-        stdout.flush()?;
+        match &self.sink {
+            Some(sink) => check(sink.write(level, &output)),
+            None => check(write_default_output(level, &output)),
+        }
+
+        #[cfg(feature = "native")]
+        self.write_files(level, &payload, &output, &mut check);
 
+        first_error.map_or(Ok(()), Err)
+    }
+
+    #[cfg(feature = "native")]
+    fn write_files(&self, level: Level, payload: &Value, output: &str, check: &mut impl FnMut(io::Result<()>)) {
+        let file_rendered;
+        let file_output = if self.file_color == self.color || self.format != LogFormat::Pretty {
+            output
+        } else {
+            file_rendered = self.render(payload, self.format, self.file_color);
+            &file_rendered
+        };
         if let Some(writer) = &self.file_writer {
-            writer.write(&output)?;
+            if self.rotation.sync_on_levels.contains(&level) {
+                check(writer.write_synced(file_output));
+            } else {
+                check(writer.write(file_output));
+            }
         }
 
-        Ok(())
+        for (format, color, writer) in &self.extra_file_writers {
+            let rendered;
+            let line = if (*format, *color) == (self.format, self.file_color) {
+                file_output
+            } else {
+                rendered = self.render(payload, *format, *color);
+                &rendered
+            };
+            if writer.options().sync_on_levels.contains(&level) {
+                check(writer.write_synced(line));
+            } else {
+                check(writer.write(line));
+            }
+        }
     }
 
-    fn do_log(&self, level: Level, args: LogArgs) -> io::Result<()> {
-        let payload = self.build_log_object(level, &args);
-        self.emit(payload)
+    fn render(&self, payload: &Value, format: LogFormat, color: bool) -> String {
+        match format {
+            LogFormat::Pretty => pretty::pretty_json_with_config(payload, &self.pretty_config, color),
+            LogFormat::Json => {
+                let mut line = pretty::plain_json(payload);
+                line.push('\n');
+                line
+            }
+            LogFormat::Logfmt => {
+                let mut line = pretty::logfmt(payload);
+                line.push('\n');
+                line
+            }
+        }
     }
 
-    fn is_enabled(&self, level: Level) -> bool {
-        level.code() >= self.level.code()
+    /// Number of lines skipped by sampling or namespace filters since the logger was created. Child loggers share
+    /// their parent's count.
+    pub fn dropped_count(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
     }
 
-    pub fn trace<A: Into<LogArgs>>(&self, args: A) -> io::Result<()> {
-        if self.is_enabled(Level::Trace) {
-            self.do_log(Level::Trace, args.into())
-        } else {
-            Ok(())
-        }
+    /// Lines emitted per level and bytes written, e.g. for exposing logger throughput on a
+    /// metrics endpoint. Lines filtered by level or sampled out are not counted. Child
+    /// loggers share their parent's counters.
+    pub fn stats(&self) -> LogStats {
+        self.stats.snapshot()
     }
 
-    pub fn debug<A: Into<LogArgs>>(&self, args: A) -> io::Result<()> {
-        if self.is_enabled(Level::Debug) {
-            self.do_log(Level::Debug, args.into())
-        } else {
-            Ok(())
-        }
+    /// Zeroes the counters returned by [`Logger::stats`].
+    pub fn reset_stats(&self) {
+        self.stats.reset();
     }
 
-    pub fn info<A: Into<LogArgs>>(&self, args: A) -> io::Result<()> {
-        if self.is_enabled(Level::Info) {
-            self.do_log(Level::Info, args.into())
-        } else {
-            Ok(())
+    fn sampled_out(&self, level: Level) -> bool {
+        let Some(rate) = self.sample_rate else {
+            return false;
+        };
+        if level.code() >= Level::Warn.code() {
+            return false;
         }
+
+        let draw = match self.correlation_id().filter(|_| self.sample_by_correlation) {
+            Some(correlation_id) => (fnv1a_64(correlation_id.as_bytes()) >> 11) as f64 / (1u64 << 53) as f64,
+            None => rand::random::<f64>(),
+        };
+        draw >= rate
     }
 
-    pub fn warn<A: Into<LogArgs>>(&self, args: A) -> io::Result<()> {
-        if self.is_enabled(Level::Warn) {
-            self.do_log(Level::Warn, args.into())
-        } else {
-            Ok(())
+    /// Whether the namespace filters drop a line that would carry `namespace`.
+    fn namespace_filtered(&self, namespace: Option<&Value>) -> bool {
+        let Some(filter) = &self.namespace_filter else {
+            return false;
+        };
+        let namespace = match namespace {
+            Some(namespace) => namespace.as_str().map(str::to_string),
+            None => self
+                .instance_context
+                .get(ContextKey::Namespace.as_str())
+                .cloned()
+                .or_else(|| base_context_key(ContextKey::Namespace.as_str()))
+                .and_then(|namespace| namespace.as_str().map(str::to_string)),
+        };
+        namespace.is_some_and(|namespace| !filter.allows(&namespace))
+    }
+
+    fn do_log(&self, level: Level, args: LogArgs) -> io::Result<()> {
+        if self.namespace_filtered(None) || self.sampled_out(level) {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return Ok(());
         }
+        let payload = self.build_log_object(level, &args);
+        self.dispatch(level, payload)
     }
 
-    pub fn error<A: Into<LogArgs>>(&self, args: A) -> io::Result<()> {
-        if self.is_enabled(Level::Error) {
-            self.do_log(Level::Error, args.into())
-        } else {
-            Ok(())
+    /// Emits `payload`, or hands it to the deduper when [`LoggerOptions::dedupe`] is set.
+    fn dispatch(&self, level: Level, payload: Value) -> io::Result<()> {
+        let Some(dedupe) = &self.dedupe else {
+            return self.emit(level, payload);
+        };
+        let mut result = Ok(());
+        for (level, payload) in dedupe.admit(level, payload) {
+            let written = self.emit(level, payload);
+            if result.is_ok() {
+                result = written;
+            }
         }
+        result
     }
 
-    pub fn fatal<A: Into<LogArgs>>(&self, args: A) -> io::Result<()> {
-        if self.is_enabled(Level::Fatal) {
-            self.do_log(Level::Fatal, args.into())
+    /// Logs `args` at `level` with `fields` set at the top level of the payload, overriding
+    /// any context value of the same key for this line only.
+    pub(crate) fn log_with_fields(&self, level: Level, args: LogArgs, fields: Map<String, Value>) -> io::Result<()> {
+        if !self.is_enabled(level) {
+            return Ok(());
+        }
+        if self.namespace_filtered(fields.get(ContextKey::Namespace.as_str())) || self.sampled_out(level) {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return Ok(());
+        }
+        let payload = self.build_log_object_with_fields(level, &args, fields);
+        self.dispatch(level, payload)
+    }
+
+    /// Starts a [`Span`] that logs at `info` with `namespace` set to `name` and `duration`
+    /// (milliseconds) once it is dropped.
+    ///
+    /// ```
+    /// use smooai_logger::Logger;
+    ///
+    /// let logger = Logger::default();
+    /// {
+    ///     let mut span = logger.span("load-user");
+    ///     span.record("userId", "u-123");
+    /// } // logs "load-user" with its duration here
+    /// ```
+    pub fn span(&self, name: &str) -> Span<'_> {
+        Span::new(self, name)
+    }
+
+    /// Runs `f` inside a [`span`](Self::span) named `label` and returns its result, so the
+    /// elapsed time is logged at `info` with `namespace` = `label`. If `f` panics, the line is
+    /// logged at `error` before the panic continues to unwind.
+    ///
+    /// ```
+    /// use smooai_logger::Logger;
+    ///
+    /// let logger = Logger::default();
+    /// let rows = logger.with_duration("db.query", || vec![1, 2, 3]);
+    /// assert_eq!(rows.len(), 3);
+    /// ```
+    pub fn with_duration<T, F: FnOnce() -> T>(&self, label: &str, f: F) -> T {
+        let _span = self.span(label);
+        f()
+    }
+
+    /// Async counterpart of [`with_duration`](Self::with_duration): awaits `future` and logs the
+    /// time from the call until it resolves. A panic while polling is logged at `error`.
+    pub async fn with_duration_async<F: Future>(&self, label: &str, future: F) -> F::Output {
+        let _span = self.span(label);
+        future.await
+    }
+
+    /// Whether a line at `level` would be written, i.e. the logger is enabled and `level` is
+    /// at or above its level.
+    pub fn is_enabled(&self, level: Level) -> bool {
+        !self.is_disabled() && level.code() >= self.level.load(Ordering::Relaxed)
+    }
+
+    /// Logs `args` at a level chosen at runtime; the level methods below all delegate here.
+    /// Does nothing when `level` is below the logger's level.
+    pub fn log<A: Into<LogArgs>>(&self, level: Level, args: A) -> io::Result<()> {
+        if self.is_enabled(level) {
+            self.do_log(level, args.into())
         } else {
             Ok(())
         }
     }
 
-    pub fn silent<A: Into<LogArgs>>(&self, _args: A) -> io::Result<()> {
-        Ok(())
+    pub fn trace<A: Into<LogArgs>>(&self, args: A) -> io::Result<()> {
+        self.log(Level::Trace, args)
     }
-}
+
+    pub fn debug<A: Into<LogArgs>>(&self, args: A) -> io::Result<()> {
+        self.log(Level::Debug, args)
+    }
+
+    pub fn info<A: Into<LogArgs>>(&self, args: A) -> io::Result<()> {
+        self.log(Level::Info, args)
+    }
+
+    pub fn warn<A: Into<LogArgs>>(&self, args: A) -> io::Result<()> {
+        self.log(Level::Warn, args)
+    }
+
+    pub fn error<A: Into<LogArgs>>(&self, args: A) -> io::Result<()> {
+        self.log(Level::Error, args)
+    }
+
+    pub fn fatal<A: Into<LogArgs>>(&self, args: A) -> io::Result<()> {
+        self.log(Level::Fatal, args)
+    }
+
+    pub fn silent<A: Into<LogArgs>>(&self, _args: A) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Runs `command` to completion, logging each line of its stdout at `info` and each
+    /// line of its stderr at `error`. Lines carry `context.source = "child"` and use the
+    /// program's file name as `namespace`; JSON object lines are nested under `context`
+    /// (their `msg`, if any, becomes the message) and anything else is logged as `msg`.
+    /// Lines go through the same level, namespace, sampling and dedupe checks as any other
+    /// line, and invalid UTF-8 is replaced rather than ending the capture. If a line cannot
+    /// be written or a stream cannot be read, the first error is returned once the command
+    /// has exited.
+    #[cfg(feature = "native")]
+    pub fn capture_command(&self, mut command: Command) -> io::Result<ExitStatus> {
+        let program = command.get_program().to_owned();
+        let namespace = Path::new(&program).file_name().unwrap_or(program.as_os_str()).to_string_lossy().into_owned();
+
+        let mut child = command.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+
+        let (stderr_result, stdout_result) = thread::scope(|scope| {
+            let stderr = stderr.map(|stderr| {
+                let namespace = namespace.as_str();
+                scope.spawn(move || self.log_child_stream(stderr, Level::Error, namespace, "stderr"))
+            });
+            let stdout_result = stdout.map_or(Ok(()), |stdout| self.log_child_stream(stdout, Level::Info, &namespace, "stdout"));
+            let stderr_result = stderr.map_or(Ok(()), |handle| handle.join().unwrap_or(Ok(())));
+            (stderr_result, stdout_result)
+        });
+
+        let status = child.wait()?;
+        stdout_result.and(stderr_result)?;
+        Ok(status)
+    }
+
+    /// Logs each line of `stream` until it closes, returning the first write or read error.
+    /// A read error stops the capture; write errors do not, so the child never blocks on a
+    /// full pipe.
+    #[cfg(feature = "native")]
+    fn log_child_stream<R: io::Read>(&self, stream: R, level: Level, namespace: &str, stream_name: &str) -> io::Result<()> {
+        let mut reader = BufReader::new(stream);
+        let mut buffer = Vec::new();
+        let mut result = Ok(());
+        loop {
+            buffer.clear();
+            match reader.read_until(b'\n', &mut buffer) {
+                Ok(0) => break,
+                Ok(_) => {}
+                Err(error) => {
+                    result = result.and(Err(error));
+                    break;
+                }
+            }
+            let line = String::from_utf8_lossy(&buffer);
+            let line = line.trim_end_matches(['\n', '\r']);
+            if line.trim().is_empty() {
+                continue;
+            }
+            let (args, fields) = self.child_line_args(namespace, stream_name, line);
+            if let Err(error) = self.log_with_fields(level, args, fields) {
+                result = result.and(Err(error));
+            }
+        }
+        result
+    }
+
+    /// Arguments and top-level fields for one line of child output.
+    #[cfg(feature = "native")]
+    fn child_line_args(&self, namespace: &str, stream_name: &str, line: &str) -> (LogArgs, Map<String, Value>) {
+        let mut args = LogArgs::new();
+        match serde_json::from_str::<Value>(line) {
+            Ok(Value::Object(object)) => {
+                if let Some(Value::String(msg)) = object.get(ContextKey::Message.as_str()) {
+                    args.push(msg.clone());
+                }
+                args.push(Value::Object(object));
+            }
+            _ => args.push(line.to_string()),
+        }
+        args.push(serde_json::json!({ "source": "child", "stream": stream_name }));
+
+        let mut fields = Map::new();
+        fields.insert(ContextKey::Namespace.as_str().into(), Value::String(namespace.to_string()));
+        (args, fields)
+    }
+
+    /// Logs `args` at `level` when `condition` is false and returns the condition, so
+    /// callers can write `if !logger.ensure(valid, Level::Error, "invalid") { return; }`.
+    /// See [`log_ensure!`](crate::log_ensure) for a variant that records the condition text.
+    pub fn ensure<A: Into<LogArgs>>(&self, condition: bool, level: Level, args: A) -> bool {
+        if !condition {
+            let _ = self.log(level, args);
+        }
+        condition
+    }
+}
 
 #[derive(Debug, Clone)]
 pub enum LogValue {
@@ -487,11 +1603,10 @@ pub enum LogValue {
     Error(LoggedError),
 }
 
+/// Messages, contexts and errors for one log call, kept in the order they were pushed.
 #[derive(Debug, Clone, Default)]
 pub struct LogArgs {
-    messages: Vec<String>,
-    pub(crate) contexts: Vec<Value>,
-    pub(crate) errors: Vec<LoggedError>,
+    values: Vec<LogValue>,
 }
 
 impl LogArgs {
@@ -500,18 +1615,14 @@ impl LogArgs {
     }
 
     pub fn push<T: Into<LogValue>>(&mut self, value: T) {
-        match value.into() {
-            LogValue::Message(message) => self.messages.push(message),
-            LogValue::Context(context) => self.contexts.push(context),
-            LogValue::Error(error) => self.errors.push(error),
-        }
+        self.values.push(value.into());
     }
 
     pub fn error<E>(&mut self, error: E)
     where
         E: Error + Send + Sync + 'static,
     {
-        self.errors.push(log_error(error));
+        self.values.push(LogValue::Error(log_error(error)));
     }
 
     pub fn extend<I, T>(&mut self, iter: I)
@@ -524,13 +1635,45 @@ impl LogArgs {
         }
     }
 
+    /// All pushed messages joined with `; `, or `None` when there are none.
     pub fn message(&self) -> Option<String> {
-        if self.messages.is_empty() {
+        let messages: Vec<&str> = self
+            .values
+            .iter()
+            .filter_map(|value| match value {
+                LogValue::Message(message) => Some(message.as_str()),
+                _ => None,
+            })
+            .collect();
+        if messages.is_empty() {
             None
         } else {
-            Some(self.messages.join("; "))
+            Some(messages.join("; "))
         }
     }
+
+    /// The pushed values in insertion order.
+    pub fn iter(&self) -> std::slice::Iter<'_, LogValue> {
+        self.values.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub(crate) fn contexts(&self) -> impl Iterator<Item = &Value> {
+        self.values.iter().filter_map(|value| match value {
+            LogValue::Context(context) => Some(context),
+            _ => None,
+        })
+    }
+
+    pub(crate) fn errors(&self) -> impl Iterator<Item = &LoggedError> {
+        self.values.iter().filter_map(|value| match value {
+            LogValue::Error(error) => Some(error),
+            _ => None,
+        })
+    }
 }
 
 impl From<&str> for LogArgs {
@@ -589,33 +1732,25 @@ impl IntoIterator for LogArgs {
 
     fn into_iter(self) -> Self::IntoIter {
         LogArgsIntoIter {
-            messages: self.messages.into_iter(),
-            contexts: self.contexts.into_iter(),
-            errors: self.errors.into_iter(),
+            values: self.values.into_iter(),
         }
     }
 }
 
+/// Yields a [`LogArgs`]' values in insertion order.
 pub struct LogArgsIntoIter {
-    messages: std::vec::IntoIter<String>,
-    contexts: std::vec::IntoIter<Value>,
-    errors: std::vec::IntoIter<LoggedError>,
+    values: std::vec::IntoIter<LogValue>,
 }
 
 impl Iterator for LogArgsIntoIter {
     type Item = LogValue;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some(message) = self.messages.next() {
-            return Some(LogValue::Message(message));
-        }
-        if let Some(context) = self.contexts.next() {
-            return Some(LogValue::Context(context));
-        }
-        if let Some(error) = self.errors.next() {
-            return Some(LogValue::Error(error));
-        }
-        None
+        self.values.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.values.size_hint()
     }
 }
 
@@ -643,12 +1778,29 @@ impl From<LoggedError> for LogValue {
     }
 }
 
+/// Errors with a [`HasErrorCode`] can be pushed as they are, keeping their code.
+impl<E> From<E> for LogValue
+where
+    E: Error + HasErrorCode + Send + Sync + 'static,
+{
+    fn from(error: E) -> Self {
+        LogValue::Error(LoggedError::from(error))
+    }
+}
+
 impl<'a> From<&'a LoggedError> for LogValue {
     fn from(value: &'a LoggedError) -> Self {
         LogValue::Error(value.clone())
     }
 }
 
+#[cfg(feature = "anyhow")]
+impl<'a> From<&'a anyhow::Error> for LogValue {
+    fn from(value: &'a anyhow::Error) -> Self {
+        LogValue::Error(crate::error::log_anyhow(value))
+    }
+}
+
 #[macro_export]
 macro_rules! log_args {
     () => {
@@ -661,6 +1813,108 @@ macro_rules! log_args {
     }};
 }
 
+/// Evaluates a condition and, when it is false, logs the given arguments at `level`
+/// with the condition's source text under `context.condition`. Returns the condition.
+///
+/// ```no_run
+/// use smooai_logger::{log_ensure, Level, Logger};
+///
+/// let logger = Logger::default();
+/// let items: Vec<u32> = Vec::new();
+/// if !log_ensure!(logger, !items.is_empty(), Level::Warn, "no items to process") {
+///     return;
+/// }
+/// ```
+#[macro_export]
+macro_rules! log_ensure {
+    ($logger:expr, $cond:expr, $level:expr, $($arg:expr),+ $(,)?) => {{
+        let condition: bool = $cond;
+        if !condition {
+            let mut args = $crate::log_args!($($arg),+);
+            args.push($crate::json!({ "condition": stringify!($cond) }));
+            $logger.ensure(condition, $level, args)
+        } else {
+            condition
+        }
+    }};
+}
+
+/// Logs at `level` with the call site's file, line and module under `context.source`.
+/// The arguments are only built when `level` is enabled. The level macros below all
+/// delegate here.
+///
+/// ```no_run
+/// use smooai_logger::{log_at, Level, Logger};
+///
+/// let logger = Logger::default();
+/// let _ = log_at!(logger, Level::Info, "user created", smooai_logger::json!({"userId": "123"}));
+/// ```
+#[macro_export]
+macro_rules! log_at {
+    ($logger:expr, $level:expr, $($arg:expr),+ $(,)?) => {{
+        let logger = &$logger;
+        let level: $crate::logger::Level = $level;
+        if logger.is_enabled(level) {
+            let mut args = $crate::log_args!($($arg),+);
+            args.push($crate::json!({
+                "source": { "file": file!(), "line": line!(), "module": module_path!() }
+            }));
+            logger.log(level, args)
+        } else {
+            ::std::io::Result::Ok(())
+        }
+    }};
+}
+
+/// [`log_at!`] at `trace`.
+#[macro_export]
+macro_rules! log_trace {
+    ($logger:expr, $($arg:expr),+ $(,)?) => {
+        $crate::log_at!($logger, $crate::logger::Level::Trace, $($arg),+)
+    };
+}
+
+/// [`log_at!`] at `debug`.
+#[macro_export]
+macro_rules! log_debug {
+    ($logger:expr, $($arg:expr),+ $(,)?) => {
+        $crate::log_at!($logger, $crate::logger::Level::Debug, $($arg),+)
+    };
+}
+
+/// [`log_at!`] at `info`.
+#[macro_export]
+macro_rules! log_info {
+    ($logger:expr, $($arg:expr),+ $(,)?) => {
+        $crate::log_at!($logger, $crate::logger::Level::Info, $($arg),+)
+    };
+}
+
+/// [`log_at!`] at `warn`.
+#[macro_export]
+macro_rules! log_warn {
+    ($logger:expr, $($arg:expr),+ $(,)?) => {
+        $crate::log_at!($logger, $crate::logger::Level::Warn, $($arg),+)
+    };
+}
+
+/// [`log_at!`] at `error`. Not to be confused with the [`log_error`](crate::log_error()) function,
+/// which converts an error for logging.
+#[macro_export]
+macro_rules! log_error {
+    ($logger:expr, $($arg:expr),+ $(,)?) => {
+        $crate::log_at!($logger, $crate::logger::Level::Error, $($arg),+)
+    };
+}
+
+/// [`log_at!`] at `fatal`.
+#[macro_export]
+macro_rules! log_fatal {
+    ($logger:expr, $($arg:expr),+ $(,)?) => {
+        $crate::log_at!($logger, $crate::logger::Level::Fatal, $($arg),+)
+    };
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -679,6 +1933,22 @@ mod tests {
         assert_eq!(context.get("foo").unwrap(), "bar");
     }
 
+    #[test]
+    fn explicit_nulls_survive_null_removal() {
+        let _guard = crate::TEST_GLOBAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let logger = Logger::default();
+        logger.reset_context();
+        let args = log_args!(
+            "lookup",
+            json!({"result": crate::explicit_null(), "cached": null, "items": [crate::explicit_null()]})
+        );
+        let payload = logger.build_log_object(Level::Info, &args);
+        let context = payload.get("context").unwrap().as_object().unwrap();
+        assert_eq!(context.get("result"), Some(&Value::Null));
+        assert!(!context.contains_key("cached"));
+        assert_eq!(context.get("items"), Some(&json!([null])));
+    }
+
     #[derive(Debug)]
     struct SampleError;
 
@@ -703,6 +1973,22 @@ mod tests {
         assert_eq!(details[0].get("message").unwrap(), "sample error");
     }
 
+    #[test]
+    fn log_args_preserve_insertion_order() {
+        let args = log_args!("first", log_error(SampleError), json!({"a": 1}), "second");
+        let kinds: Vec<&str> = args
+            .iter()
+            .map(|value| match value {
+                LogValue::Message(_) => "message",
+                LogValue::Context(_) => "context",
+                LogValue::Error(_) => "error",
+            })
+            .collect();
+        assert_eq!(kinds, ["message", "error", "context", "message"]);
+        assert_eq!(args.message().as_deref(), Some("first; second"));
+        assert!(matches!(args.into_iter().nth(1), Some(LogValue::Error(_))));
+    }
+
     #[test]
     fn add_http_request_sets_namespace_and_correlation() {
         let _guard = crate::TEST_GLOBAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
@@ -735,6 +2021,31 @@ mod tests {
         assert_eq!(logger.http_request_origin_domain().as_deref(), Some("example.com"));
     }
 
+    #[test]
+    fn origin_and_client_ip_read_headers_in_any_case() {
+        let _guard = crate::TEST_GLOBAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let logger = Logger::default();
+        logger.reset_context();
+        let request = |headers: &[(&str, &str)], source_ip: Option<&str>| HttpRequest {
+            headers: Some(headers.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()),
+            source_ip: source_ip.map(str::to_string),
+            ..Default::default()
+        };
+
+        logger.add_http_request(request(
+            &[("Referer", "https://app.example.com/page"), ("X-Forwarded-For", "203.0.113.7, 10.0.0.1")],
+            Some("10.0.0.2"),
+        ));
+        assert_eq!(logger.http_request_origin_domain().as_deref(), Some("app.example.com"));
+        assert_eq!(logger.http_request_client_ip().as_deref(), Some("203.0.113.7"));
+
+        logger.reset_context();
+        logger.add_http_request(request(&[("Origin", "not a url")], Some("10.0.0.2")));
+        assert_eq!(logger.http_request_origin_domain(), None);
+        assert_eq!(logger.http_request_client_ip().as_deref(), Some("10.0.0.2"));
+        logger.reset_context();
+    }
+
     #[test]
     fn context_config_filters_fields() {
         let _guard = crate::TEST_GLOBAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
@@ -758,6 +2069,590 @@ mod tests {
         assert!(http.get("method").is_some());
     }
 
+    #[test]
+    fn sampling_drops_low_levels_and_counts_them() {
+        let _guard = crate::TEST_GLOBAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let sink = crate::sink::MemorySink::new();
+        let logger = crate::memory_logger(
+            &sink,
+            LoggerOptions {
+                level: Some(Level::Trace),
+                sample_rate: Some(0.0),
+                ..Default::default()
+            },
+        );
+        logger.debug("sampled").unwrap();
+        logger.info("sampled").unwrap();
+        logger.error("kept").unwrap();
+        assert_eq!(logger.dropped_count(), 2);
+        assert_eq!(sink.lines().len(), 1);
+    }
+
+    #[test]
+    fn stats_count_emitted_lines_per_level() {
+        let _guard = crate::TEST_GLOBAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let sink = crate::sink::MemorySink::new();
+        let logger = crate::memory_logger(
+            &sink,
+            LoggerOptions {
+                level: Some(Level::Info),
+                ..Default::default()
+            },
+        );
+        logger.reset_context();
+        logger.debug("filtered").unwrap();
+        logger.info("one").unwrap();
+        logger.child(json!({"job": 1})).warn("two").unwrap();
+        logger.error("three").unwrap();
+
+        let stats = logger.stats();
+        assert_eq!((stats.debug, stats.info, stats.warn, stats.error), (0, 1, 1, 1));
+        assert_eq!(stats.total(), 3);
+        assert_eq!(stats.bytes, sink.contents().len() as u64);
+
+        logger.reset_stats();
+        assert_eq!(logger.stats(), LogStats::default());
+    }
+
+    #[test]
+    fn dedupe_collapses_runs_and_reports_them() {
+        let _guard = crate::TEST_GLOBAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let sink = crate::sink::MemorySink::new();
+        let logger = crate::memory_logger(
+            &sink,
+            LoggerOptions {
+                dedupe: Some(DedupeConfig::default()),
+                ..Default::default()
+            },
+        );
+        logger.reset_context();
+        for attempt in 0..4 {
+            logger.error(log_args!("retry failed", json!({ "attempt": attempt }))).unwrap();
+        }
+        logger.info("gave up").unwrap();
+        logger.info("gave up").unwrap();
+        logger.flush().unwrap();
+        logger.info("gave up").unwrap();
+        logger.warn("shutting down").unwrap();
+        logger.warn("shutting down").unwrap();
+        drop(logger);
+
+        let lines: Vec<Value> = sink.lines().iter().map(|line| serde_json::from_str(line).unwrap()).collect();
+        let summary: Vec<_> = lines
+            .iter()
+            .map(|line| (line["msg"].as_str().unwrap(), line.get("repeated").and_then(Value::as_u64)))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("retry failed", None),
+                ("retry failed", Some(3)),
+                ("gave up", None),
+                ("gave up", Some(1)),
+                ("gave up", Some(1)),
+                ("shutting down", None),
+                ("shutting down", Some(1)),
+            ]
+        );
+        assert_eq!(lines[1]["context"]["attempt"], 3);
+    }
+
+    #[test]
+    fn namespace_filters_drop_lines_and_reject_bad_patterns() {
+        let _guard = crate::TEST_GLOBAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let sink = crate::sink::MemorySink::new();
+        let logger = crate::memory_logger(
+            &sink,
+            LoggerOptions {
+                include_namespaces: vec!["^api".into(), "^db".into()],
+                exclude_namespaces: vec!["^db::pool$".into()],
+                ..Default::default()
+            },
+        );
+        logger.reset_context();
+        logger.info("no namespace").unwrap();
+        for namespace in ["api::users", "db::pool", "db::query", "cache"] {
+            logger.child(json!({ "namespace": namespace })).info(namespace).unwrap();
+        }
+        let mut fields = Map::new();
+        fields.insert("namespace".into(), json!("cache"));
+        logger.log_with_fields(Level::Info, LogArgs::from("bridged"), fields).unwrap();
+
+        let messages: Vec<String> = sink
+            .lines()
+            .iter()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap()["msg"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(messages, ["no namespace", "api::users", "db::query"]);
+        assert_eq!(logger.dropped_count(), 3);
+
+        let invalid = Logger::try_new(LoggerOptions {
+            log_to_file: Some(false),
+            exclude_namespaces: vec!["(".into()],
+            ..Default::default()
+        });
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid namespace filter \"(\"")]
+    fn new_panics_on_a_bad_namespace_pattern() {
+        Logger::new(LoggerOptions {
+            log_to_file: Some(false),
+            include_namespaces: vec!["^api".into()],
+            exclude_namespaces: vec!["(".into()],
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn sampling_by_correlation_is_deterministic() {
+        let _guard = crate::TEST_GLOBAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let logger = crate::memory_logger(
+            &crate::sink::MemorySink::new(),
+            LoggerOptions {
+                sample_rate: Some(0.5),
+                sample_by_correlation: true,
+                ..Default::default()
+            },
+        );
+        logger.set_correlation_id("request-1");
+        let first = logger.sampled_out(Level::Info);
+        assert!((0..20).all(|_| logger.sampled_out(Level::Info) == first));
+        logger.reset_context();
+
+        // Published FNV-1a test vectors; the hash must not change between builds.
+        assert_eq!(fnv1a_64(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a_64(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a_64(b"foobar"), 0x8594_4171_f739_67e8);
+    }
+
+    #[test]
+    fn seed_ids_false_leaves_ids_unset_across_resets() {
+        let _guard = crate::TEST_GLOBAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let logger = Logger::new(LoggerOptions {
+            log_to_file: Some(false),
+            seed_ids: Some(false),
+            ..Default::default()
+        });
+        let payload = logger.build_log_object(Level::Info, &LogArgs::from("no ids"));
+        assert!(payload.get("correlationId").is_none());
+        assert!(payload.get("requestId").is_none());
+        assert!(payload.get("traceId").is_none());
+
+        logger.set_correlation_id("mine");
+        logger.reset_context();
+        assert!(logger.correlation_id().is_none());
+
+        let seeded = Logger::new(LoggerOptions {
+            log_to_file: Some(false),
+            ..Default::default()
+        });
+        seeded.reset_context();
+        assert!(seeded.correlation_id().is_some());
+    }
+
+    #[test]
+    fn scoped_correlation_restores_previous_ids() {
+        let _guard = crate::TEST_GLOBAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let logger = Logger::new(LoggerOptions {
+            log_to_file: Some(false),
+            ..Default::default()
+        });
+        logger.reset_context();
+        logger.set_correlation_id("outer");
+        {
+            let _correlation = logger.scoped_correlation("request-1");
+            let payload = logger.build_log_object(Level::Info, &LogArgs::from("inside"));
+            assert_eq!(payload["correlationId"], "request-1");
+            assert_eq!(payload["traceId"], "request-1");
+        }
+        let payload = logger.build_log_object(Level::Info, &LogArgs::from("after"));
+        assert_eq!(payload["correlationId"], "outer");
+        assert_eq!(payload["requestId"], "outer");
+
+        crate::context::clear_request_ids();
+        drop(logger.scoped_correlation("request-2"));
+        assert!(logger.correlation_id().is_none());
+        logger.reset_context();
+    }
+
+    #[test]
+    fn time_format_and_timezone_shape_the_time_field() {
+        let now = DateTime::parse_from_rfc3339("2024-05-01T12:00:00.123456789Z").unwrap().with_timezone(&Utc);
+        let plus_two = Timezone::Fixed(FixedOffset::east_opt(2 * 3600).unwrap());
+        assert_eq!(TimeFormat::default().timestamp(now, Timezone::Utc), "2024-05-01T12:00:00.123Z");
+        assert_eq!(TimeFormat::Rfc3339Nanos.timestamp(now, Timezone::Utc), "2024-05-01T12:00:00.123456789Z");
+        assert_eq!(TimeFormat::EpochMillis.timestamp(now, plus_two), 1_714_564_800_123_i64);
+        assert_eq!(TimeFormat::EpochSeconds.timestamp(now, Timezone::Utc), 1_714_564_800_i64);
+        assert_eq!(TimeFormat::default().timestamp(now, plus_two), "2024-05-01T14:00:00.123+02:00");
+        assert_eq!(TimeFormat::Custom("%d/%m/%Y %H:%M".into()).timestamp(now, plus_two), "01/05/2024 14:00");
+        assert_eq!(TimeFormat::Custom("%Q".into()).timestamp(now, Timezone::Utc), "2024-05-01T12:00:00.123Z");
+
+        let logger = Logger::new(LoggerOptions {
+            log_to_file: Some(false),
+            time_format: Some(TimeFormat::EpochMillis),
+            ..Default::default()
+        });
+        assert!(logger.build_log_object(Level::Info, &LogArgs::from("epoch")).get("time").unwrap().is_i64());
+    }
+
+    #[test]
+    fn level_serde_round_trips_through_names() {
+        assert_eq!(serde_json::from_str::<Level>("\"warning\"").unwrap(), Level::Warn);
+        assert_eq!(serde_json::from_str::<Level>("\"FATAL\"").unwrap(), Level::Fatal);
+        assert_eq!(serde_json::to_string(&Level::Warn).unwrap(), "\"warn\"");
+        let levels: HashMap<String, Level> = serde_json::from_str(r#"{"db": "debug", "http": "error"}"#).unwrap();
+        assert_eq!(levels["db"], Level::Debug);
+        assert!(serde_json::from_str::<Level>("\"loud\"").unwrap_err().to_string().contains("loud"));
+    }
+
+    #[test]
+    fn log_dispatches_on_runtime_level() {
+        let _guard = crate::TEST_GLOBAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let sink = crate::sink::MemorySink::new();
+        let logger = crate::memory_logger(
+            &sink,
+            LoggerOptions {
+                level: Some(Level::Warn),
+                ..Default::default()
+            },
+        );
+        logger.reset_context();
+        logger.log(Level::Info, "filtered").unwrap();
+        logger.log(Level::Error, "kept").unwrap();
+        let lines = sink.lines();
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].contains("\"msg\":\"kept\""));
+        assert!(lines[0].contains("\"LogLevel\":\"error\""));
+    }
+
+    #[test]
+    fn disable_silences_every_level_until_enabled() {
+        let _guard = crate::TEST_GLOBAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let sink = crate::sink::MemorySink::new();
+        let logger = Arc::new(crate::memory_logger(&sink, LoggerOptions::default()));
+        logger.reset_context();
+
+        let shared = Arc::clone(&logger);
+        shared.disable();
+        logger.fatal("muted").unwrap();
+        logger.child(json!({ "component": "db" })).error("muted child").unwrap();
+        assert!(sink.lines().is_empty());
+
+        shared.enable();
+        logger.fatal("audible").unwrap();
+        let lines = sink.lines();
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].contains("\"msg\":\"audible\""));
+    }
+
+    #[test]
+    fn set_level_through_shared_reference() {
+        let _guard = crate::TEST_GLOBAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let sink = crate::sink::MemorySink::new();
+        let logger = Arc::new(crate::memory_logger(
+            &sink,
+            LoggerOptions {
+                level: Some(Level::Info),
+                ..Default::default()
+            },
+        ));
+        logger.reset_context();
+
+        logger.debug("hidden").unwrap();
+        let handle = Arc::clone(&logger);
+        std::thread::spawn(move || handle.set_level(Level::Debug)).join().unwrap();
+        assert_eq!(logger.level(), Level::Debug);
+        logger.debug("shown").unwrap();
+
+        let lines = sink.lines();
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].contains("\"msg\":\"shown\""));
+    }
+
+    #[test]
+    fn max_context_depth_truncates_deep_context() {
+        let _guard = crate::TEST_GLOBAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let logger = Logger::new(LoggerOptions {
+            log_to_file: Some(false),
+            max_context_depth: Some(2),
+            ..Default::default()
+        });
+        logger.reset_context();
+        let payload = logger.build_log_object(Level::Info, &log_args!("deep", json!({ "a": { "b": { "c": 1 } } })));
+        assert_eq!(payload.pointer("/context/a/b").unwrap(), crate::TRUNCATED_VALUE);
+        assert_eq!(payload.get("_truncated").unwrap(), true);
+        assert_eq!(payload.get("msg").unwrap(), "deep");
+    }
+
+    #[test]
+    fn payload_keys_follow_a_stable_order() {
+        let _guard = crate::TEST_GLOBAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let logger = Logger::new(LoggerOptions {
+            log_to_file: Some(false),
+            ..Default::default()
+        });
+        logger.reset_context();
+        logger.add_context(json!({ "zeta": 1 }));
+        let payload = logger.build_log_object(Level::Warn, &log_args!("ordered", log_error(SampleError)));
+        let keys: Vec<&str> = payload.as_object().unwrap().keys().map(String::as_str).collect();
+        assert_eq!(&keys[..3], ["time", "level", "LogLevel"]);
+        assert_eq!(keys[3], "name");
+        let msg = keys.iter().position(|key| *key == "msg").unwrap();
+        assert_eq!(keys[msg - 1], "correlationId");
+        assert_eq!(&keys[keys.len() - 2..], ["error", "errorDetails"]);
+        let middle = &keys[msg + 1..keys.len() - 2];
+        assert!(middle.windows(2).all(|pair| pair[0] <= pair[1]), "{middle:?}");
+    }
+
+    #[test]
+    fn fields_are_ordered_and_redacted_like_context() {
+        let _guard = crate::TEST_GLOBAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let sink = crate::sink::MemorySink::new();
+        let logger = crate::memory_logger(
+            &sink,
+            LoggerOptions {
+                redact_keys: Some(vec!["password".into()]),
+                ..Default::default()
+            },
+        );
+        logger.reset_context();
+        let mut fields = Map::new();
+        fields.insert("namespace".into(), json!("bridge"));
+        fields.insert("password".into(), json!("hunter2"));
+        logger.log_with_fields(Level::Info, LogArgs::from("bridged"), fields).unwrap();
+
+        let line = &sink.lines()[0];
+        let payload: Value = serde_json::from_str(line).unwrap();
+        let keys: Vec<&str> = payload.as_object().unwrap().keys().map(String::as_str).collect();
+        assert_eq!(&keys[..5], ["time", "level", "LogLevel", "name", "namespace"]);
+        assert_eq!(payload["password"], "[REDACTED]");
+    }
+
+    #[test]
+    fn breadcrumbs_attach_to_errors_only_and_are_capped() {
+        let _guard = crate::TEST_GLOBAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let logger = Logger::new(LoggerOptions {
+            log_to_file: Some(false),
+            max_breadcrumbs: Some(2),
+            ..Default::default()
+        });
+        logger.reset_context();
+        let _scope = crate::push_scope(json!({}));
+
+        logger.breadcrumb("loaded user", json!({ "userId": "u-1" }));
+        logger.breadcrumb("fetched cart", Value::Null);
+        logger.breadcrumb("charged card", json!({ "amount": 5 }));
+        let info = logger.build_log_object(Level::Info, &log_args!("fine"));
+        assert!(info.get("breadcrumbs").is_none());
+
+        let error = logger.build_log_object(Level::Error, &log_args!("failed"));
+        let crumbs = error.get("breadcrumbs").unwrap().as_array().unwrap();
+        assert_eq!(crumbs.len(), 2);
+        assert_eq!(crumbs[0]["message"], "fetched cart");
+        assert!(crumbs[0].get("data").is_none());
+        assert_eq!(crumbs[1]["data"]["amount"], 5);
+
+        let again = logger.build_log_object(Level::Error, &log_args!("failed again"));
+        assert!(again.get("breadcrumbs").is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "native")]
+    fn on_error_reports_failed_outputs() {
+        struct FailingSink;
+        impl LogSink for FailingSink {
+            fn write(&self, _level: Level, _output: &str) -> io::Result<()> {
+                Err(io::Error::other("disk full"))
+            }
+        }
+
+        let _guard = crate::TEST_GLOBAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let dir = tempfile::tempdir().unwrap();
+        let blocker = dir.path().join("not-a-dir");
+        std::fs::write(&blocker, "").unwrap();
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let seen = errors.clone();
+        let logger = Logger::new(LoggerOptions {
+            log_to_file: Some(true),
+            rotation: Some(RotationOptions {
+                path: blocker,
+                ..Default::default()
+            }),
+            sink: Some(Arc::new(FailingSink)),
+            on_error: Some(ErrorCallback::new(move |error| seen.lock().push(error.to_string()))),
+            ..Default::default()
+        });
+        assert_eq!(errors.lock().len(), 1, "opening the log file should fail");
+
+        let result = logger.info("lost");
+        assert_eq!(result.unwrap_err().to_string(), "disk full");
+        assert_eq!(errors.lock().last().unwrap(), "disk full");
+    }
+
+    #[test]
+    fn span_logs_duration_on_drop_unless_cancelled() {
+        let _guard = crate::TEST_GLOBAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let sink = crate::sink::MemorySink::new();
+        let logger = crate::memory_logger(&sink, LoggerOptions::default());
+        logger.reset_context();
+        {
+            let mut span = logger.span("load-user");
+            span.record("userId", "u-1");
+        }
+        {
+            let mut span = logger.span("skipped");
+            span.cancel();
+        }
+
+        let lines = sink.lines();
+        assert_eq!(lines.len(), 1);
+        let value: Value = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!(value.get("namespace").unwrap(), "load-user");
+        assert_eq!(value.get("msg").unwrap(), "load-user");
+        assert_eq!(value.pointer("/context/userId").unwrap(), "u-1");
+        assert!(value.get("duration").unwrap().as_f64().unwrap() >= 0.0);
+    }
+
+    #[test]
+    #[cfg(feature = "native")]
+    fn extra_file_sinks_render_in_their_own_format() {
+        let _guard = crate::TEST_GLOBAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let dir = tempfile::tempdir().unwrap();
+        let file_sink = |format, extension: &str| FileSinkOptions {
+            format,
+            rotation: RotationOptions {
+                path: dir.path().into(),
+                extension: extension.into(),
+                buffered: false,
+                ..Default::default()
+            },
+            color: Some(true),
+        };
+        let logger = crate::memory_logger(
+            &crate::sink::MemorySink::new(),
+            LoggerOptions {
+                extra_file_sinks: vec![file_sink(LogFormat::Pretty, "ansi"), file_sink(LogFormat::Json, "jsonl")],
+                ..Default::default()
+            },
+        );
+        logger.reset_context();
+        logger.info("to both files").unwrap();
+        logger.flush().unwrap();
+
+        let read_extension = |extension: &str| -> String {
+            let mut contents = String::new();
+            for month in std::fs::read_dir(dir.path()).unwrap() {
+                for file in std::fs::read_dir(month.unwrap().path()).unwrap() {
+                    let path = file.unwrap().path();
+                    if path.extension().is_some_and(|ext| ext == extension) {
+                        contents.push_str(&std::fs::read_to_string(path).unwrap());
+                    }
+                }
+            }
+            contents
+        };
+        let pretty = read_extension("ansi");
+        assert!(pretty.contains('\u{1b}'));
+        assert!(pretty.contains("to both files"));
+        let jsonl = read_extension("jsonl");
+        assert!(!jsonl.contains('\u{1b}'));
+        assert_eq!(jsonl.lines().count(), 1);
+        let value: Value = serde_json::from_str(jsonl.lines().next().unwrap()).unwrap();
+        assert_eq!(value.get("msg").unwrap(), "to both files");
+    }
+
+    #[test]
+    #[cfg(feature = "native")]
+    fn file_colors_do_not_follow_the_terminal() {
+        let _guard = crate::TEST_GLOBAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let dir = tempfile::tempdir().unwrap();
+        let sink = crate::sink::MemorySink::new();
+        let logger = Logger::new(LoggerOptions {
+            format: Some(LogFormat::Pretty),
+            log_to_file: Some(true),
+            rotation: Some(RotationOptions {
+                path: dir.path().into(),
+                ..Default::default()
+            }),
+            sink: Some(Arc::new(sink.clone())),
+            ..Default::default()
+        });
+        logger.reset_context();
+        logger.info("colored on disk").unwrap();
+        logger.flush().unwrap();
+
+        // Test output is never a terminal, so the console copy is plain.
+        assert!(!sink.contents().contains('\u{1b}'));
+        let month = std::fs::read_dir(dir.path()).unwrap().next().unwrap().unwrap().path();
+        let file = std::fs::read_dir(month).unwrap().next().unwrap().unwrap().path();
+        let contents = std::fs::read_to_string(file).unwrap();
+        assert!(contents.contains('\u{1b}'));
+        assert!(contents.contains("colored on disk"));
+    }
+
+    #[test]
+    fn with_duration_logs_error_when_closure_panics() {
+        let _guard = crate::TEST_GLOBAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let sink = crate::sink::MemorySink::new();
+        let logger = crate::memory_logger(&sink, LoggerOptions::default());
+        logger.reset_context();
+
+        assert_eq!(logger.with_duration("db.query", || 42), 42);
+        let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| logger.with_duration("db.write", || panic!("boom"))));
+        assert!(panicked.is_err());
+
+        let lines = sink.lines();
+        assert_eq!(lines.len(), 2);
+        let ok: Value = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!(ok.get("namespace").unwrap(), "db.query");
+        assert_eq!(ok.get("LogLevel").unwrap(), "info");
+        assert!(ok.get("duration").unwrap().as_f64().unwrap() >= 0.0);
+        let failed: Value = serde_json::from_str(&lines[1]).unwrap();
+        assert_eq!(failed.get("namespace").unwrap(), "db.write");
+        assert_eq!(failed.get("LogLevel").unwrap(), "error");
+        assert!(failed.get("duration").is_some());
+    }
+
+    #[test]
+    fn set_trace_context_parses_traceparent() {
+        let _guard = crate::TEST_GLOBAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let logger = Logger::default();
+        logger.reset_context();
+
+        let mut headers = HashMap::new();
+        headers.insert("Traceparent".to_string(), "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01".to_string());
+        logger.add_http_request(HttpRequest {
+            headers: Some(headers),
+            ..Default::default()
+        });
+        assert_eq!(logger.base_context_key("traceId").unwrap(), "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(logger.base_context_key("spanId").unwrap(), "00f067aa0ba902b7");
+
+        logger.set_trace_context("00-not-a-trace-01");
+        assert_eq!(logger.base_context_key("traceId").unwrap(), "4bf92f3577b34da6a3ce929d0e0e4736");
+
+        logger.set_trace_context("v1-opaque-trace");
+        assert_eq!(logger.base_context_key("traceId").unwrap(), "v1-opaque-trace");
+        logger.reset_context();
+    }
+
+    #[test]
+    fn redact_option_masks_key_paths() {
+        let _guard = crate::TEST_GLOBAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let logger = Logger::new(LoggerOptions {
+            redact: vec!["user.email".into()],
+            ..Default::default()
+        });
+        logger.reset_context();
+        logger.add_base_context(json!({"user": {"email": "a@b.c", "id": "u1"}}));
+        let payload = logger.build_log_object(Level::Info, &log_args!("hi"));
+        assert_eq!(payload.pointer("/user/email").unwrap(), "[REDACTED]");
+        assert_eq!(payload.pointer("/user/id").unwrap(), "u1");
+        logger.reset_context();
+    }
+
     #[test]
     fn redact_default_keys_strips_auth_headers_and_secret_fields() {
         let _guard = crate::TEST_GLOBAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
@@ -807,4 +2702,279 @@ mod tests {
         assert_eq!(ctx.get("customSecret").unwrap(), "[REDACTED]");
         assert_eq!(ctx.get("visible").unwrap(), "ok");
     }
+
+    #[test]
+    fn ensure_returns_condition() {
+        let _guard = crate::TEST_GLOBAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let sink = crate::sink::MemorySink::new();
+        let logger = crate::memory_logger(&sink, LoggerOptions::default());
+        logger.reset_context();
+        logger.set_level(Level::Fatal);
+        assert!(logger.ensure(true, Level::Error, "never logged"));
+        assert!(!logger.ensure(false, Level::Error, "filtered by level"));
+        assert!(sink.lines().is_empty());
+
+        logger.set_level(Level::Info);
+        let values: Vec<u32> = Vec::new();
+        assert!(crate::log_ensure!(logger, values.is_empty(), Level::Error, "values present"));
+        assert!(!crate::log_ensure!(logger, !values.is_empty(), Level::Error, "values empty"));
+        let lines = sink.lines();
+        assert_eq!(lines.len(), 1);
+        let payload: Value = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!(payload["msg"], "values empty");
+        assert_eq!(payload["LogLevel"], "error");
+        assert_eq!(payload["context"]["condition"], "!values.is_empty()");
+    }
+
+    #[test]
+    fn level_macros_attach_source_and_skip_disabled_levels() {
+        let _guard = crate::TEST_GLOBAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let sink = crate::sink::MemorySink::new();
+        let logger = crate::memory_logger(
+            &sink,
+            LoggerOptions {
+                level: Some(Level::Info),
+                ..Default::default()
+            },
+        );
+        logger.reset_context();
+        let built = std::cell::Cell::new(0);
+        let message = || {
+            built.set(built.get() + 1);
+            "built"
+        };
+        crate::log_debug!(logger, message()).unwrap();
+        assert_eq!(built.get(), 0);
+
+        let line = line!() + 1;
+        crate::log_info!(logger, message(), json!({ "userId": "123" })).unwrap();
+        assert_eq!(built.get(), 1);
+
+        let lines = sink.lines();
+        assert_eq!(lines.len(), 1);
+        let payload: Value = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!(payload["msg"], "built");
+        assert_eq!(payload["context"]["userId"], "123");
+        assert_eq!(payload["context"]["source"]["file"], file!());
+        assert_eq!(payload["context"]["source"]["line"], line);
+        assert_eq!(payload["context"]["source"]["module"], module_path!());
+    }
+
+    #[test]
+    fn service_defaults_from_env_and_can_be_set() {
+        let _guard = crate::TEST_GLOBAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        assert_eq!(
+            default_service(|var| (var == "OTEL_SERVICE_NAME").then(|| "otel-service".to_string())).as_deref(),
+            Some("otel-service")
+        );
+        let both = |var: &str| Some(if var == "SERVICE_NAME" { "billing" } else { "otel-service" }.to_string());
+        assert_eq!(default_service(both).as_deref(), Some("billing"));
+        assert_eq!(default_service(|_| Some(" ".into())), None);
+
+        let (mut logger, _captured) = Logger::testing();
+        logger.reset_context();
+        assert!(logger.build_log_object(Level::Info, &log_args!()).get("service").is_none());
+
+        // The default fills in only when no context names a service, and never becomes global.
+        logger.default_service = Some("billing".into());
+        let child = logger.child(json!({}));
+        assert_eq!(child.build_log_object(Level::Info, &log_args!())["service"], "billing");
+        assert!(logger.context().get("service").is_none());
+        logger.set_service("payments");
+        assert_eq!(child.build_log_object(Level::Info, &log_args!())["service"], "payments");
+        logger.reset_context();
+    }
+
+    #[test]
+    fn emit_guard_blocks_reentrant_emit() {
+        let _guard = crate::TEST_GLOBAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let sink = crate::sink::MemorySink::new();
+        let nested = crate::sink::MemorySink::new();
+        let mut logger = crate::memory_logger(&sink, LoggerOptions::default());
+        logger.nested_sink = Some(Arc::new(nested.clone()));
+        let outer = EmitGuard::enter().expect("first entry should succeed");
+        assert!(EmitGuard::enter().is_none());
+
+        // A log call made while already emitting falls back to the nested output instead of recursing.
+        assert!(logger.info("nested").is_ok());
+        assert!(sink.lines().is_empty());
+        assert!(nested.lines()[0].contains("\"msg\":\"nested\""));
+
+        drop(outer);
+        assert!(EmitGuard::enter().is_some());
+    }
+
+    #[test]
+    #[cfg(feature = "native")]
+    fn child_line_payload_nests_json_and_falls_back_to_msg() {
+        let _guard = crate::TEST_GLOBAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let logger = Logger::default();
+        logger.reset_context();
+        let child_line_payload = |level, namespace, stream_name, line| {
+            let (args, fields) = logger.child_line_args(namespace, stream_name, line);
+            logger.build_log_object_with_fields(level, &args, fields)
+        };
+
+        let payload = child_line_payload(Level::Info, "worker", "stdout", r#"{"msg": "started", "jobId": 7}"#);
+        assert_eq!(payload.get("msg").unwrap(), "started");
+        assert_eq!(payload.get("namespace").unwrap(), "worker");
+        assert_eq!(payload.pointer("/context/jobId").unwrap(), 7);
+        assert_eq!(payload.pointer("/context/source").unwrap(), "child");
+
+        let payload = child_line_payload(Level::Error, "worker", "stderr", "plain failure");
+        assert_eq!(payload.get("msg").unwrap(), "plain failure");
+        assert_eq!(payload.pointer("/context/stream").unwrap(), "stderr");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    #[cfg(feature = "native")]
+    fn capture_command_decodes_lossily_and_honors_namespace_filters() {
+        let _guard = crate::TEST_GLOBAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let sink = crate::sink::MemorySink::new();
+        let options = |exclude_namespaces: Vec<String>| LoggerOptions {
+            exclude_namespaces,
+            ..Default::default()
+        };
+        let command = || {
+            let mut command = Command::new("sh");
+            command.args(["-c", r"printf 'bad \377 byte\nafter\n'"]);
+            command
+        };
+
+        let logger = crate::memory_logger(&sink, options(Vec::new()));
+        logger.reset_context();
+        logger.capture_command(command()).unwrap();
+        let messages: Vec<String> = sink
+            .lines()
+            .iter()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap()["msg"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(messages, ["bad \u{FFFD} byte", "after"]);
+
+        sink.clear();
+        let logger = crate::memory_logger(&sink, options(vec!["^sh$".into()]));
+        logger.capture_command(command()).unwrap();
+        assert!(sink.lines().is_empty());
+        assert_eq!(logger.dropped_count(), 2);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    #[cfg(feature = "native")]
+    fn capture_command_returns_exit_status() {
+        let _guard = crate::TEST_GLOBAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let logger = Logger::default();
+        logger.set_level(Level::Fatal);
+        let mut command = Command::new("sh");
+        command.args(["-c", "echo out; echo err 1>&2; exit 3"]);
+        let status = logger.capture_command(command).unwrap();
+        assert_eq!(status.code(), Some(3));
+    }
+
+    #[test]
+    fn child_loggers_keep_context_separate() {
+        let _guard = crate::TEST_GLOBAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let parent = Logger::new(LoggerOptions {
+            name: Some("Parent".into()),
+            level: Some(Level::Warn),
+            ..Default::default()
+        });
+        parent.reset_context();
+
+        let first = parent.child(json!({"requestId": "req-1", "tenant": "a"})).with_name_suffix("child");
+        let second = parent.child(json!({"requestId": "req-2"}));
+        assert_eq!(first.name(), "Parent:child");
+        assert_eq!(first.level(), Level::Warn);
+
+        let first_payload = first.build_log_object(Level::Warn, &log_args!("one"));
+        let second_payload = second.build_log_object(Level::Warn, &log_args!("two"));
+        assert_eq!(first_payload.get("requestId").unwrap(), "req-1");
+        assert_eq!(first_payload.get("name").unwrap(), "Parent:child");
+        assert_eq!(second_payload.get("requestId").unwrap(), "req-2");
+        assert!(second_payload.get("tenant").is_none());
+
+        let parent_payload = parent.build_log_object(Level::Warn, &log_args!("parent"));
+        assert!(parent_payload.get("tenant").is_none());
+        assert_ne!(parent_payload.get("requestId").unwrap(), "req-1");
+
+        let grandchild = first.child(json!({"step": 2}));
+        let payload = grandchild.build_log_object(Level::Warn, &log_args!());
+        assert_eq!(payload.get("tenant").unwrap(), "a");
+        assert_eq!(payload.get("step").unwrap(), 2);
+    }
+
+    #[test]
+    fn memory_sink_captures_emitted_lines() {
+        let _guard = crate::TEST_GLOBAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let sink = crate::sink::MemorySink::new();
+        let logger = crate::memory_logger(&sink, LoggerOptions::default());
+        logger.reset_context();
+        logger.info("captured").unwrap();
+        let lines = sink.lines();
+        assert_eq!(lines.len(), 1);
+        let value: Value = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!(value.get("msg").unwrap(), "captured");
+    }
+
+    #[test]
+    fn with_writer_routes_output_to_writer() {
+        let _guard = crate::TEST_GLOBAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let (tx, rx) = std::sync::mpsc::channel::<Vec<u8>>();
+        struct ChannelWriter(std::sync::mpsc::Sender<Vec<u8>>);
+        impl Write for ChannelWriter {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                let _ = self.0.send(buf.to_vec());
+                Ok(buf.len())
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+        let options = LoggerOptions {
+            pretty_print: Some(false),
+            log_to_file: Some(false),
+            ..Default::default()
+        };
+        let logger = Logger::with_writer(options, ChannelWriter(tx));
+        logger.warn("to writer").unwrap();
+        let written = String::from_utf8(rx.try_iter().flatten().collect()).unwrap();
+        assert!(written.contains("\"msg\":\"to writer\""));
+    }
+
+    #[test]
+    fn recursive_sink_does_not_recurse() {
+        let _guard = crate::TEST_GLOBAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        #[derive(Default)]
+        struct RecursiveSink {
+            logger: parking_lot::Mutex<Option<Arc<Logger>>>,
+            lines: crate::sink::MemorySink,
+        }
+        impl LogSink for RecursiveSink {
+            fn write(&self, level: Level, output: &str) -> io::Result<()> {
+                if let Some(logger) = self.logger.lock().clone() {
+                    logger.error("sink is logging")?;
+                }
+                self.lines.write(level, output)
+            }
+        }
+
+        let sink = Arc::new(RecursiveSink::default());
+        let nested = crate::sink::MemorySink::new();
+        let mut logger = Logger::new(LoggerOptions {
+            pretty_print: Some(false),
+            log_to_file: Some(false),
+            sink: Some(sink.clone()),
+            ..Default::default()
+        });
+        logger.nested_sink = Some(Arc::new(nested.clone()));
+        let logger = Arc::new(logger);
+        *sink.logger.lock() = Some(logger.clone());
+
+        logger.info("outer").unwrap();
+        assert_eq!(sink.lines.lines().len(), 1);
+        assert_eq!(nested.lines().len(), 1);
+        assert!(nested.lines()[0].contains("sink is logging"));
+        *sink.logger.lock() = None;
+    }
 }
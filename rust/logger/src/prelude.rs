@@ -0,0 +1,17 @@
+//! Common imports for working with the logger.
+//!
+//! ```
+//! use smooai_logger::prelude::*;
+//!
+//! let logger = Logger::new(LoggerOptions {
+//!     level: Some(Level::Warn),
+//!     ..Default::default()
+//! });
+//! let _ = logger.info(log_args!["hello", json!({"userId": "123"})]);
+//! ```
+
+pub use crate::context::ContextConfig;
+pub use crate::error::LoggedError;
+pub use crate::logger::{Level, LogArgs, Logger, LoggerOptions};
+pub use crate::{log_args, log_at, log_debug, log_ensure, log_error, log_fatal, log_info, log_trace, log_warn};
+pub use serde_json::json;
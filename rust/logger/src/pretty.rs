@@ -1,44 +1,111 @@
-use colored::{Color, Colorize};
+use std::collections::HashMap;
+use std::env;
+use std::io::IsTerminal;
+
+use colored::Color;
 use serde_json::Value;
 
+use crate::logger::Level;
+
 const SEPARATOR: &str = "----------------------------------------------------------------------------------------------------";
 const MESSAGE_COLOR: Color = Color::TrueColor { r: 46, g: 204, b: 113 };
 const TIME_COLOR: Color = Color::TrueColor { r: 52, g: 152, b: 219 };
 const ERROR_COLOR: Color = Color::TrueColor { r: 231, g: 76, b: 60 };
 
+/// Layout of pretty-printed log blocks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrettyConfig {
+    /// Line printed after each block.
+    pub separator: String,
+    /// How many times the separator line is repeated.
+    pub separator_count: usize,
+    /// Keys whose name is highlighted (bold, in the given color) wherever they appear.
+    pub key_colors: HashMap<String, Color>,
+    /// Color the `msg` and `LogLevel` keys by the payload's level (see [`Level::rgb`])
+    /// instead of using `key_colors` for them.
+    pub color_by_level: bool,
+}
+
+impl Default for PrettyConfig {
+    fn default() -> Self {
+        Self {
+            separator: SEPARATOR.to_string(),
+            separator_count: 3,
+            key_colors: HashMap::from([
+                ("msg".to_string(), MESSAGE_COLOR),
+                ("time".to_string(), TIME_COLOR),
+                ("error".to_string(), ERROR_COLOR),
+            ]),
+            color_by_level: true,
+        }
+    }
+}
+
+/// Whether pretty output bound for the console should carry ANSI colors. `force_color` wins
+/// when set; otherwise color is off when `NO_COLOR` is set (to any value), when `TERM=dumb`,
+/// or when stdout is not a terminal. Log files do not go through this check.
+pub fn color_enabled(force_color: Option<bool>) -> bool {
+    force_color.unwrap_or_else(|| env::var_os("NO_COLOR").is_none() && env::var("TERM").map_or(true, |term| term != "dumb") && std::io::stdout().is_terminal())
+}
+
 pub fn pretty_json(object: &Value) -> String {
+    pretty_json_with_color(object, color_enabled(None))
+}
+
+/// Same layout as [`pretty_json`], with key highlighting only when `color` is true.
+pub fn pretty_json_with_color(object: &Value, color: bool) -> String {
+    pretty_json_with_config(object, &PrettyConfig::default(), color)
+}
+
+/// Pretty-prints `object` using the separator and key colors from `config`.
+pub fn pretty_json_with_config(object: &Value, config: &PrettyConfig, color: bool) -> String {
     let mut output = String::new();
     let serialized = serde_json::to_string_pretty(object).unwrap_or_else(|_| "{}".to_string());
+    let level_color = config
+        .color_by_level
+        .then(|| object.get("LogLevel").and_then(Value::as_str).and_then(Level::parse_level))
+        .flatten()
+        .map(|level| {
+            let (r, g, b) = level.rgb();
+            Color::TrueColor { r, g, b }
+        });
 
     for line in serialized.lines() {
-        let trimmed = line.trim_start();
-        let formatted = if trimmed.starts_with("\"msg\"") {
-            highlight_key(line, MESSAGE_COLOR)
-        } else if trimmed.starts_with("\"time\"") {
-            highlight_key(line, TIME_COLOR)
-        } else if trimmed.starts_with("\"error\"") {
-            highlight_key(line, ERROR_COLOR)
+        let key_color = if color {
+            line_key(line).and_then(|key| match (key, level_color) {
+                ("msg" | "LogLevel", Some(level_color)) => Some(level_color),
+                _ => config.key_colors.get(key).copied(),
+            })
         } else {
-            line.to_string()
+            None
+        };
+        let formatted = match key_color {
+            Some(key_color) => highlight_key(line, key_color),
+            None => line.to_string(),
         };
         output.push_str(&formatted);
         output.push('\n');
     }
 
-    output.push_str(SEPARATOR);
-    output.push('\n');
-    output.push_str(SEPARATOR);
-    output.push('\n');
-    output.push_str(SEPARATOR);
-    output.push('\n');
+    for _ in 0..config.separator_count {
+        output.push_str(&config.separator);
+        output.push('\n');
+    }
 
     output
 }
 
+/// Returns the key of a `"key": value` line from `to_string_pretty` output.
+fn line_key(line: &str) -> Option<&str> {
+    let rest = line.trim_start().strip_prefix('"')?;
+    let end = rest.find("\":")?;
+    Some(&rest[..end])
+}
+
 fn highlight_key(line: &str, color: Color) -> String {
     let mut parts = line.splitn(2, ':');
     if let (Some(key), Some(rest)) = (parts.next(), parts.next()) {
-        format!("{}:{}", key.color(color).bold(), rest)
+        format!("\x1b[1;{}m{}\x1b[0m:{}", color.to_fg_str(), key, rest)
     } else {
         line.to_string()
     }
@@ -48,6 +115,57 @@ pub fn plain_json(object: &Value) -> String {
     serde_json::to_string(object).unwrap_or_else(|_| "{}".to_string())
 }
 
+/// Formats `object` as one logfmt line (without trailing newline). Nested objects are
+/// flattened to dotted keys and arrays to `key[index]`. Strings are quoted when they are
+/// empty or contain spaces, `=`, quotes or control characters; numbers, booleans and
+/// `null` are written bare.
+pub fn logfmt(object: &Value) -> String {
+    let mut pairs = Vec::new();
+    flatten_logfmt(object, "", &mut pairs);
+    pairs.join(" ")
+}
+
+fn flatten_logfmt(value: &Value, prefix: &str, out: &mut Vec<String>) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map {
+                let key = if prefix.is_empty() { key.clone() } else { format!("{prefix}.{key}") };
+                flatten_logfmt(child, &key, out);
+            }
+        }
+        Value::Array(items) => {
+            for (index, child) in items.iter().enumerate() {
+                flatten_logfmt(child, &format!("{prefix}[{index}]"), out);
+            }
+        }
+        Value::String(text) => out.push(format!("{prefix}={}", logfmt_string(text))),
+        other if !prefix.is_empty() => out.push(format!("{prefix}={other}")),
+        _ => {}
+    }
+}
+
+fn logfmt_string(text: &str) -> String {
+    let needs_quotes = text.is_empty() || text.chars().any(|c| c == ' ' || c == '=' || c == '"' || c == '\\' || c.is_control());
+    if !needs_quotes {
+        return text.to_string();
+    }
+
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
 pub fn separator() -> &'static str {
     SEPARATOR
 }
@@ -63,4 +181,56 @@ mod tests {
         let formatted = pretty_json(&value);
         assert!(formatted.contains(SEPARATOR));
     }
+
+    #[test]
+    fn pretty_json_without_color_has_no_escape_codes() {
+        let value = json!({"msg": "hello", "error": "boom"});
+        let plain = pretty_json_with_color(&value, false);
+        assert!(!plain.contains('\x1b'));
+        assert!(plain.contains(SEPARATOR));
+        assert!(pretty_json_with_color(&value, true).contains("\x1b[1;"));
+        assert!(color_enabled(Some(true)));
+        assert!(!color_enabled(Some(false)));
+    }
+
+    #[test]
+    fn pretty_config_controls_separator_and_key_colors() {
+        let config = PrettyConfig {
+            separator: "==".into(),
+            separator_count: 1,
+            key_colors: HashMap::from([("correlationId".to_string(), Color::Yellow)]),
+            color_by_level: false,
+        };
+        let value = json!({"msg": "hello", "correlationId": "abc"});
+        let formatted = pretty_json_with_config(&value, &config, true);
+        assert!(formatted.ends_with("}\n==\n"));
+        assert!(formatted.contains(&format!("\x1b[1;{}m  \"correlationId\"\x1b[0m", Color::Yellow.to_fg_str())));
+        assert!(formatted.contains("\n  \"msg\": \"hello\""));
+    }
+
+    #[test]
+    fn pretty_json_colors_msg_by_level() {
+        let value = json!({"msg": "boom", "LogLevel": "error"});
+        let formatted = pretty_json_with_color(&value, true);
+        let red = Color::TrueColor { r: 0xff, g: 0x6b, b: 0x6c }.to_fg_str();
+        assert!(formatted.contains(&format!("\x1b[1;{red}m  \"msg\"\x1b[0m")));
+        assert!(formatted.contains(&format!("\x1b[1;{red}m  \"LogLevel\"\x1b[0m")));
+        assert!(!pretty_json_with_color(&value, false).contains('\x1b'));
+    }
+
+    #[test]
+    fn logfmt_flattens_and_quotes() {
+        let value = json!({
+            "msg": "hello world",
+            "level": 30,
+            "ok": true,
+            "name": "api",
+            "http": {"request": {"path": "/a=b"}},
+            "tags": ["x", "line\none"]
+        });
+        assert_eq!(
+            logfmt(&value),
+            r#"msg="hello world" level=30 ok=true name=api http.request.path="/a=b" tags[0]=x tags[1]="line\none""#
+        );
+    }
 }
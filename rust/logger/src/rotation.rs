@@ -1,11 +1,51 @@
 use std::fs::{self, File, OpenOptions};
-use std::io::{self, Write};
+use std::io::{self, BufWriter, Write};
 use std::path::{Path, PathBuf};
+use std::thread::{self, JoinHandle};
 use std::time::UNIX_EPOCH;
 
 use chrono::{Datelike, Duration, Utc};
+use flate2::write::GzEncoder;
+use flate2::Compression;
 use parking_lot::Mutex;
 
+use crate::logger::{ErrorCallback, Level};
+
+/// How `RotationOptions::interval` is measured.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RotationInterval {
+    /// Rotate once `interval` has elapsed since the current file was opened.
+    #[default]
+    Sliding,
+    /// Rotate when the clock crosses the next multiple of `interval` since the Unix epoch
+    /// (UTC): midnight for `1d`, the top of the hour for `1h`, and so on.
+    Calendar,
+}
+
+/// How hard [`RotatingFileWriter::write`] works to get each line onto disk. Levels in
+/// `RotationOptions::sync_on_levels` are always fsynced, whatever the mode.
+///
+/// `RotationOptions::buffered` only drops the per-line flush of `Flush` and
+/// `FsyncOnRotate`. `None` buffers whether or not it is set, and `FsyncEveryWrite` always
+/// wins over it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SyncMode {
+    /// Leave lines in the userspace buffer until it fills, the file rotates, or
+    /// [`RotatingFileWriter::flush`] is called. Cheapest; a crash loses the buffer.
+    None,
+    /// Flush the buffer to the OS after every line (unless `buffered` is set). Survives a
+    /// process crash but not a power loss, since the OS may still hold the data.
+    #[default]
+    Flush,
+    /// Flush and `sync_all` after every line. Every line survives a power loss, at the cost
+    /// of a disk round trip per line; use it for audit logs, not high-volume output.
+    FsyncEveryWrite,
+    /// Flush after every line like `Flush` (unless `buffered` is set), and `sync_all` each
+    /// file once before it is rotated out, so finished files are durable without paying per
+    /// line.
+    FsyncOnRotate,
+}
+
 #[derive(Clone, Debug)]
 pub struct RotationOptions {
     pub path: PathBuf,
@@ -13,8 +53,35 @@ pub struct RotationOptions {
     pub extension: String,
     pub size: Option<String>,
     pub interval: Option<String>,
+    pub interval_mode: RotationInterval,
     pub max_files: usize,
     pub max_total_size: Option<String>,
+    /// Delete log files last modified longer ago than this (same syntax as `interval`, e.g.
+    /// `"14d"`), across every `YYYY-MM` month folder. Month folders left empty are removed.
+    /// Checked on rotation and best effort: a file that cannot be removed is reported on
+    /// stderr and logging carries on.
+    pub max_age: Option<String>,
+    /// File name, relative to `path`, of a symlink kept pointing at the current log file
+    /// (e.g. `"output-latest.ansi"`). On Windows a pointer file containing the relative
+    /// path is written instead. The link is replaced atomically on rotation. It is best
+    /// effort: a link that cannot be written is reported on stderr and logging carries on.
+    pub latest_symlink: Option<String>,
+    /// Levels whose lines are followed by a `sync_all` on the log file so they survive a
+    /// crash. Other levels are appended without forcing a sync.
+    pub sync_on_levels: Vec<Level>,
+    /// Gzip each file once it has been rotated out (to `<name>.gz`) and remove the original.
+    /// Compression runs on a background thread; the currently-open file is never compressed,
+    /// and retention leaves a file alone until its compression has finished. A failed
+    /// compression keeps the original and goes to `LoggerOptions::on_error`, or stderr.
+    pub compress: bool,
+    /// Buffer writes instead of flushing after every line. Buffered bytes are written out by
+    /// [`RotatingFileWriter::flush`], on rotation, on synced writes and when the writer is
+    /// dropped. A panic that aborts or a `process::exit` skips `Drop`, losing whatever is
+    /// still buffered, so call `Logger::flush` before exiting explicitly. Ignored by
+    /// [`SyncMode::FsyncEveryWrite`], which flushes every line anyway; see [`SyncMode`].
+    pub buffered: bool,
+    /// Flush and fsync behavior for each write. Defaults to [`SyncMode::Flush`].
+    pub sync_mode: SyncMode,
 }
 
 impl Default for RotationOptions {
@@ -25,20 +92,31 @@ impl Default for RotationOptions {
             extension: "ansi".into(),
             size: Some("1M".into()),
             interval: Some("1d".into()),
+            interval_mode: RotationInterval::Sliding,
             max_files: 30,
             max_total_size: Some("100M".into()),
+            max_age: None,
+            latest_symlink: None,
+            sync_on_levels: vec![Level::Error, Level::Fatal],
+            compress: false,
+            buffered: false,
+            sync_mode: SyncMode::Flush,
         }
     }
 }
 
 #[derive(Debug)]
 struct WriterState {
-    file: File,
+    file: BufWriter<File>,
     bytes_written: u64,
     current_dir: PathBuf,
     current_path: PathBuf,
     index: u32,
+    /// When the current interval started (`Sliding`) or the boundary at which it ends
+    /// (`Calendar`).
     interval_anchor: chrono::DateTime<Utc>,
+    /// Background compressions started by `rotate`, keyed by the file being compressed.
+    compressions: Vec<(PathBuf, JoinHandle<()>)>,
 }
 
 #[derive(Debug)]
@@ -47,17 +125,31 @@ pub struct RotatingFileWriter {
     max_bytes: Option<u64>,
     max_total_bytes: Option<u64>,
     interval: Option<Duration>,
+    max_age: Option<Duration>,
+    /// Receives failures from background compression; stderr when unset.
+    on_error: Option<ErrorCallback>,
     state: Mutex<WriterState>,
 }
 
 impl RotatingFileWriter {
-    pub fn new(options: RotationOptions) -> io::Result<Self> {
-        let max_bytes = options.size.as_ref().and_then(|s| parse_size(s).ok());
-        let max_total_bytes = options.max_total_size.as_ref().and_then(|s| parse_size(s).ok());
+    /// Opens the current log file. Fails with `InvalidInput` if `filename_prefix` or
+    /// `extension` could escape `path` (a path separator or `..`), or if a size or
+    /// `max_age` does not parse; a leading dot on `extension` is dropped, so `".ansi"` and
+    /// `"ansi"` are equivalent.
+    pub fn new(mut options: RotationOptions) -> io::Result<Self> {
+        options.extension = options.extension.trim_start_matches('.').to_string();
+        validate_file_part("filename_prefix", &options.filename_prefix)?;
+        validate_file_part("extension", &options.extension)?;
+        let max_bytes = options.size.as_deref().map(parse_size_option).transpose()?;
+        let max_total_bytes = options.max_total_size.as_deref().map(parse_size_option).transpose()?;
         let interval = options.interval.as_ref().and_then(|s| parse_interval(s).ok());
+        let max_age = options.max_age.as_deref().map(parse_max_age_option).transpose()?;
 
         let now = Utc::now();
-        let (file, current_dir, current_path) = open_file(&options, &now, 0)?;
+        let interval_mode = options.interval_mode;
+        let index = resume_index(&options, &now);
+        let (file, current_dir, current_path) = open_file(&options, &now, index)?;
+        refresh_latest_link(&options, &current_path);
         let bytes_written = file.metadata().map(|m| m.len()).unwrap_or(0);
 
         Ok(Self {
@@ -65,28 +157,68 @@ impl RotatingFileWriter {
             max_bytes,
             max_total_bytes,
             interval,
+            max_age,
+            on_error: None,
             state: Mutex::new(WriterState {
-                file,
+                file: BufWriter::new(file),
                 bytes_written,
                 current_dir,
                 current_path,
-                index: 0,
-                interval_anchor: now,
+                index,
+                interval_anchor: interval_anchor(interval_mode, interval, &now),
+                compressions: Vec::new(),
             }),
         })
     }
 
+    /// Sends errors from background compression to `on_error` instead of stderr.
+    pub(crate) fn with_on_error(mut self, on_error: Option<ErrorCallback>) -> Self {
+        self.on_error = on_error;
+        self
+    }
+
+    pub fn options(&self) -> &RotationOptions {
+        &self.options
+    }
+
     pub fn write(&self, payload: &str) -> io::Result<()> {
+        self.write_inner(payload, false)
+    }
+
+    /// Writes `payload` and then forces it to disk with `sync_all`.
+    pub fn write_synced(&self, payload: &str) -> io::Result<()> {
+        self.write_inner(payload, true)
+    }
+
+    fn write_inner(&self, payload: &str, sync: bool) -> io::Result<()> {
         let mut state = self.state.lock();
         let now = Utc::now();
         let payload_bytes = payload.as_bytes();
         if self.should_rotate(&state, &now, payload_bytes.len() as u64) {
-            rotate(&self.options, &mut state, &now, self.max_total_bytes)?;
+            self.rotate(&mut state, &now)?;
+            state.interval_anchor = interval_anchor(self.options.interval_mode, self.interval, &now);
         }
 
         state.file.write_all(payload_bytes)?;
         state.bytes_written += payload_bytes.len() as u64;
-        state.file.flush()
+        let fsync = sync || self.options.sync_mode == SyncMode::FsyncEveryWrite;
+        let flush = match self.options.sync_mode {
+            SyncMode::None => false,
+            SyncMode::Flush | SyncMode::FsyncOnRotate => !self.options.buffered,
+            SyncMode::FsyncEveryWrite => true,
+        };
+        if flush || fsync {
+            state.file.flush()?;
+        }
+        if fsync {
+            state.file.get_ref().sync_all()?;
+        }
+        Ok(())
+    }
+
+    /// Writes out any buffered bytes to the current file.
+    pub fn flush(&self) -> io::Result<()> {
+        self.state.lock().file.flush()
     }
 
     fn should_rotate(&self, state: &WriterState, now: &chrono::DateTime<Utc>, additional: u64) -> bool {
@@ -97,35 +229,183 @@ impl RotatingFileWriter {
         }
 
         if let Some(interval) = self.interval {
-            if *now - state.interval_anchor >= interval {
+            let elapsed = match self.options.interval_mode {
+                RotationInterval::Sliding => *now - state.interval_anchor >= interval,
+                RotationInterval::Calendar => *now >= state.interval_anchor,
+            };
+            if elapsed {
                 return true;
             }
         }
 
         false
     }
+
+    fn rotate(&self, state: &mut WriterState, now: &chrono::DateTime<Utc>) -> io::Result<()> {
+        let options = &self.options;
+        let current_dir = log_directory(options, now);
+        let first_index = if current_dir == state.current_dir { state.index + 1 } else { 0 };
+        let next_index = next_free_index(options, now, first_index);
+
+        state.file.flush()?;
+        if options.sync_mode == SyncMode::FsyncOnRotate {
+            state.file.get_ref().sync_all()?;
+        }
+        let (file, dir, path) = open_file(options, now, next_index)?;
+        refresh_latest_link(options, &path);
+
+        let finished_path = std::mem::replace(&mut state.current_path, path.clone());
+        state.file = BufWriter::new(file);
+        state.bytes_written = 0;
+        state.current_dir = dir.clone();
+        state.index = next_index;
+
+        state.compressions.retain(|(_, handle)| !handle.is_finished());
+        if options.compress && finished_path != path {
+            let compressing = finished_path.clone();
+            let on_error = self.on_error.clone();
+            let handle = thread::spawn(move || {
+                if let Err(error) = compress_file(&compressing) {
+                    let message = format!("failed to compress {}: {error}", compressing.display());
+                    match on_error {
+                        Some(on_error) => on_error.call(&io::Error::new(error.kind(), message)),
+                        None => eprintln!("smooai-logger: {message}"),
+                    }
+                }
+            });
+            state.compressions.push((finished_path, handle));
+        }
+
+        let in_flight: Vec<&Path> = state.compressions.iter().map(|(path, _)| path.as_path()).collect();
+        enforce_limits(options, &dir, self.max_total_bytes, &in_flight)?;
+        // The new file is already open, so a failed prune must not fail the write.
+        if let Some(max_age) = self.max_age {
+            if let Err(error) = prune_by_age(options, max_age, now, &in_flight) {
+                eprintln!("smooai-logger: could not prune old logs in {}: {error}", options.path.display());
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for RotatingFileWriter {
+    fn drop(&mut self) {
+        let state = self.state.get_mut();
+        let _ = state.file.flush();
+        for (_, handle) in state.compressions.drain(..) {
+            let _ = handle.join();
+        }
+    }
+}
+
+fn interval_anchor(mode: RotationInterval, interval: Option<Duration>, now: &chrono::DateTime<Utc>) -> chrono::DateTime<Utc> {
+    match (mode, interval) {
+        (RotationInterval::Calendar, Some(interval)) => next_boundary(interval, now),
+        _ => *now,
+    }
+}
+
+/// Returns the first multiple of `interval` since the Unix epoch that is after `now`.
+fn next_boundary(interval: Duration, now: &chrono::DateTime<Utc>) -> chrono::DateTime<Utc> {
+    let step = interval.num_milliseconds();
+    if step <= 0 {
+        return *now;
+    }
+    let millis = now.timestamp_millis();
+    let next = (millis.div_euclid(step) + 1) * step;
+    chrono::DateTime::from_timestamp_millis(next).unwrap_or(*now)
 }
 
-fn rotate(options: &RotationOptions, state: &mut WriterState, now: &chrono::DateTime<Utc>, max_total_bytes: Option<u64>) -> io::Result<()> {
-    let mut next_index = state.index + 1;
-    let current_dir = log_directory(options, now);
-    if current_dir != state.current_dir {
-        next_index = 0;
+fn refresh_latest_link(options: &RotationOptions, current_path: &Path) {
+    if let Err(error) = update_latest_link(options, current_path) {
+        eprintln!("smooai-logger: could not point the latest link at {}: {error}", current_path.display());
     }
+}
 
-    let (file, dir, path) = open_file(options, now, next_index)?;
+fn update_latest_link(options: &RotationOptions, current_path: &Path) -> io::Result<()> {
+    let Some(name) = &options.latest_symlink else {
+        return Ok(());
+    };
+    let link = options.path.join(name);
+    let temp = options.path.join(format!(".{name}.tmp"));
+    let target = current_path.strip_prefix(&options.path).unwrap_or(current_path);
+
+    let _ = fs::remove_file(&temp);
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(target, &temp)?;
+    #[cfg(not(unix))]
+    fs::write(&temp, target.to_string_lossy().as_bytes())?;
+    fs::rename(&temp, &link)
+}
 
-    state.file = file;
-    state.bytes_written = 0;
-    state.current_dir = dir.clone();
-    state.current_path = path.clone();
-    state.index = next_index;
-    state.interval_anchor = *now;
+fn prune_by_age(options: &RotationOptions, max_age: Duration, now: &chrono::DateTime<Utc>, in_flight: &[&Path]) -> io::Result<()> {
+    if !options.path.exists() {
+        return Ok(());
+    }
+    let Ok(cutoff) = (*now - max_age).timestamp_millis().try_into() else {
+        return Ok(());
+    };
+    let cutoff = UNIX_EPOCH + std::time::Duration::from_millis(cutoff);
+
+    for month_dir in fs::read_dir(&options.path)?.filter_map(|entry| entry.ok()) {
+        if !month_dir.file_type().map(|ft| ft.is_dir()).unwrap_or(false) || !is_month_folder(&month_dir.file_name()) {
+            continue;
+        }
+        let month_path = month_dir.path();
+        let Ok(entries) = fs::read_dir(&month_path) else {
+            continue;
+        };
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let is_log =
+                entry.file_type().map(|ft| ft.is_file()).unwrap_or(false) && has_prefix(entry.file_name(), &options.filename_prefix, &options.extension);
+            let expired = entry.metadata().and_then(|m| m.modified()).map(|modified| modified < cutoff).unwrap_or(false);
+            if is_log && expired && !is_compressing(&entry.path(), in_flight) {
+                let _ = fs::remove_file(entry.path());
+            }
+        }
+        if fs::read_dir(&month_path).is_ok_and(|mut entries| entries.next().is_none()) {
+            let _ = fs::remove_dir(&month_path);
+        }
+    }
+    Ok(())
+}
+
+/// Whether `name` is a `YYYY-MM` folder, as created by [`log_directory`].
+fn is_month_folder(name: &std::ffi::OsStr) -> bool {
+    let name = name.as_encoded_bytes();
+    name.len() == 7 && name[4] == b'-' && name.iter().enumerate().all(|(i, byte)| i == 4 || byte.is_ascii_digit())
+}
+
+fn gz_path(path: &Path) -> PathBuf {
+    let mut gz_name = path.as_os_str().to_owned();
+    gz_name.push(".gz");
+    PathBuf::from(gz_name)
+}
 
-    enforce_limits(options, &dir, max_total_bytes)
+/// Whether `path` is a file being compressed, or the archive it is being compressed into.
+fn is_compressing(path: &Path, in_flight: &[&Path]) -> bool {
+    in_flight.iter().any(|source| *source == path || gz_path(source) == path)
 }
 
-fn enforce_limits(options: &RotationOptions, directory: &Path, max_total_bytes: Option<u64>) -> io::Result<()> {
+/// Gzips `path` into `<path>.gz` and removes the original. Never overwrites an existing
+/// archive; on failure the original is kept and any partial archive is removed.
+fn compress_file(path: &Path) -> io::Result<PathBuf> {
+    let gz_path = gz_path(path);
+    let mut input = File::open(path)?;
+    let output = OpenOptions::new().write(true).create_new(true).open(&gz_path)?;
+    let mut encoder = GzEncoder::new(output, Compression::default());
+    let written = io::copy(&mut input, &mut encoder)
+        .and_then(|_| encoder.finish())
+        .and_then(|file| file.sync_all());
+    if let Err(error) = written {
+        let _ = fs::remove_file(&gz_path);
+        return Err(error);
+    }
+    fs::remove_file(path)?;
+    Ok(gz_path)
+}
+
+fn enforce_limits(options: &RotationOptions, directory: &Path, max_total_bytes: Option<u64>, in_flight: &[&Path]) -> io::Result<()> {
     if !directory.exists() {
         return Ok(());
     }
@@ -134,6 +414,7 @@ fn enforce_limits(options: &RotationOptions, directory: &Path, max_total_bytes:
         .filter_map(|entry| entry.ok())
         .filter(|entry| entry.file_type().map(|ft| ft.is_file()).unwrap_or(false))
         .filter(|entry| has_prefix(entry.file_name(), &options.filename_prefix, &options.extension))
+        .filter(|entry| !is_compressing(&entry.path(), in_flight))
         .collect();
 
     entries.sort_by(|a, b| {
@@ -177,6 +458,7 @@ fn enforce_limits(options: &RotationOptions, directory: &Path, max_total_bytes:
 
 fn has_prefix(name: std::ffi::OsString, prefix: &str, extension: &str) -> bool {
     let name = name.to_string_lossy();
+    let name = name.strip_suffix(".gz").unwrap_or(&name);
     name.starts_with(prefix) && name.ends_with(extension)
 }
 
@@ -189,6 +471,48 @@ fn open_file(options: &RotationOptions, now: &chrono::DateTime<Utc>, index: u32)
     Ok((file, directory, path))
 }
 
+/// Index to open on startup: the highest index already on disk for today, so a restart
+/// keeps appending to the file it was writing, or the one after it if that file has
+/// already been compressed.
+fn resume_index(options: &RotationOptions, now: &chrono::DateTime<Utc>) -> u32 {
+    let stem = log_filename(options, now, 0);
+    let stem = stem.strip_suffix(&format!("000.{}", options.extension)).unwrap_or(&stem);
+    let suffix = format!(".{}", options.extension);
+    let Ok(entries) = fs::read_dir(log_directory(options, now)) else {
+        return 0;
+    };
+    let highest = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let rest = name.strip_prefix(stem)?;
+            let (digits, compressed) = match rest.strip_suffix(".gz") {
+                Some(rest) => (rest.strip_suffix(&suffix)?, true),
+                None => (rest.strip_suffix(&suffix)?, false),
+            };
+            Some((digits.parse::<u32>().ok()?, compressed))
+        })
+        .max();
+    match highest {
+        None => 0,
+        Some((index, false)) => index,
+        Some((index, true)) => index + 1,
+    }
+}
+
+/// First index from `from` onwards with neither a log file nor an archive on disk.
+fn next_free_index(options: &RotationOptions, now: &chrono::DateTime<Utc>, from: u32) -> u32 {
+    let directory = log_directory(options, now);
+    let mut index = from;
+    loop {
+        let path = directory.join(log_filename(options, now, index));
+        if !path.exists() && !gz_path(&path).exists() {
+            return index;
+        }
+        index += 1;
+    }
+}
+
 fn log_directory(options: &RotationOptions, now: &chrono::DateTime<Utc>) -> PathBuf {
     let folder = format!("{:04}-{:02}", now.year(), now.month());
     options.path.join(folder)
@@ -206,18 +530,41 @@ fn log_filename(options: &RotationOptions, now: &chrono::DateTime<Utc>, index: u
     )
 }
 
+fn validate_file_part(field: &str, value: &str) -> io::Result<()> {
+    if value.contains(['/', '\\']) || value.contains("..") {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("rotation {field} `{value}` must not contain path separators or `..`"),
+        ));
+    }
+    Ok(())
+}
+
+fn parse_max_age_option(max_age: &str) -> io::Result<Duration> {
+    parse_interval(max_age).map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, format!("invalid rotation max_age `{max_age}`")))
+}
+
+fn parse_size_option(size: &str) -> io::Result<u64> {
+    parse_size(size).map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, format!("invalid rotation size `{size}`")))
+}
+
+/// Parses sizes such as `512`, `10K`, `1.5M`, `0.5G` or `2T` (binary multiples).
 fn parse_size(size: &str) -> Result<u64, &'static str> {
     let upper = size.trim().to_uppercase();
-    if let Some(stripped) = upper.strip_suffix('K') {
-        return stripped.parse::<u64>().map(|n| n * 1024).map_err(|_| "invalid size");
+    let (number, multiplier) = match upper.char_indices().last() {
+        Some((pos, 'K')) => (&upper[..pos], 1u64 << 10),
+        Some((pos, 'M')) => (&upper[..pos], 1u64 << 20),
+        Some((pos, 'G')) => (&upper[..pos], 1u64 << 30),
+        Some((pos, 'T')) => (&upper[..pos], 1u64 << 40),
+        _ => (upper.as_str(), 1),
+    };
+    if let Ok(whole) = number.parse::<u64>() {
+        return whole.checked_mul(multiplier).ok_or("invalid size");
     }
-    if let Some(stripped) = upper.strip_suffix('M') {
-        return stripped.parse::<u64>().map(|n| n * 1024 * 1024).map_err(|_| "invalid size");
+    match number.parse::<f64>() {
+        Ok(value) if value.is_finite() && value >= 0.0 && value * (multiplier as f64) < u64::MAX as f64 => Ok((value * multiplier as f64) as u64),
+        _ => Err("invalid size"),
     }
-    if let Some(stripped) = upper.strip_suffix('G') {
-        return stripped.parse::<u64>().map(|n| n * 1024 * 1024 * 1024).map_err(|_| "invalid size");
-    }
-    upper.parse::<u64>().map_err(|_| "invalid size")
 }
 
 fn parse_interval(interval: &str) -> Result<Duration, &'static str> {
@@ -261,4 +608,344 @@ mod tests {
         let writer = RotatingFileWriter::new(options).unwrap();
         writer.write("test line\n").unwrap();
     }
+
+    #[test]
+    fn write_synced_appends_to_current_file() {
+        let dir = tempdir().unwrap();
+        let options = RotationOptions {
+            path: dir.path().into(),
+            ..Default::default()
+        };
+        assert_eq!(options.sync_on_levels, vec![Level::Error, Level::Fatal]);
+        let writer = RotatingFileWriter::new(options).unwrap();
+        writer.write("buffered\n").unwrap();
+        writer.write_synced("synced\n").unwrap();
+        let path = writer.state.lock().current_path.clone();
+        assert_eq!(fs::read_to_string(path).unwrap(), "buffered\nsynced\n");
+    }
+
+    #[test]
+    fn has_prefix_matches_compressed_files() {
+        assert!(has_prefix("output-2024-01-01-000.ansi".into(), "output", "ansi"));
+        assert!(has_prefix("output-2024-01-01-000.ansi.gz".into(), "output", "ansi"));
+        assert!(!has_prefix("output-2024-01-01-000.gz".into(), "output", "ansi"));
+    }
+
+    #[test]
+    fn compress_file_replaces_original_with_gzip() {
+        use flate2::read::GzDecoder;
+        use std::io::Read;
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("output-2024-01-01-000.ansi");
+        fs::write(&path, "rotated line\n").unwrap();
+        let gz_path = compress_file(&path).unwrap();
+        assert!(!path.exists());
+        assert_eq!(gz_path, dir.path().join("output-2024-01-01-000.ansi.gz"));
+
+        let mut contents = String::new();
+        GzDecoder::new(File::open(gz_path).unwrap()).read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "rotated line\n");
+    }
+
+    #[test]
+    fn restart_then_rotation_keeps_earlier_archives() {
+        use flate2::read::GzDecoder;
+        use std::io::Read;
+
+        let dir = tempdir().unwrap();
+        let options = RotationOptions {
+            path: dir.path().into(),
+            compress: true,
+            ..Default::default()
+        };
+        let now = Utc::now();
+        let writer = RotatingFileWriter::new(options.clone()).unwrap();
+        writer.write("first run\n").unwrap();
+        writer.rotate(&mut writer.state.lock(), &now).unwrap();
+        writer.write("first run, second file\n").unwrap();
+        drop(writer);
+
+        let writer = RotatingFileWriter::new(options.clone()).unwrap();
+        assert_eq!(writer.state.lock().index, 1);
+        writer.write("second run\n").unwrap();
+        writer.rotate(&mut writer.state.lock(), &now).unwrap();
+        assert_eq!(writer.state.lock().index, 2);
+        drop(writer);
+
+        let archive = |index| {
+            let mut contents = String::new();
+            let path = gz_path(&log_directory(&options, &now).join(log_filename(&options, &now, index)));
+            GzDecoder::new(File::open(path).unwrap()).read_to_string(&mut contents).unwrap();
+            contents
+        };
+        assert_eq!(archive(0), "first run\n");
+        assert_eq!(archive(1), "first run, second file\nsecond run\n");
+    }
+
+    #[test]
+    fn failed_compression_reaches_on_error() {
+        let dir = tempdir().unwrap();
+        let options = RotationOptions {
+            path: dir.path().into(),
+            compress: true,
+            ..Default::default()
+        };
+        let errors = std::sync::Arc::new(Mutex::new(Vec::new()));
+        let seen = errors.clone();
+        let on_error = ErrorCallback::new(move |error| seen.lock().push((error.kind(), error.to_string())));
+        let writer = RotatingFileWriter::new(options).unwrap().with_on_error(Some(on_error));
+        writer.write("kept\n").unwrap();
+        let finished = writer.state.lock().current_path.clone();
+        File::create(gz_path(&finished)).unwrap();
+
+        writer.rotate(&mut writer.state.lock(), &Utc::now()).unwrap();
+        drop(writer);
+
+        let errors = errors.lock();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, io::ErrorKind::AlreadyExists);
+        assert!(errors[0].1.contains("failed to compress"), "{}", errors[0].1);
+        assert_eq!(fs::read_to_string(&finished).unwrap(), "kept\n");
+    }
+
+    #[test]
+    fn compress_file_never_overwrites_an_archive() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("output-2024-01-01-000.ansi");
+        fs::write(&path, "new\n").unwrap();
+        fs::write(gz_path(&path), "existing").unwrap();
+        assert_eq!(compress_file(&path).unwrap_err().kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(fs::read_to_string(gz_path(&path)).unwrap(), "existing");
+        assert!(path.exists());
+    }
+
+    #[test]
+    fn buffered_writer_flushes_on_flush_and_drop() {
+        let dir = tempdir().unwrap();
+        let options = RotationOptions {
+            path: dir.path().into(),
+            buffered: true,
+            ..Default::default()
+        };
+        let writer = RotatingFileWriter::new(options).unwrap();
+        let path = writer.state.lock().current_path.clone();
+
+        writer.write("first\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "");
+        writer.flush().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "first\n");
+
+        writer.write("second\n").unwrap();
+        drop(writer);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "first\nsecond\n");
+    }
+
+    #[test]
+    fn calendar_boundaries_align_to_midnight_and_hour() {
+        let now = chrono::DateTime::parse_from_rfc3339("2024-03-10T23:59:30Z").unwrap().with_timezone(&Utc);
+        assert_eq!(next_boundary(Duration::days(1), &now).to_rfc3339(), "2024-03-11T00:00:00+00:00");
+        assert_eq!(next_boundary(Duration::hours(1), &now).to_rfc3339(), "2024-03-11T00:00:00+00:00");
+        assert_eq!(interval_anchor(RotationInterval::Sliding, Some(Duration::days(1)), &now), now);
+    }
+
+    #[test]
+    fn prune_by_age_removes_old_files_and_empty_month_folders() {
+        let dir = tempdir().unwrap();
+        let options = RotationOptions {
+            path: dir.path().into(),
+            ..Default::default()
+        };
+        let old_month = dir.path().join("2020-01");
+        let current_month = dir.path().join("2020-02");
+        fs::create_dir_all(&old_month).unwrap();
+        fs::create_dir_all(&current_month).unwrap();
+
+        let now = Utc::now();
+        let old_time = std::time::SystemTime::now() - std::time::Duration::from_secs(40 * 24 * 3600);
+        let old_file = old_month.join("output-2020-01-01-000.ansi");
+        File::create(&old_file).unwrap().set_modified(old_time).unwrap();
+        let recent_file = current_month.join("output-2020-02-01-000.ansi.gz");
+        File::create(&recent_file).unwrap();
+        let other_file = current_month.join("notes.txt");
+        File::create(&other_file).unwrap().set_modified(old_time).unwrap();
+        let unrelated_dir = dir.path().join("archive");
+        fs::create_dir_all(&unrelated_dir).unwrap();
+        let unrelated_log = unrelated_dir.join("output-2020-01-01-000.ansi");
+        File::create(&unrelated_log).unwrap().set_modified(old_time).unwrap();
+        let empty_dir = dir.path().join("empty");
+        fs::create_dir_all(&empty_dir).unwrap();
+
+        prune_by_age(&options, Duration::days(30), &now, &[]).unwrap();
+        assert!(!old_file.exists());
+        assert!(!old_month.exists());
+        assert!(recent_file.exists());
+        assert!(other_file.exists());
+        assert!(unrelated_log.exists());
+        assert!(empty_dir.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn latest_symlink_follows_current_file() {
+        let dir = tempdir().unwrap();
+        let options = RotationOptions {
+            path: dir.path().into(),
+            latest_symlink: Some("output-latest.ansi".into()),
+            ..Default::default()
+        };
+        let writer = RotatingFileWriter::new(options.clone()).unwrap();
+        writer.write("first\n").unwrap();
+        let link = dir.path().join("output-latest.ansi");
+        assert_eq!(fs::read_to_string(&link).unwrap(), "first\n");
+
+        let now = Utc::now();
+        writer.rotate(&mut writer.state.lock(), &now).unwrap();
+        writer.write("second\n").unwrap();
+        assert_eq!(fs::read_to_string(&link).unwrap(), "second\n");
+        assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn latest_symlink_failure_does_not_stop_logging() {
+        let dir = tempdir().unwrap();
+        let blocker = dir.path().join("output-latest.ansi");
+        fs::create_dir_all(blocker.join("occupied")).unwrap();
+        let options = RotationOptions {
+            path: dir.path().into(),
+            latest_symlink: Some("output-latest.ansi".into()),
+            ..Default::default()
+        };
+        let writer = RotatingFileWriter::new(options.clone()).unwrap();
+        writer.rotate(&mut writer.state.lock(), &Utc::now()).unwrap();
+        writer.write("still logging\n").unwrap();
+        let path = writer.state.lock().current_path.clone();
+        assert_eq!(fs::read_to_string(path).unwrap(), "still logging\n");
+        assert!(blocker.is_dir());
+    }
+
+    #[test]
+    fn parse_size_accepts_fractions_and_terabytes() {
+        assert_eq!(parse_size("1.5M"), Ok(1_572_864));
+        assert_eq!(parse_size("0.5G"), Ok(536_870_912));
+        assert_eq!(parse_size("512"), Ok(512));
+        assert_eq!(parse_size("2T"), Ok(2 * (1 << 40)));
+        assert!(parse_size("abc").is_err());
+        assert!(parse_size("-1M").is_err());
+    }
+
+    #[test]
+    fn invalid_size_fails_writer_creation() {
+        let dir = tempdir().unwrap();
+        let options = RotationOptions {
+            path: dir.path().into(),
+            size: Some("abc".into()),
+            ..Default::default()
+        };
+        let err = RotatingFileWriter::new(options).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(err.to_string().contains("abc"));
+    }
+
+    #[test]
+    fn invalid_max_age_fails_writer_creation() {
+        let dir = tempdir().unwrap();
+        let options = RotationOptions {
+            path: dir.path().into(),
+            max_age: Some("14days".into()),
+            ..Default::default()
+        };
+        let err = RotatingFileWriter::new(options).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(err.to_string().contains("14days"));
+    }
+
+    #[test]
+    fn path_traversal_in_prefix_is_rejected() {
+        let dir = tempdir().unwrap();
+        let options = RotationOptions {
+            path: dir.path().into(),
+            filename_prefix: "../evil".into(),
+            ..Default::default()
+        };
+        let err = RotatingFileWriter::new(options).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(err.to_string().contains("../evil"));
+        assert!(fs::read_dir(dir.path()).unwrap().next().is_none());
+    }
+
+    #[test]
+    fn leading_dot_in_extension_is_dropped() {
+        let dir = tempdir().unwrap();
+        let options = RotationOptions {
+            path: dir.path().into(),
+            extension: ".ansi".into(),
+            ..Default::default()
+        };
+        let writer = RotatingFileWriter::new(options).unwrap();
+        assert_eq!(writer.options().extension, "ansi");
+        let path = writer.state.lock().current_path.clone();
+        let name = path.file_name().unwrap().to_string_lossy().into_owned();
+        assert!(name.ends_with("-000.ansi"), "{name}");
+    }
+
+    #[test]
+    fn sync_mode_none_buffers_until_flush() {
+        let dir = tempdir().unwrap();
+        let options = RotationOptions {
+            path: dir.path().into(),
+            sync_mode: SyncMode::None,
+            ..Default::default()
+        };
+        let writer = RotatingFileWriter::new(options).unwrap();
+        writer.write("pending\n").unwrap();
+        let path = writer.state.lock().current_path.clone();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "");
+        writer.flush().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "pending\n");
+
+        let options = RotationOptions {
+            path: dir.path().join("audit"),
+            sync_mode: SyncMode::FsyncEveryWrite,
+            buffered: true,
+            ..Default::default()
+        };
+        let writer = RotatingFileWriter::new(options).unwrap();
+        writer.write("durable\n").unwrap();
+        let path = writer.state.lock().current_path.clone();
+        assert_eq!(fs::read_to_string(path).unwrap(), "durable\n");
+    }
+
+    #[test]
+    fn buffered_only_drops_the_per_line_flush_of_flushing_modes() {
+        let dir = tempdir().unwrap();
+        let cases = [
+            (SyncMode::None, false, false),
+            (SyncMode::None, true, false),
+            (SyncMode::Flush, false, true),
+            (SyncMode::Flush, true, false),
+            (SyncMode::FsyncOnRotate, false, true),
+            (SyncMode::FsyncOnRotate, true, false),
+            (SyncMode::FsyncEveryWrite, false, true),
+            (SyncMode::FsyncEveryWrite, true, true),
+        ];
+        for (index, (sync_mode, buffered, on_disk)) in cases.into_iter().enumerate() {
+            let options = RotationOptions {
+                path: dir.path().join(index.to_string()),
+                sync_mode,
+                buffered,
+                ..Default::default()
+            };
+            let writer = RotatingFileWriter::new(options).unwrap();
+            writer.write("line\n").unwrap();
+            let path = writer.state.lock().current_path.clone();
+            let expected = if on_disk { "line\n" } else { "" };
+            assert_eq!(fs::read_to_string(&path).unwrap(), expected, "{sync_mode:?} with buffered: {buffered}");
+
+            // Synced writes (levels in `sync_on_levels`) go through whatever the mode.
+            writer.write_synced("synced\n").unwrap();
+            assert_eq!(fs::read_to_string(&path).unwrap(), "line\nsynced\n", "{sync_mode:?} with buffered: {buffered}");
+        }
+    }
 }
@@ -0,0 +1,210 @@
+//! Output sinks for formatted log lines.
+//!
+//! By default a [`Logger`](crate::Logger) writes to stdout (plus the rotating file when
+//! enabled), or to the browser console in a `wasm` build without `native`. Supplying a [`LogSink`] through [`LoggerOptions::sink`](crate::LoggerOptions)
+//! or [`Logger::with_writer`](crate::Logger::with_writer) replaces the stdout output.
+
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use serde_json::Value;
+
+use crate::logger::Level;
+
+/// Destination for formatted log lines. `output` is exactly what would have been written
+/// to stdout, including the trailing newline (or the pretty-print separators).
+pub trait LogSink: Send + Sync {
+    fn write(&self, level: Level, output: &str) -> io::Result<()>;
+
+    fn flush(&self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl fmt::Debug for dyn LogSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("LogSink")
+    }
+}
+
+/// Adapts any [`io::Write`] into a [`LogSink`].
+pub struct WriterSink<W: Write + Send> {
+    writer: Mutex<W>,
+}
+
+impl<W: Write + Send> WriterSink<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer: Mutex::new(writer) }
+    }
+}
+
+impl<W: Write + Send> LogSink for WriterSink<W> {
+    fn write(&self, _level: Level, output: &str) -> io::Result<()> {
+        let mut writer = self.writer.lock();
+        writer.write_all(output.as_bytes())?;
+        writer.flush()
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        self.writer.lock().flush()
+    }
+}
+
+/// In-memory sink that collects emitted bytes, mainly for asserting on output in tests.
+/// Clones share the same buffer.
+#[derive(Debug, Clone, Default)]
+pub struct MemorySink {
+    buffer: Arc<Mutex<Vec<u8>>>,
+}
+
+impl MemorySink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a copy of everything written so far.
+    pub fn bytes(&self) -> Vec<u8> {
+        self.buffer.lock().clone()
+    }
+
+    /// Returns everything written so far as (lossy) UTF-8.
+    pub fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.buffer.lock()).into_owned()
+    }
+
+    /// Returns the non-empty lines written so far.
+    pub fn lines(&self) -> Vec<String> {
+        self.contents().lines().filter(|line| !line.is_empty()).map(str::to_string).collect()
+    }
+
+    pub fn clear(&self) {
+        self.buffer.lock().clear();
+    }
+}
+
+impl LogSink for MemorySink {
+    fn write(&self, _level: Level, output: &str) -> io::Result<()> {
+        self.buffer.lock().extend_from_slice(output.as_bytes());
+        Ok(())
+    }
+}
+
+/// Sink that parses each JSON line back into a [`Value`] and collects it, so tests can assert
+/// on fields directly. Expects [`LogFormat::Json`](crate::LogFormat) output; lines that are not
+/// valid JSON are kept as strings. See [`Logger::testing`](crate::Logger::testing).
+#[derive(Debug, Clone, Default)]
+pub struct CaptureSink {
+    values: Arc<Mutex<Vec<Value>>>,
+}
+
+impl CaptureSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The shared list of captured payloads.
+    pub fn values(&self) -> Arc<Mutex<Vec<Value>>> {
+        self.values.clone()
+    }
+}
+
+impl LogSink for CaptureSink {
+    fn write(&self, _level: Level, output: &str) -> io::Result<()> {
+        let mut values = self.values.lock();
+        for line in output.lines().filter(|line| !line.is_empty()) {
+            values.push(serde_json::from_str(line).unwrap_or_else(|_| Value::String(line.to_string())));
+        }
+        Ok(())
+    }
+}
+
+/// Writes each line to the browser console, using `console.error`, `console.warn`,
+/// `console.info` or `console.debug` by level. The trailing newline is trimmed.
+#[cfg(feature = "wasm")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ConsoleSink;
+
+#[cfg(feature = "wasm")]
+impl LogSink for ConsoleSink {
+    fn write(&self, level: Level, output: &str) -> io::Result<()> {
+        use web_sys::{console, wasm_bindgen::JsValue};
+
+        let line = JsValue::from_str(output.trim_end_matches('\n'));
+        match level {
+            Level::Error | Level::Fatal => console::error_1(&line),
+            Level::Warn => console::warn_1(&line),
+            Level::Info => console::info_1(&line),
+            Level::Debug | Level::Trace => console::debug_1(&line),
+        }
+        Ok(())
+    }
+}
+
+/// Passes each line to a JavaScript function as `(level, line)`, e.g. a closure handed over
+/// from a Leptos or Yew app that forwards logs to its own telemetry. Only available when
+/// compiling for `wasm32`.
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub struct JsCallbackSink {
+    callback: web_sys::js_sys::Function,
+}
+
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+impl JsCallbackSink {
+    pub fn new(callback: web_sys::js_sys::Function) -> Self {
+        Self { callback }
+    }
+}
+
+// SAFETY: JS values can only be used from the thread that created them. Without the
+// `atomics` target feature, wasm32 has a single thread, so the sink is never shared.
+#[cfg(all(feature = "wasm", target_arch = "wasm32", not(target_feature = "atomics")))]
+unsafe impl Send for JsCallbackSink {}
+#[cfg(all(feature = "wasm", target_arch = "wasm32", not(target_feature = "atomics")))]
+unsafe impl Sync for JsCallbackSink {}
+
+#[cfg(all(feature = "wasm", target_arch = "wasm32", not(target_feature = "atomics")))]
+impl LogSink for JsCallbackSink {
+    fn write(&self, level: Level, output: &str) -> io::Result<()> {
+        use web_sys::wasm_bindgen::JsValue;
+
+        let line = JsValue::from_str(output.trim_end_matches('\n'));
+        self.callback
+            .call2(&JsValue::NULL, &JsValue::from_str(level.as_str()), &line)
+            .map(|_| ())
+            .map_err(|error| io::Error::other(format!("log callback threw: {error:?}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writer_sink_writes_through() {
+        let sink = WriterSink::new(Vec::new());
+        sink.write(Level::Info, "line\n").unwrap();
+        assert_eq!(sink.writer.lock().as_slice(), b"line\n");
+    }
+
+    #[test]
+    fn memory_sink_clones_share_buffer() {
+        let sink = MemorySink::new();
+        let clone = sink.clone();
+        clone.write(Level::Info, "one\n\ntwo\n").unwrap();
+        assert_eq!(sink.lines(), vec!["one".to_string(), "two".to_string()]);
+        sink.clear();
+        assert!(clone.contents().is_empty());
+    }
+
+    #[test]
+    fn capture_sink_parses_json_lines() {
+        let sink = CaptureSink::new();
+        sink.write(Level::Info, "{\"msg\":\"one\"}\nnot json\n").unwrap();
+        let values = sink.values();
+        let values = values.lock();
+        assert_eq!(values[0]["msg"], "one");
+        assert_eq!(values[1], "not json");
+    }
+}
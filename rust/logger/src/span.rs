@@ -0,0 +1,87 @@
+//! Timing guards created by [`Logger::span`](crate::Logger::span).
+
+#[cfg(feature = "native")]
+use std::time::Instant;
+
+use serde_json::{Map, Value};
+
+use crate::logger::{Level, LogArgs, Logger};
+
+/// `std::time::Instant` panics on `wasm32-unknown-unknown`, so without `native` spans are
+/// timed with the wall clock, which `chrono` reads from `Date.now()` in the browser.
+#[cfg(not(feature = "native"))]
+struct Instant(chrono::DateTime<chrono::Utc>);
+
+#[cfg(not(feature = "native"))]
+impl Instant {
+    fn now() -> Self {
+        Self(chrono::Utc::now())
+    }
+
+    fn elapsed(&self) -> std::time::Duration {
+        (chrono::Utc::now() - self.0).to_std().unwrap_or_default()
+    }
+}
+
+/// Measures the time until it is dropped and then logs one `info` line with `namespace`
+/// set to the span name and `duration` set to the elapsed milliseconds. If the span is
+/// dropped while the thread is unwinding from a panic, the line is logged at `error` instead.
+#[must_use = "a span logs when dropped; binding it to `_` drops it immediately"]
+pub struct Span<'a> {
+    logger: &'a Logger,
+    name: String,
+    start: Instant,
+    context: Map<String, Value>,
+    cancelled: bool,
+}
+
+impl<'a> Span<'a> {
+    pub(crate) fn new(logger: &'a Logger, name: &str) -> Self {
+        Self {
+            logger,
+            name: name.to_string(),
+            start: Instant::now(),
+            context: Map::new(),
+            cancelled: false,
+        }
+    }
+
+    /// Attaches `key` to the `context` of the final log line.
+    pub fn record<V: Into<Value>>(&mut self, key: &str, value: V) {
+        self.context.insert(key.to_string(), value.into());
+    }
+
+    /// Suppresses the final log line, e.g. on an early return that isn't worth timing.
+    pub fn cancel(&mut self) {
+        self.cancelled = true;
+    }
+
+    /// Milliseconds since the span was created.
+    pub fn elapsed_ms(&self) -> f64 {
+        self.start.elapsed().as_secs_f64() * 1000.0
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl Drop for Span<'_> {
+    fn drop(&mut self) {
+        if self.cancelled {
+            return;
+        }
+
+        let mut args = LogArgs::new();
+        args.push(self.name.clone());
+        if !self.context.is_empty() {
+            args.push(Value::Object(std::mem::take(&mut self.context)));
+        }
+
+        let mut fields = Map::new();
+        fields.insert("namespace".into(), Value::String(self.name.clone()));
+        fields.insert("duration".into(), Value::from(self.elapsed_ms()));
+        let level = if std::thread::panicking() { Level::Error } else { Level::Info };
+        let _ = self.logger.log_with_fields(level, args, fields);
+    }
+}
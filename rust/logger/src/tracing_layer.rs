@@ -0,0 +1,227 @@
+//! A [`tracing_subscriber::Layer`] that writes `tracing` events through a [`Logger`].
+
+use std::cell::RefCell;
+use std::fmt;
+
+use serde_json::{Map, Value};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+use crate::context::{push_scope, ContextKey, ScopeGuard};
+use crate::logger::{Level, LogArgs, Logger};
+
+thread_local! {
+    /// Scopes pushed for the spans entered on this thread, innermost last.
+    static ENTERED: RefCell<Vec<(Id, ScopeGuard)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Converts `tracing` events into log lines. The event's `message` becomes `msg` and its
+/// other fields, together with the fields of every enclosing span, go into `context`;
+/// the innermost span's name becomes `namespace`.
+///
+/// Entering a span also pushes its fields as a thread-local scope (see
+/// [`push_scope`](crate::push_scope)), so plain `Logger` calls made inside the span carry
+/// them too. Fields recorded later with `Span::record` are picked up by both.
+pub struct SmooLayer {
+    logger: Logger,
+}
+
+impl SmooLayer {
+    pub fn new(logger: Logger) -> Self {
+        Self { logger }
+    }
+}
+
+/// Fields recorded on a span so far, stored in the span's extensions.
+struct SpanFields(Map<String, Value>);
+
+#[derive(Default)]
+struct FieldVisitor {
+    message: Option<String>,
+    fields: Map<String, Value>,
+}
+
+impl FieldVisitor {
+    fn insert(&mut self, field: &Field, value: Value) {
+        if field.name() == "message" {
+            self.message = Some(match value {
+                Value::String(message) => message,
+                other => other.to_string(),
+            });
+        } else {
+            self.fields.insert(field.name().to_string(), value);
+        }
+    }
+}
+
+impl Visit for FieldVisitor {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.insert(field, Value::String(format!("{value:?}")));
+    }
+}
+
+fn span_scope(name: &str, fields: &Map<String, Value>) -> Map<String, Value> {
+    let mut scope = Map::new();
+    scope.insert(ContextKey::Namespace.as_str().to_string(), Value::String(name.to_string()));
+    scope.insert(ContextKey::Context.as_str().to_string(), Value::Object(fields.clone()));
+    scope
+}
+
+fn from_tracing_level(level: &tracing::Level) -> Level {
+    match *level {
+        tracing::Level::ERROR => Level::Error,
+        tracing::Level::WARN => Level::Warn,
+        tracing::Level::INFO => Level::Info,
+        tracing::Level::DEBUG => Level::Debug,
+        tracing::Level::TRACE => Level::Trace,
+    }
+}
+
+impl<S> Layer<S> for SmooLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut visitor = FieldVisitor::default();
+        attrs.record(&mut visitor);
+        span.extensions_mut().insert(SpanFields(visitor.fields));
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut visitor = FieldVisitor::default();
+        values.record(&mut visitor);
+        if let Some(SpanFields(fields)) = span.extensions_mut().get_mut::<SpanFields>() {
+            fields.extend(visitor.fields.clone());
+        }
+        ENTERED.with(|entered| {
+            for (_, guard) in entered.borrow().iter().filter(|(entered_id, _)| entered_id == id) {
+                guard.merge(&span_scope(span.name(), &visitor.fields));
+            }
+        });
+    }
+
+    fn on_enter(&self, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let scope = match span.extensions().get::<SpanFields>() {
+            Some(SpanFields(fields)) => span_scope(span.name(), fields),
+            None => span_scope(span.name(), &Map::new()),
+        };
+        let guard = push_scope(Value::Object(scope));
+        ENTERED.with(|entered| entered.borrow_mut().push((id.clone(), guard)));
+    }
+
+    fn on_exit(&self, id: &Id, _ctx: Context<'_, S>) {
+        ENTERED.with(|entered| {
+            let mut entered = entered.borrow_mut();
+            if let Some(position) = entered.iter().rposition(|(entered_id, _)| entered_id == id) {
+                // Dropping the guard also drops any scope pushed after it.
+                entered.truncate(position);
+            }
+        });
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let level = from_tracing_level(event.metadata().level());
+        if !self.logger.is_enabled(level) {
+            return;
+        }
+
+        let mut context = Map::new();
+        let mut namespace = None;
+        if let Some(scope) = ctx.event_scope(event) {
+            for span in scope.from_root() {
+                if let Some(SpanFields(fields)) = span.extensions().get::<SpanFields>() {
+                    context.extend(fields.clone());
+                }
+                namespace = Some(span.name());
+            }
+        }
+
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+        context.extend(visitor.fields);
+
+        let mut args = LogArgs::new();
+        if let Some(message) = visitor.message {
+            args.push(message);
+        }
+        if !context.is_empty() {
+            args.push(Value::Object(context));
+        }
+        let mut fields = Map::new();
+        if let Some(namespace) = namespace {
+            fields.insert(ContextKey::Namespace.as_str().to_string(), Value::String(namespace.to_string()));
+        }
+        let _ = self.logger.log_with_fields(level, args, fields);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logger::LoggerOptions;
+    use crate::sink::MemorySink;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn events_carry_span_fields_including_recorded_ones() {
+        let _guard = crate::TEST_GLOBAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let sink = MemorySink::new();
+        let logger = crate::memory_logger(&sink, LoggerOptions::default());
+        logger.reset_context();
+        let subscriber = tracing_subscriber::registry().with(SmooLayer::new(crate::memory_logger(&sink, LoggerOptions::default())));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("checkout", order = 42, user = tracing::field::Empty);
+            let _entered = span.enter();
+            span.record("user", "alice");
+            tracing::warn!(attempt = 2, "payment retry");
+            logger.info("plain call").unwrap();
+        });
+        logger.info("after span").unwrap();
+
+        let lines: Vec<Value> = sink.lines().iter().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0]["msg"], "payment retry");
+        assert_eq!(lines[0]["LogLevel"], "warn");
+        assert_eq!(lines[0]["namespace"], "checkout");
+        assert_eq!(lines[0]["context"]["order"], 42);
+        assert_eq!(lines[0]["context"]["user"], "alice");
+        assert_eq!(lines[0]["context"]["attempt"], 2);
+        assert_eq!(lines[1]["namespace"], "checkout");
+        assert_eq!(lines[1]["context"]["user"], "alice");
+        assert!(lines[2].get("namespace").is_none());
+    }
+}